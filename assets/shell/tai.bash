@@ -0,0 +1,27 @@
+# tai shell integration for bash
+# 用法：在 ~/.bashrc 中加入 eval "$(tai init bash)"
+# 按 {key_label} 把当前输入行交给 tai go，生成的命令会替换输入行，回车执行后进入历史记录
+
+__tai_go() {
+    local input="$READLINE_LINE"
+    [[ -z "${input// }" ]] && return
+    local cmd
+    cmd="$(tai go --print --shell bash -- "$input" </dev/tty)" || return
+    if [[ -n "$cmd" ]]; then
+        READLINE_LINE="$cmd"
+        READLINE_POINT=${#cmd}
+    fi
+}
+
+bind -x '"{bash_key}": __tai_go'
+
+# 记录上一条命令及其退出码，供 tai fix 使用
+__tai_precmd() {
+    local status=$?
+    export TAI_LAST_STATUS=$status
+    export TAI_LAST_COMMAND="$(HISTTIMEFORMAT= builtin history 1 | sed 's/^ *[0-9]* *//')"
+}
+
+if [[ ";${PROMPT_COMMAND[*]};" != *";__tai_precmd;"* ]]; then
+    PROMPT_COMMAND="__tai_precmd${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+fi
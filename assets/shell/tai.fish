@@ -0,0 +1,23 @@
+# tai shell integration for fish
+# 用法：在 ~/.config/fish/config.fish 中加入 tai init fish | source
+# 按 {key_label} 把当前输入行交给 tai go，生成的命令会替换输入行，回车执行后进入历史记录
+
+function __tai_go
+    set -l input (commandline)
+    if test -z (string trim -- "$input")
+        return
+    end
+    set -l cmd (tai go --print --shell fish -- "$input" </dev/tty | string collect)
+    if test $status -eq 0 -a -n "$cmd"
+        commandline -r -- $cmd
+    end
+    commandline -f repaint
+end
+
+bind {fish_key} __tai_go
+
+# 记录上一条命令及其退出码，供 tai fix 使用
+function __tai_postexec --on-event fish_postexec
+    set -gx TAI_LAST_STATUS $status
+    set -gx TAI_LAST_COMMAND $argv[1]
+end
@@ -0,0 +1,29 @@
+# tai shell integration for PowerShell (PSReadLine)
+# 用法：在 $PROFILE 中加入 tai init powershell | Out-String | Invoke-Expression
+# 按 {key_label} 把当前输入行交给 tai go，生成的命令会替换输入行，回车执行后进入历史记录
+
+Set-PSReadLineKeyHandler -Chord '{ps_key}' -BriefDescription 'tai go' -ScriptBlock {
+    $line = $null
+    $cursor = $null
+    [Microsoft.PowerShell.PSConsoleReadLine]::GetBufferState([ref]$line, [ref]$cursor)
+    if ([string]::IsNullOrWhiteSpace($line)) { return }
+    $cmd = tai go --print --shell powershell -- $line
+    if ($LASTEXITCODE -eq 0 -and $cmd) {
+        [Microsoft.PowerShell.PSConsoleReadLine]::Replace(0, $line.Length, ($cmd -join "`n"))
+    }
+    [Microsoft.PowerShell.PSConsoleReadLine]::InvokePrompt()
+}
+
+# 记录上一条命令及其退出码，供 tai fix 使用
+$global:__TaiOriginalPrompt = $function:prompt
+function global:prompt {
+    $succeeded = $?
+    $code = $global:LASTEXITCODE
+    $last = Get-History -Count 1
+    if ($last) {
+        $env:TAI_LAST_COMMAND = $last.CommandLine
+        $env:TAI_LAST_STATUS = if ($succeeded) { 0 } elseif ($code) { $code } else { 1 }
+    }
+    $global:LASTEXITCODE = $code
+    & $global:__TaiOriginalPrompt
+}
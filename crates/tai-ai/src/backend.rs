@@ -0,0 +1,199 @@
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use tai_core::{TaiError, TaiResult};
+use tracing::{debug, warn};
+
+use crate::config::ProviderConfig;
+use crate::{ChatTool, StreamChunk};
+
+/// 流式响应：依次产出 reasoning / answer / 工具调用块，出错时产出 Err 并结束
+pub type ChunkStream = BoxStream<'static, TaiResult<StreamChunk>>;
+
+/// 聊天后端抽象。每个 provider 类型实现一次，`chat` / `chat_stream` 不再按厂商 match。
+///
+/// 实现方负责把底层错误转换为 `TaiError`（认证失败应返回 `TaiError::AuthError`，
+/// 以便上层触发 API Key 重新输入流程）。
+pub trait ChatBackend: Send + Sync {
+    /// 一次性请求，返回完整回答
+    fn chat<'a>(&'a self, model: &'a str, prompt: &'a str) -> BoxFuture<'a, TaiResult<String>>;
+
+    /// 流式请求，返回 reasoning / answer 块组成的流
+    fn chat_stream<'a>(
+        &'a self,
+        model: &'a str,
+        prompt: &'a str,
+    ) -> BoxFuture<'a, TaiResult<ChunkStream>>;
+
+    /// 带工具的流式请求：模型可以多轮调用 tools，调用和结果以 ToolCall / ToolResult 块产出
+    ///
+    /// 默认实现忽略工具，退化为 `chat_stream`
+    fn chat_stream_with_tools<'a>(
+        &'a self,
+        model: &'a str,
+        prompt: &'a str,
+        tools: &'a [ChatTool],
+    ) -> BoxFuture<'a, TaiResult<ChunkStream>> {
+        if !tools.is_empty() {
+            warn!("该后端不支持工具调用，忽略 {} 个工具", tools.len());
+        }
+        self.chat_stream(model, prompt)
+    }
+
+    /// 列出可用模型，默认返回 providers.json 中配置的 model_names
+    fn list_models(&self) -> BoxFuture<'_, TaiResult<Vec<String>>>;
+}
+
+/// 根据 provider 配置构造后端实例
+pub type BackendFactory =
+    Arc<dyn Fn(&ProviderConfig) -> TaiResult<Arc<dyn ChatBackend>> + Send + Sync>;
+
+static FACTORIES: OnceLock<RwLock<HashMap<String, BackendFactory>>> = OnceLock::new();
+static BACKENDS: OnceLock<RwLock<HashMap<String, Arc<dyn ChatBackend>>>> = OnceLock::new();
+
+fn factories() -> &'static RwLock<HashMap<String, BackendFactory>> {
+    FACTORIES.get_or_init(|| RwLock::new(crate::provider::builtin_factories()))
+}
+
+fn backends() -> &'static RwLock<HashMap<String, Arc<dyn ChatBackend>>> {
+    BACKENDS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// 注册（或覆盖）某个 provider 类型的后端工厂，kind 不区分大小写。
+///
+/// 下游 crate 可在启动时调用，例如注册内部网关：
+/// `register_backend("gateway", |cfg| Ok(Arc::new(GatewayBackend::new(cfg))))`
+pub fn register_backend<F>(kind: &str, factory: F)
+where
+    F: Fn(&ProviderConfig) -> TaiResult<Arc<dyn ChatBackend>> + Send + Sync + 'static,
+{
+    let kind = kind.to_lowercase();
+    debug!("注册聊天后端: {}", kind);
+    factories()
+        .write()
+        .expect("FACTORIES write lock poisoned")
+        .insert(kind.clone(), Arc::new(factory));
+    // 同类型已缓存的实例作废，下次请求时用新工厂重建
+    backends()
+        .write()
+        .expect("BACKENDS write lock poisoned")
+        .retain(|key, _| !key.starts_with(&format!("{}|", kind)));
+}
+
+/// 已注册的 provider 类型（排序后）
+pub fn registered_kinds() -> Vec<String> {
+    let mut kinds: Vec<String> = factories()
+        .read()
+        .expect("FACTORIES read lock poisoned")
+        .keys()
+        .cloned()
+        .collect();
+    kinds.sort();
+    kinds
+}
+
+/// 按 (kind, base_url, api_key) 复用后端实例，不存在时用注册的工厂惰性创建
+///
+/// api_key_env / api_key_cmd 在此时才解析，工厂收到的配置中 api_key 已是实际值。
+pub fn get_backend(config: &ProviderConfig) -> TaiResult<Arc<dyn ChatBackend>> {
+    let config = &config.with_resolved_api_key()?;
+    let kind = config.kind();
+    let key = format!("{}|{}|{}", kind, config.base_url, config.api_key);
+
+    {
+        let read = backends().read().expect("BACKENDS read lock poisoned");
+        if let Some(backend) = read.get(&key) {
+            return Ok(backend.clone());
+        }
+    }
+
+    let factory = factories()
+        .read()
+        .expect("FACTORIES read lock poisoned")
+        .get(&kind)
+        .cloned()
+        .ok_or_else(|| {
+            TaiError::ConfigError(format!(
+                "不支持的 provider 类型 `{}`（{}），可用类型: {}",
+                kind,
+                config.provider,
+                registered_kinds().join(", ")
+            ))
+        })?;
+
+    let backend = factory(config)?;
+    debug!("创建聊天后端: {} ({})", config.provider, kind);
+    backends()
+        .write()
+        .expect("BACKENDS write lock poisoned")
+        .insert(key, backend.clone());
+    Ok(backend)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    struct EchoBackend;
+
+    impl ChatBackend for EchoBackend {
+        fn chat<'a>(&'a self, _model: &'a str, prompt: &'a str) -> BoxFuture<'a, TaiResult<String>> {
+            Box::pin(async move { Ok(prompt.to_string()) })
+        }
+
+        fn chat_stream<'a>(
+            &'a self,
+            _model: &'a str,
+            prompt: &'a str,
+        ) -> BoxFuture<'a, TaiResult<ChunkStream>> {
+            let chunks = vec![Ok(StreamChunk::Answer(prompt.to_string()))];
+            Box::pin(async move { Ok(futures::stream::iter(chunks).boxed()) })
+        }
+
+        fn list_models(&self) -> BoxFuture<'_, TaiResult<Vec<String>>> {
+            Box::pin(async { Ok(vec!["echo-1".to_string()]) })
+        }
+    }
+
+    fn gateway_config() -> ProviderConfig {
+        ProviderConfig {
+            provider: "corp".to_string(),
+            kind: Some("Echo-Gateway".to_string()),
+            base_url: "http://localhost".to_string(),
+            api_key: "k".to_string(),
+            api_key_env: None,
+            api_key_cmd: None,
+            model_names: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_backend_is_used() {
+        register_backend("echo-gateway", |_| Ok(Arc::new(EchoBackend)));
+        assert!(registered_kinds().contains(&"openai".to_string()));
+
+        let config = gateway_config();
+        assert_eq!(crate::chat(&config, "m", "hi").await.unwrap(), "hi");
+        assert_eq!(crate::list_models(&config).await.unwrap(), vec!["echo-1"]);
+
+        let mut answer = String::new();
+        crate::chat_stream(&config, "m", "streamed", |chunk| {
+            if let StreamChunk::Answer(text) = chunk {
+                answer.push_str(&text);
+            }
+            Ok(())
+        })
+        .await
+        .unwrap();
+        assert_eq!(answer, "streamed");
+    }
+
+    #[test]
+    fn test_unknown_kind_is_config_error() {
+        let mut config = gateway_config();
+        config.kind = Some("nope".to_string());
+        assert!(matches!(get_backend(&config), Err(TaiError::ConfigError(_))));
+    }
+}
@@ -1,17 +1,70 @@
 use dirs_next::home_dir;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tai_core::schema::{
+    array_element_spans, load_versioned, object_version, parse_json, set_version, suggest_key,
+    Diagnostic, Migration, Schema, Severity,
+};
 use tai_core::{TaiError, TaiResult};
 use tracing::{debug, error, warn};
 
+use crate::credential::{resolve_api_key, ApiKeySource};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProviderConfig {
     pub provider: String,
+    /// 后端类型（对应 `register_backend` 注册的 kind），缺省时使用 provider 名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
     pub base_url: String,
+    #[serde(default)]
     pub api_key: String,
+    /// 从环境变量读取 API Key，如 "OPENAI_API_KEY"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+    /// 执行命令读取 API Key（取 stdout 首行），如 "pass show openai"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_cmd: Option<String>,
     pub model_names: Vec<String>,
 }
 
+impl ProviderConfig {
+    /// 用于查找聊天后端的类型标识（小写）
+    pub fn kind(&self) -> String {
+        self.kind
+            .as_deref()
+            .unwrap_or(&self.provider)
+            .to_lowercase()
+    }
+
+    /// API Key 的来源（不触发解析）
+    pub fn api_key_source(&self) -> ApiKeySource {
+        if let Some(var) = self.api_key_env.as_ref().filter(|v| !v.is_empty()) {
+            ApiKeySource::Env(var.clone())
+        } else if let Some(cmd) = self.api_key_cmd.as_ref().filter(|c| !c.is_empty()) {
+            ApiKeySource::Command(cmd.clone())
+        } else {
+            ApiKeySource::Plain
+        }
+    }
+
+    /// 是否配置了 API Key（环境变量 / 命令来源视为已配置，实际值在请求时解析）
+    pub fn has_api_key(&self) -> bool {
+        self.api_key_source() != ApiKeySource::Plain || !self.api_key.is_empty()
+    }
+
+    /// 返回 api_key 已解析为实际值的副本
+    pub fn with_resolved_api_key(&self) -> TaiResult<ProviderConfig> {
+        let mut resolved = self.clone();
+        resolved.api_key = resolve_api_key(self)?;
+        Ok(resolved)
+    }
+}
+
 /// 当前激活的提供商和模型，持久化到 ~/.tai/state.json
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ActiveModel {
@@ -25,16 +78,48 @@ fn tai_dir() -> PathBuf {
         .join(".tai")
 }
 
-fn providers_path() -> PathBuf {
+pub fn providers_path() -> PathBuf {
     tai_dir().join("providers.json")
 }
 
-fn state_path() -> PathBuf {
+pub fn state_path() -> PathBuf {
     tai_dir().join("state.json")
 }
 
 const DEFAULT_PROVIDERS: &str = include_str!("../../../assets/providers.json");
 
+/// providers.json 的 schema 版本
+pub const PROVIDERS_VERSION: u32 = 2;
+/// state.json 的 schema 版本
+pub const STATE_VERSION: u32 = 2;
+
+/// v1: 顶层为 provider 数组；v2: `{ "version": 2, "providers": [...] }`
+static PROVIDERS_SCHEMA: Schema = Schema {
+    current: PROVIDERS_VERSION,
+    detect: |doc| if doc.is_array() { 1 } else { object_version(doc) },
+    migrations: &[Migration {
+        from: 1,
+        apply: |doc| Ok(json!({ "version": 2, "providers": doc })),
+    }],
+};
+
+/// v1: 无 version 字段；v2: 增加 version 字段
+static STATE_SCHEMA: Schema = Schema {
+    current: STATE_VERSION,
+    detect: object_version,
+    migrations: &[Migration { from: 1, apply: |doc| set_version(doc, 2) }],
+};
+
+const PROVIDER_KEYS: &[&str] = &[
+    "provider",
+    "kind",
+    "base_url",
+    "api_key",
+    "api_key_env",
+    "api_key_cmd",
+    "model_names",
+];
+
 pub fn load_providers() -> TaiResult<Vec<ProviderConfig>> {
     let path = providers_path();
     debug!("加载 provider 配置: {:?}", path);
@@ -53,12 +138,16 @@ pub fn load_providers() -> TaiResult<Vec<ProviderConfig>> {
         debug!("默认 provider 配置已写入: {:?}", path);
     }
 
-    let content = fs::read_to_string(&path).map_err(|e| {
-        error!("读取 provider 配置失败: {}", e);
-        TaiError::FileError(format!("无法读取 {:?}: {}", path, e))
-    })?;
-
-    let providers: Vec<ProviderConfig> = serde_json::from_str(&content)?;
+    let doc = load_versioned(&path, &PROVIDERS_SCHEMA, true)?;
+    let providers: Vec<ProviderConfig> =
+        serde_json::from_value(doc.get("providers").cloned().unwrap_or(Value::Null)).map_err(|e| {
+            error!("解析 provider 配置失败: {}", e);
+            TaiError::ConfigError(format!(
+                "{} 格式错误: {}\n  运行 `tai config validate` 查看详情",
+                path.display(),
+                e
+            ))
+        })?;
     debug!("成功加载 {} 个 provider 配置", providers.len());
 
     Ok(providers)
@@ -73,12 +162,11 @@ pub fn load_active_model() -> TaiResult<Option<ActiveModel>> {
         return Ok(None);
     }
     
-    let content = fs::read_to_string(&path).map_err(|e| {
-        error!("读取状态文件失败: {}", e);
-        TaiError::FileError(format!("无法读取 {:?}: {}", path, e))
+    let doc = load_versioned(&path, &STATE_SCHEMA, true)?;
+    let active: ActiveModel = serde_json::from_value(doc).map_err(|e| {
+        error!("解析状态文件失败: {}", e);
+        TaiError::ConfigError(format!("{} 格式错误: {}", path.display(), e))
     })?;
-    
-    let active: ActiveModel = serde_json::from_str(&content)?;
     debug!("加载激活模型: {}/{}", active.provider, active.model);
     
     Ok(Some(active))
@@ -93,7 +181,8 @@ pub fn save_active_model(active: &ActiveModel) -> TaiResult<()> {
         TaiError::FileError(format!("无法创建目录: {}", e))
     })?;
     
-    let content = serde_json::to_string_pretty(active)?;
+    let doc = set_version(serde_json::to_value(active)?, STATE_VERSION)?;
+    let content = serde_json::to_string_pretty(&doc)?;
     fs::write(&path, content).map_err(|e| {
         error!("写入状态文件失败: {}", e);
         TaiError::FileError(format!("无法写入 {:?}: {}", path, e))
@@ -109,7 +198,8 @@ pub fn save_providers(providers: &[ProviderConfig]) -> TaiResult<()> {
         error!("创建目录失败: {}", e);
         TaiError::FileError(format!("无法创建目录: {}", e))
     })?;
-    let content = serde_json::to_string_pretty(providers)?;
+    let doc = json!({ "version": PROVIDERS_VERSION, "providers": providers });
+    let content = serde_json::to_string_pretty(&doc)?;
     fs::write(&path, content).map_err(|e| {
         error!("写入 provider 配置失败: {}", e);
         TaiError::FileError(format!("无法写入 {:?}: {}", path, e))
@@ -131,6 +221,29 @@ pub fn update_provider_api_key(provider_name: &str, api_key: &str) -> TaiResult<
     save_providers(&providers)
 }
 
+/// 解析 "provider/model" 或单独的模型名，返回匹配的 ActiveModel
+pub fn find_model(providers: &[ProviderConfig], spec: &str) -> Option<ActiveModel> {
+    let spec = spec.trim();
+    if let Some((provider, model)) = spec.split_once('/') {
+        let found = providers.iter().find(|p| {
+            p.provider.eq_ignore_ascii_case(provider) && p.model_names.iter().any(|m| m == model)
+        });
+        if let Some(p) = found {
+            return Some(ActiveModel {
+                provider: p.provider.clone(),
+                model: model.to_string(),
+            });
+        }
+    }
+    providers
+        .iter()
+        .find(|p| p.model_names.iter().any(|m| m == spec))
+        .map(|p| ActiveModel {
+            provider: p.provider.clone(),
+            model: spec.to_string(),
+        })
+}
+
 /// 根据状态文件找到对应的 (ProviderConfig, model_name)。
 /// 如果状态不存在或已失效，回退到第一个可用的 provider + model。
 pub fn resolve_active<'a>(
@@ -166,3 +279,234 @@ pub fn resolve_active<'a>(
     
     fallback
 }
+
+/// 校验 providers.json，返回所有问题
+pub fn validate_providers_file(path: &Path) -> Vec<Diagnostic> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            return vec![Diagnostic::error(
+                path,
+                1,
+                1,
+                format!("无法读取文件: {}", e),
+            )]
+        }
+    };
+    let doc = match parse_json(path, &content) {
+        Ok(doc) => doc,
+        Err(diag) => return vec![diag],
+    };
+
+    let mut diags = Vec::new();
+    let version = (PROVIDERS_SCHEMA.detect)(&doc);
+    if version > PROVIDERS_VERSION {
+        return vec![Diagnostic::at_key(
+            path,
+            &content,
+            "version",
+            Severity::Error,
+            format!("不支持的版本 {}", version),
+        )
+        .with_hint(format!(
+            "当前 tai 支持的最高版本为 {}，请升级 tai",
+            PROVIDERS_VERSION
+        ))];
+    }
+    let (doc, original) = match PROVIDERS_SCHEMA.migrate(path, doc) {
+        Ok((doc, original)) => {
+            if original < PROVIDERS_VERSION {
+                diags.push(
+                    Diagnostic::warning(path, 1, 1, format!("版本 {} 较旧（顶层为数组）", original))
+                        .with_hint("下次加载时会自动迁移为 { \"version\": 2, \"providers\": [...] } 并备份原文件"),
+                );
+            }
+            (doc, original)
+        }
+        Err(e) => {
+            diags.push(Diagnostic::error(path, 1, 1, e.to_string()));
+            return diags;
+        }
+    };
+
+    let Some(list) = doc.get("providers").and_then(Value::as_array) else {
+        diags.push(
+            Diagnostic::error(path, 1, 1, "缺少 providers 数组").with_hint(
+                "格式为 { \"version\": 2, \"providers\": [ { \"provider\": ..., ... } ] }",
+            ),
+        );
+        return diags;
+    };
+
+    // 每个 provider 在原文中的范围，诊断定位到对应元素内部；旧版本的顶层就是数组
+    let spans = array_element_spans(&content, (original >= 2).then_some("providers"));
+    let mut seen = Vec::new();
+    for (i, entry) in list.iter().enumerate() {
+        let span = spans.get(i).cloned().unwrap_or(0..content.len());
+        let at = |key: &str, severity, message: String| {
+            Diagnostic::at_key_in(path, &content, span.clone(), key, severity, message)
+        };
+        let label = entry
+            .get("provider")
+            .and_then(Value::as_str)
+            .map(|name| format!("provider `{}`", name))
+            .unwrap_or_else(|| format!("第 {} 个 provider", i + 1));
+        let Some(map) = entry.as_object() else {
+            diags.push(Diagnostic::at_offset(
+                path,
+                &content,
+                span.start,
+                Severity::Error,
+                format!("{} 必须是 JSON 对象", label),
+            ));
+            continue;
+        };
+
+        for key in map.keys() {
+            if !PROVIDER_KEYS.contains(&key.as_str()) {
+                let mut diag = at(
+                    key,
+                    Severity::Warning,
+                    format!("{} 中的未知字段 `{}`", label, key),
+                );
+                if let Some(similar) = suggest_key(key, PROVIDER_KEYS.iter().copied()) {
+                    diag = diag.with_hint(format!("是否想写 `{}`？", similar));
+                }
+                diags.push(diag);
+            }
+        }
+
+        for key in ["provider", "base_url"] {
+            match map.get(key) {
+                Some(Value::String(v)) if !v.trim().is_empty() => {}
+                _ => diags.push(
+                    at(
+                        key,
+                        Severity::Error,
+                        format!("{} 缺少字段 `{}` 或其值不是非空字符串", label, key),
+                    )
+                    .with_hint(format!("添加 \"{}\": \"...\"", key)),
+                ),
+            }
+        }
+
+        if let Some(url) = map.get("base_url").and_then(Value::as_str) {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                diags.push(
+                    at(
+                        "base_url",
+                        Severity::Error,
+                        format!("{} 的 base_url `{}` 不是 http(s) 地址", label, url),
+                    )
+                    .with_hint("例如 https://api.openai.com/v1"),
+                );
+            }
+        }
+
+        match map.get("model_names") {
+            Some(Value::Array(models)) if models.iter().all(Value::is_string) => {
+                if models.is_empty() {
+                    diags.push(at(
+                        "model_names",
+                        Severity::Warning,
+                        format!("{} 没有配置任何模型", label),
+                    ));
+                }
+            }
+            _ => diags.push(
+                at(
+                    "model_names",
+                    Severity::Error,
+                    format!("{} 的 model_names 必须是字符串数组", label),
+                )
+                .with_hint("例如 \"model_names\": [\"gpt-4o-mini\"]"),
+            ),
+        }
+
+        for key in ["kind", "api_key", "api_key_env", "api_key_cmd"] {
+            if let Some(v) = map.get(key) {
+                if !v.is_string() && !v.is_null() {
+                    diags.push(at(
+                        key,
+                        Severity::Error,
+                        format!("{} 的 `{}` 必须是字符串", label, key),
+                    ));
+                }
+            }
+        }
+
+        if let Some(name) = map.get("provider").and_then(Value::as_str) {
+            if seen.contains(&name) {
+                diags.push(
+                    at("provider", Severity::Error, format!("provider `{}` 重复定义", name))
+                        .with_hint("合并 model_names 或重命名其中一个"),
+                );
+            }
+            seen.push(name);
+        }
+    }
+    diags
+}
+
+/// 只读地解析 providers.json（在内存中迁移，不写回），失败时返回空列表
+fn peek_providers(path: &Path) -> Vec<ProviderConfig> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|doc| PROVIDERS_SCHEMA.migrate(path, doc).ok())
+        .and_then(|(doc, _)| serde_json::from_value(doc.get("providers")?.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// 校验 state.json，并检查激活模型是否仍存在于 providers_file 中
+pub fn validate_state_file(path: &Path, providers_file: &Path) -> Vec<Diagnostic> {
+    let providers = peek_providers(providers_file);
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            return vec![Diagnostic::error(
+                path,
+                1,
+                1,
+                format!("无法读取文件: {}", e),
+            )]
+        }
+    };
+    let doc = match parse_json(path, &content) {
+        Ok(doc) => doc,
+        Err(diag) => return vec![diag],
+    };
+    let version = object_version(&doc);
+    if version > STATE_VERSION {
+        return vec![Diagnostic::at_key(
+            path,
+            &content,
+            "version",
+            Severity::Error,
+            format!("不支持的版本 {}", version),
+        )];
+    }
+    let active: ActiveModel = match serde_json::from_value(doc) {
+        Ok(a) => a,
+        Err(e) => {
+            return vec![Diagnostic::error(path, 1, 1, format!("格式错误: {}", e))
+                .with_hint("运行 `tai model` 重新选择模型")]
+        }
+    };
+    if !providers.is_empty()
+        && find_model(&providers, &format!("{}/{}", active.provider, active.model)).is_none()
+    {
+        return vec![Diagnostic::at_key(
+            path,
+            &content,
+            "model",
+            Severity::Warning,
+            format!(
+                "激活模型 {}/{} 不在 providers.json 中，将回退到第一个模型",
+                active.provider, active.model
+            ),
+        )
+        .with_hint("运行 `tai model` 重新选择模型")];
+    }
+    Vec::new()
+}
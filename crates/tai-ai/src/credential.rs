@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use tai_core::{TaiError, TaiResult};
+use tracing::{debug, error};
+
+use crate::config::ProviderConfig;
+
+/// API Key 的来源，优先级：环境变量 > 外部命令 > providers.json 明文
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiKeySource {
+    /// providers.json 中的 api_key 字段
+    Plain,
+    /// 从环境变量读取（api_key_env）
+    Env(String),
+    /// 执行外部命令读取 stdout 首行（api_key_cmd），进程内缓存
+    Command(String),
+}
+
+impl ApiKeySource {
+    /// 面向用户的来源描述
+    pub fn describe(&self) -> String {
+        match self {
+            ApiKeySource::Plain => "~/.tai/providers.json".to_string(),
+            ApiKeySource::Env(var) => format!("环境变量 `{}`", var),
+            ApiKeySource::Command(cmd) => format!("命令 `{}`", cmd),
+        }
+    }
+}
+
+static COMMAND_CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+fn command_cache() -> &'static Mutex<HashMap<String, String>> {
+    COMMAND_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 解析 provider 的 API Key，外部命令的结果在进程生命周期内缓存
+pub fn resolve_api_key(provider: &ProviderConfig) -> TaiResult<String> {
+    match provider.api_key_source() {
+        ApiKeySource::Plain => Ok(provider.api_key.clone()),
+        ApiKeySource::Env(var) => {
+            debug!("从环境变量 {} 读取 {} 的 API Key", var, provider.provider);
+            match std::env::var(&var) {
+                Ok(value) if !value.trim().is_empty() => Ok(value.trim().to_string()),
+                _ => Err(TaiError::ConfigError(format!(
+                    "{} 的 API Key 应来自环境变量 `{}`，但该变量未设置或为空",
+                    provider.provider, var
+                ))),
+            }
+        }
+        ApiKeySource::Command(cmd) => {
+            if let Some(key) = command_cache()
+                .lock()
+                .expect("COMMAND_CACHE lock poisoned")
+                .get(&cmd)
+            {
+                return Ok(key.clone());
+            }
+            let key = run_key_command(&provider.provider, &cmd)?;
+            command_cache()
+                .lock()
+                .expect("COMMAND_CACHE lock poisoned")
+                .insert(cmd, key.clone());
+            Ok(key)
+        }
+    }
+}
+
+/// 丢弃外部命令的缓存结果，下次请求时重新执行
+pub fn forget_cached_api_key(provider: &ProviderConfig) {
+    if let ApiKeySource::Command(cmd) = provider.api_key_source() {
+        debug!("清除 {} 的 API Key 命令缓存", provider.provider);
+        command_cache()
+            .lock()
+            .expect("COMMAND_CACHE lock poisoned")
+            .remove(&cmd);
+    }
+}
+
+fn run_key_command(provider_name: &str, cmd: &str) -> TaiResult<String> {
+    debug!("执行 API Key 命令: {}", cmd);
+
+    let mut command = if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(cmd);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(cmd);
+        c
+    };
+
+    // stdin/stderr 继承终端，便于 gpg pinentry 等交互式解锁
+    let output = command
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| {
+            error!("执行 API Key 命令失败: {}", e);
+            TaiError::ConfigError(format!("无法执行 {} 的 api_key_cmd `{}`: {}", provider_name, cmd, e))
+        })?;
+
+    if !output.status.success() {
+        return Err(TaiError::ConfigError(format!(
+            "{} 的 api_key_cmd `{}` 执行失败（{}）",
+            provider_name, cmd, output.status
+        )));
+    }
+
+    // 与 `pass show` 约定一致：只取输出的第一行
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let key = stdout.lines().next().unwrap_or_default().trim().to_string();
+    if key.is_empty() {
+        return Err(TaiError::ConfigError(format!(
+            "{} 的 api_key_cmd `{}` 没有输出 API Key",
+            provider_name, cmd
+        )));
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(env: Option<&str>, cmd: Option<&str>) -> ProviderConfig {
+        ProviderConfig {
+            provider: "test".to_string(),
+            kind: None,
+            base_url: String::new(),
+            api_key: "plain".to_string(),
+            api_key_env: env.map(str::to_string),
+            api_key_cmd: cmd.map(str::to_string),
+            model_names: vec![],
+        }
+    }
+
+    #[test]
+    fn test_env_takes_precedence() {
+        std::env::set_var("TAI_TEST_CREDENTIAL_KEY", "from-env\n");
+        let p = provider(Some("TAI_TEST_CREDENTIAL_KEY"), Some("echo from-cmd"));
+        assert_eq!(resolve_api_key(&p).unwrap(), "from-env");
+
+        let missing = provider(Some("TAI_TEST_CREDENTIAL_MISSING"), None);
+        assert!(matches!(resolve_api_key(&missing), Err(TaiError::ConfigError(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_first_line_is_cached() {
+        let p = provider(None, Some("printf 'secret\\nlogin: me\\n'"));
+        assert_eq!(resolve_api_key(&p).unwrap(), "secret");
+        assert!(command_cache().lock().unwrap().contains_key("printf 'secret\\nlogin: me\\n'"));
+
+        forget_cached_api_key(&p);
+        assert!(!command_cache().lock().unwrap().contains_key("printf 'secret\\nlogin: me\\n'"));
+
+        let failing = provider(None, Some("exit 3"));
+        assert!(resolve_api_key(&failing).is_err());
+    }
+}
@@ -1,16 +1,25 @@
+mod backend;
 mod config;
+mod credential;
 mod provider;
+mod tool;
 
+pub use backend::{
+    get_backend, register_backend, registered_kinds, BackendFactory, ChatBackend, ChunkStream,
+};
 pub use config::{
-    load_active_model, load_providers, resolve_active, save_active_model, save_providers,
-    update_provider_api_key, ActiveModel, ProviderConfig,
+    find_model, load_active_model, load_providers, providers_path, resolve_active,
+    save_active_model, save_providers, state_path, update_provider_api_key,
+    validate_providers_file, validate_state_file, ActiveModel, ProviderConfig, PROVIDERS_VERSION,
+    STATE_VERSION,
 };
-
-use provider::{get_client, AiClient};
+pub use credential::{forget_cached_api_key, resolve_api_key, ApiKeySource};
+pub use tool::ChatTool;
 
 use futures::StreamExt;
 
-fn classify_error(err: &str, provider: &ProviderConfig) -> TaiError {
+/// 将后端返回的错误文本归类为 TaiError，供 `ChatBackend` 实现复用
+pub fn classify_error(err: &str, provider: &ProviderConfig) -> TaiError {
     let lower = err.to_lowercase();
     if lower.contains("401")
         || lower.contains("authentication")
@@ -31,12 +40,6 @@ fn classify_error(err: &str, provider: &ProviderConfig) -> TaiError {
         TaiError::AiError(format!("请求失败: {}", err))
     }
 }
-use rig::{
-    agent::MultiTurnStreamItem,
-    client::CompletionClient,
-    completion::Prompt,
-    streaming::{StreamedAssistantContent, StreamingChat},
-};
 use tai_core::{TaiError, TaiResult};
 use tokio::time::{sleep, Duration};
 use tracing::{debug, error, warn};
@@ -50,6 +53,14 @@ const TEST_DELAY_MS: u64 = 50;
 pub enum StreamChunk {
     Reasoning(String),
     Answer(String),
+    /// 模型请求调用工具，arguments 为 JSON 参数
+    ToolCall {
+        id: String,
+        name: String,
+        arguments: serde_json::Value,
+    },
+    /// 工具的执行结果（出错时为错误信息），id 与对应的 ToolCall 相同
+    ToolResult { id: String, output: String },
 }
 
 pub async fn chat(
@@ -60,21 +71,8 @@ pub async fn chat(
     debug!("开始非流式 AI 请求: provider={}, model={}", provider.provider, model);
     debug!("提示词: {}", prompt);
     
-    let client = get_client(provider);
-    let response = match client {
-        AiClient::OpenAI(c) => {
-            c.agent(model).build().prompt(prompt).await.map_err(|e| {
-                error!("OpenAI API 请求失败: {}", e);
-                classify_error(&e.to_string(), provider)
-            })?
-        }
-        AiClient::DeepSeek(c) => {
-            c.agent(model).build().prompt(prompt).await.map_err(|e| {
-                error!("DeepSeek API 请求失败: {}", e);
-                classify_error(&e.to_string(), provider)
-            })?
-        }
-    };
+    let backend = get_backend(provider)?;
+    let response = backend.chat(model, prompt).await?;
     
     debug!("AI 请求成功，响应长度: {} 字符", response.len());
     Ok(response)
@@ -84,7 +82,7 @@ pub async fn chat_stream<F>(
     provider: &ProviderConfig,
     model: &str,
     prompt: &str,
-    mut on_chunk: F,
+    on_chunk: F,
 ) -> TaiResult<String>
 where
     F: FnMut(StreamChunk) -> TaiResult<()>,
@@ -98,73 +96,62 @@ where
     debug!("开始流式 AI 请求: provider={}, model={}", provider.provider, model);
     debug!("提示词: {}", prompt);
     
-    let client = get_client(provider);
+    let backend = get_backend(provider)?;
+    let stream = backend.chat_stream(model, prompt).await?;
+    consume_stream(stream, on_chunk).await
+}
+
+/// 与 chat_stream 相同，但允许模型调用 tools；工具调用及其结果也通过 on_chunk 回调
+pub async fn chat_stream_with_tools<F>(
+    provider: &ProviderConfig,
+    model: &str,
+    prompt: &str,
+    tools: &[ChatTool],
+    on_chunk: F,
+) -> TaiResult<String>
+where
+    F: FnMut(StreamChunk) -> TaiResult<()>,
+{
+    debug!(
+        "开始带工具的流式 AI 请求: provider={}, model={}, tools={}",
+        provider.provider,
+        model,
+        tools.len()
+    );
+    debug!("提示词: {}", prompt);
+
+    let backend = get_backend(provider)?;
+    let stream = backend.chat_stream_with_tools(model, prompt, tools).await?;
+    consume_stream(stream, on_chunk).await
+}
+
+/// 把流中的块依次交给回调，返回拼接后的完整回答
+async fn consume_stream<F>(mut stream: ChunkStream, mut on_chunk: F) -> TaiResult<String>
+where
+    F: FnMut(StreamChunk) -> TaiResult<()>,
+{
     let mut full_response = String::new();
     let mut chunk_count = 0;
 
-    match client {
-        AiClient::OpenAI(c) => {
-            let agent = c.agent(model).build();
-            let mut stream = agent.stream_chat(prompt, Vec::new()).await;
-
-            while let Some(result) = stream.next().await {
-                match result {
-                    Ok(MultiTurnStreamItem::StreamAssistantItem(content)) => match content {
-                        StreamedAssistantContent::Text(text) => {
-                            chunk_count += 1;
-                            debug!("收到答案块 #{}: {} 字符", chunk_count, text.text.len());
-                            on_chunk(StreamChunk::Answer(text.text.clone()))?;
-                            full_response.push_str(&text.text);
-                        }
-                        StreamedAssistantContent::ReasoningDelta { reasoning, .. } => {
-                            chunk_count += 1;
-                            debug!("收到推理块 #{}: {} 字符", chunk_count, reasoning.len());
-                            on_chunk(StreamChunk::Reasoning(reasoning.clone()))?;
-                        }
-                        _ => {}
-                    },
-                    Ok(MultiTurnStreamItem::FinalResponse(_)) => {
-                        debug!("收到最终响应标记");
-                        break;
-                    }
-                    Ok(_) => {}
-                    Err(e) => {
-                        error!("流式请求出错: {}", e);
-                        return Err(classify_error(&e.to_string(), provider));
-                    }
-                }
+    while let Some(chunk) = stream.next().await {
+        chunk_count += 1;
+        match chunk? {
+            StreamChunk::Answer(text) => {
+                debug!("收到答案块 #{}: {} 字符", chunk_count, text.len());
+                full_response.push_str(&text);
+                on_chunk(StreamChunk::Answer(text))?;
             }
-        }
-        AiClient::DeepSeek(c) => {
-            let agent = c.agent(model).build();
-            let mut stream = agent.stream_chat(prompt, Vec::new()).await;
-
-            while let Some(result) = stream.next().await {
-                match result {
-                    Ok(MultiTurnStreamItem::StreamAssistantItem(content)) => match content {
-                        StreamedAssistantContent::Text(text) => {
-                            chunk_count += 1;
-                            debug!("收到答案块 #{}: {} 字符", chunk_count, text.text.len());
-                            on_chunk(StreamChunk::Answer(text.text.clone()))?;
-                            full_response.push_str(&text.text);
-                        }
-                        StreamedAssistantContent::ReasoningDelta { reasoning, .. } => {
-                            chunk_count += 1;
-                            debug!("收到推理块 #{}: {} 字符", chunk_count, reasoning.len());
-                            on_chunk(StreamChunk::Reasoning(reasoning.clone()))?;
-                        }
-                        _ => {}
-                    },
-                    Ok(MultiTurnStreamItem::FinalResponse(_)) => {
-                        debug!("收到最终响应标记");
-                        break;
-                    }
-                    Ok(_) => {}
-                    Err(e) => {
-                        error!("流式请求出错: {}", e);
-                        return Err(classify_error(&e.to_string(), provider));
-                    }
-                }
+            StreamChunk::Reasoning(reasoning) => {
+                debug!("收到推理块 #{}: {} 字符", chunk_count, reasoning.len());
+                on_chunk(StreamChunk::Reasoning(reasoning))?;
+            }
+            chunk @ StreamChunk::ToolCall { .. } => {
+                debug!("收到工具调用 #{}: {:?}", chunk_count, chunk);
+                on_chunk(chunk)?;
+            }
+            chunk @ StreamChunk::ToolResult { .. } => {
+                debug!("收到工具结果 #{}", chunk_count);
+                on_chunk(chunk)?;
             }
         }
     }
@@ -173,6 +160,12 @@ where
     Ok(full_response)
 }
 
+/// 列出 provider 的可用模型
+pub async fn list_models(provider: &ProviderConfig) -> TaiResult<Vec<String>> {
+    debug!("列出模型: provider={}", provider.provider);
+    get_backend(provider)?.list_models().await
+}
+
 /// 测试模式：先流式输出 reasoning 文件，再流式输出 answer 文件
 async fn chat_stream_test_mode<F>(mut on_chunk: F) -> TaiResult<String>
 where
@@ -1,53 +1,183 @@
-use rig::providers::{deepseek, openai};
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use futures::Stream;
+use rig::{
+    agent::MultiTurnStreamItem,
+    client::CompletionClient,
+    completion::{message::ToolResultContent, CompletionModel, GetTokenUsage, Prompt},
+    providers::{deepseek, openai},
+    streaming::{StreamedAssistantContent, StreamedUserContent, StreamingChat},
+    tool::ToolDyn,
+};
 use std::collections::HashMap;
-use std::sync::{OnceLock, RwLock};
+use std::fmt::Display;
+use std::sync::Arc;
+use tai_core::{TaiError, TaiResult};
+use tracing::{debug, error};
 
+use crate::backend::{BackendFactory, ChatBackend, ChunkStream};
 use crate::config::ProviderConfig;
+use crate::tool::RigTool;
+use crate::{classify_error, ChatTool, StreamChunk};
 
-#[derive(Clone)]
-pub enum AiClient {
-    OpenAI(openai::Client),
-    DeepSeek(deepseek::Client),
-}
-
-static CLIENT_REGISTRY: OnceLock<RwLock<HashMap<String, AiClient>>> = OnceLock::new();
+/// 一次带工具的请求中，模型最多连续调用工具的轮数
+const MAX_TOOL_TURNS: usize = 10;
 
-fn registry() -> &'static RwLock<HashMap<String, AiClient>> {
-    CLIENT_REGISTRY.get_or_init(|| RwLock::new(HashMap::new()))
+/// 基于 rig `CompletionClient` 的通用后端，OpenAI / DeepSeek 共用同一份实现
+struct RigBackend<C> {
+    client: C,
+    config: ProviderConfig,
 }
 
-/// 按 (provider, base_url, api_key) 复用 client，不存在时惰性创建
-pub fn get_client(config: &ProviderConfig) -> AiClient {
-    let key = format!("{}|{}|{}", config.provider, config.base_url, config.api_key);
+impl<C> ChatBackend for RigBackend<C>
+where
+    C: CompletionClient + Send + Sync,
+    C::CompletionModel: 'static,
+    <C::CompletionModel as CompletionModel>::StreamingResponse: GetTokenUsage + Send,
+{
+    fn chat<'a>(&'a self, model: &'a str, prompt: &'a str) -> BoxFuture<'a, TaiResult<String>> {
+        Box::pin(async move {
+            self.client
+                .agent(model)
+                .build()
+                .prompt(prompt)
+                .await
+                .map_err(|e| {
+                    error!("{} API 请求失败: {}", self.config.provider, e);
+                    classify_error(&e.to_string(), &self.config)
+                })
+        })
+    }
 
-    {
-        let read = registry().read().expect("CLIENT_REGISTRY read lock poisoned");
-        if let Some(client) = read.get(&key) {
-            return client.clone();
+    fn chat_stream<'a>(
+        &'a self,
+        model: &'a str,
+        prompt: &'a str,
+    ) -> BoxFuture<'a, TaiResult<ChunkStream>> {
+        Box::pin(async move {
+            let agent = self.client.agent(model).build();
+            let stream = agent.stream_chat(prompt, Vec::new()).await;
+            Ok(into_chunks(stream, self.config.clone()))
+        })
+    }
+
+    fn chat_stream_with_tools<'a>(
+        &'a self,
+        model: &'a str,
+        prompt: &'a str,
+        tools: &'a [ChatTool],
+    ) -> BoxFuture<'a, TaiResult<ChunkStream>> {
+        if tools.is_empty() {
+            return self.chat_stream(model, prompt);
         }
+        Box::pin(async move {
+            let tools: Vec<Box<dyn ToolDyn>> = tools
+                .iter()
+                .map(|tool| Box::new(RigTool(tool.clone())) as Box<dyn ToolDyn>)
+                .collect();
+            let agent = self.client.agent(model).tools(tools).build();
+            let stream = agent
+                .stream_chat(prompt, Vec::new())
+                .multi_turn(MAX_TOOL_TURNS)
+                .await;
+            Ok(into_chunks(stream, self.config.clone()))
+        })
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, TaiResult<Vec<String>>> {
+        Box::pin(async move { Ok(self.config.model_names.clone()) })
     }
+}
 
-    let mut write = registry()
-        .write()
-        .expect("CLIENT_REGISTRY write lock poisoned");
-    write
-        .entry(key)
-        .or_insert_with(|| match config.provider.to_lowercase().as_str() {
-            "openai" => AiClient::OpenAI(
-                openai::Client::builder()
-                    .base_url(&config.base_url)
-                    .api_key(&config.api_key)
-                    .build()
-                    .expect("Failed to build OpenAI client"),
-            ),
-            "deepseek" => AiClient::DeepSeek(
-                deepseek::Client::builder()
-                    .base_url(&config.base_url)
-                    .api_key(&config.api_key)
-                    .build()
-                    .expect("Failed to build DeepSeek client"),
-            ),
-            _ => panic!("Unsupported provider: {}", config.provider),
+/// 把 rig 的多轮流式响应转换为 StreamChunk 流，收到最终响应标记时结束
+fn into_chunks<S, R, E>(stream: S, config: ProviderConfig) -> ChunkStream
+where
+    S: Stream<Item = Result<MultiTurnStreamItem<R>, E>> + Send + 'static,
+    R: Send + 'static,
+    E: Display + Send + 'static,
+{
+    stream
+        .take_while(|item| {
+            let done = matches!(item, Ok(MultiTurnStreamItem::FinalResponse(_)));
+            if done {
+                debug!("收到最终响应标记");
+            }
+            futures::future::ready(!done)
+        })
+        .filter_map(move |item| {
+            let chunk = match item {
+                Ok(MultiTurnStreamItem::StreamAssistantItem(content)) => match content {
+                    StreamedAssistantContent::Text(text) => Some(Ok(StreamChunk::Answer(text.text))),
+                    StreamedAssistantContent::ReasoningDelta { reasoning, .. } => {
+                        Some(Ok(StreamChunk::Reasoning(reasoning)))
+                    }
+                    StreamedAssistantContent::ToolCall {
+                        tool_call,
+                        internal_call_id,
+                    } => Some(Ok(StreamChunk::ToolCall {
+                        id: internal_call_id,
+                        name: tool_call.function.name,
+                        arguments: tool_call.function.arguments,
+                    })),
+                    _ => None,
+                },
+                Ok(MultiTurnStreamItem::StreamUserItem(StreamedUserContent::ToolResult {
+                    tool_result,
+                    internal_call_id,
+                })) => {
+                    let output = tool_result
+                        .content
+                        .into_iter()
+                        .filter_map(|content| match content {
+                            ToolResultContent::Text(text) => Some(text.text),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    Some(Ok(StreamChunk::ToolResult {
+                        id: internal_call_id,
+                        output,
+                    }))
+                }
+                Ok(_) => None,
+                Err(e) => {
+                    error!("流式请求出错: {}", e);
+                    Some(Err(classify_error(&e.to_string(), &config)))
+                }
+            };
+            futures::future::ready(chunk)
         })
-        .clone()
+        .boxed()
+}
+
+fn build_openai(config: &ProviderConfig) -> TaiResult<Arc<dyn ChatBackend>> {
+    let client: openai::Client = openai::Client::builder()
+        .base_url(&config.base_url)
+        .api_key(&config.api_key)
+        .build()
+        .map_err(|e| TaiError::InitError(format!("无法创建 OpenAI 客户端: {}", e)))?;
+    Ok(Arc::new(RigBackend {
+        client,
+        config: config.clone(),
+    }))
+}
+
+fn build_deepseek(config: &ProviderConfig) -> TaiResult<Arc<dyn ChatBackend>> {
+    let client: deepseek::Client = deepseek::Client::builder()
+        .base_url(&config.base_url)
+        .api_key(&config.api_key)
+        .build()
+        .map_err(|e| TaiError::InitError(format!("无法创建 DeepSeek 客户端: {}", e)))?;
+    Ok(Arc::new(RigBackend {
+        client,
+        config: config.clone(),
+    }))
+}
+
+/// 内置的 provider 类型，注册表初始化时载入
+pub(crate) fn builtin_factories() -> HashMap<String, BackendFactory> {
+    let mut map: HashMap<String, BackendFactory> = HashMap::new();
+    map.insert("openai".to_string(), Arc::new(build_openai));
+    map.insert("deepseek".to_string(), Arc::new(build_deepseek));
+    map
 }
@@ -0,0 +1,117 @@
+use futures::future::BoxFuture;
+use rig::{
+    completion::ToolDefinition,
+    tool::{ToolDyn, ToolError},
+    wasm_compat::WasmBoxedFuture,
+};
+use serde_json::Value;
+use std::{fmt, future::Future, sync::Arc};
+use tai_core::TaiResult;
+use tracing::debug;
+
+type ToolHandler = Arc<dyn Fn(Value) -> BoxFuture<'static, TaiResult<String>> + Send + Sync>;
+
+/// 提供给模型调用的工具：名称、用途说明、参数的 JSON Schema 和处理函数
+///
+/// 处理函数返回的文本作为工具结果交给模型；返回 Err 时错误信息同样交给模型，不会中断对话
+#[derive(Clone)]
+pub struct ChatTool {
+    pub name: String,
+    pub description: String,
+    pub parameters: Value,
+    handler: ToolHandler,
+}
+
+impl ChatTool {
+    pub fn new<F, Fut>(name: &str, description: &str, parameters: Value, handler: F) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = TaiResult<String>> + Send + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters,
+            handler: Arc::new(move |args| Box::pin(handler(args))),
+        }
+    }
+
+    pub async fn call(&self, args: Value) -> TaiResult<String> {
+        (self.handler)(args).await
+    }
+}
+
+impl fmt::Debug for ChatTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChatTool")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 把 ChatTool 适配为 rig 的 `ToolDyn`，通过 agent builder 的 `tools()` 注册
+pub(crate) struct RigTool(pub ChatTool);
+
+impl ToolDyn for RigTool {
+    fn name(&self) -> String {
+        self.0.name.clone()
+    }
+
+    fn definition<'a>(&'a self, _prompt: String) -> WasmBoxedFuture<'a, ToolDefinition> {
+        Box::pin(async move {
+            ToolDefinition {
+                name: self.0.name.clone(),
+                description: self.0.description.clone(),
+                parameters: self.0.parameters.clone(),
+            }
+        })
+    }
+
+    fn call<'a>(&'a self, args: String) -> WasmBoxedFuture<'a, Result<String, ToolError>> {
+        Box::pin(async move {
+            debug!("调用工具 {}: {}", self.0.name, args);
+            // 部分模型在无参数时返回空字符串
+            let args = match args.trim() {
+                "" => Value::Object(Default::default()),
+                text => serde_json::from_str(text).map_err(ToolError::JsonError)?,
+            };
+            self.0
+                .call(args)
+                .await
+                .map_err(|e| ToolError::ToolCallError(Box::new(e)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_rig_tool_adapter() {
+        let tool = RigTool(ChatTool::new(
+            "echo",
+            "原样返回 text 参数",
+            json!({"type": "object", "properties": {"text": {"type": "string"}}}),
+            |args| async move {
+                args["text"]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| tai_core::TaiError::Other("缺少 text 参数".to_string()))
+            },
+        ));
+
+        let definition = tool.definition(String::new()).await;
+        assert_eq!(definition.name, "echo");
+        assert_eq!(
+            ToolDyn::call(&tool, r#"{"text": "hi"}"#.to_string())
+                .await
+                .unwrap(),
+            "hi"
+        );
+        assert!(ToolDyn::call(&tool, "{}".to_string()).await.is_err());
+        assert!(ToolDyn::call(&tool, "not json".to_string()).await.is_err());
+    }
+}
@@ -1,10 +1,13 @@
 use clap::{Args, ValueHint};
-use tai_ai::{chat_stream, ProviderConfig, StreamChunk};
+use tai_ai::{chat_stream, chat_stream_with_tools, ChatTool, ProviderConfig, StreamChunk};
 use tai_core::{TaiConfig, TaiError, TaiResult};
 use tai_tui::{Spinner, TextRenderer};
 use tracing::debug;
 
+use crate::clipboard::copy_for_viewer;
+use crate::index::{describe_hits, project_root, ProjectIndex};
 use crate::provider::{ensure_active_provider, recover_auth_error};
+use crate::tools::builtin_tools;
 
 mod history;
 use history::show_history;
@@ -19,6 +22,18 @@ pub struct AskArgs {
     #[arg(short, long, num_args = 0..=1, default_missing_value = "1")]
     pub cache: Option<usize>,
 
+    /// let the model inspect the project with read-only tools (read_file, list_dir, grep, git_status)
+    #[arg(long)]
+    pub tools: bool,
+
+    /// retrieve relevant code from the project index (.tai/index) and include it as context
+    #[arg(long)]
+    pub project: bool,
+
+    /// number of chunks to retrieve with --project
+    #[arg(short = 'k', long, default_value_t = 8, requires = "project")]
+    pub top_k: usize,
+
     /// user requirement (if empty, enter editor)
     pub user_input: Option<String>,
 }
@@ -39,12 +54,38 @@ impl AskArgs {
             None => prompt,
         };
 
-        let config = TaiConfig::load().unwrap_or_default();
+        let config = TaiConfig::load()?;
+        let final_prompt = match config.role.as_deref().filter(|r| !r.trim().is_empty()) {
+            Some(role) => {
+                debug!("附加角色设定: {}", role);
+                format!("{}\n\n{}", role, final_prompt)
+            }
+            None => final_prompt,
+        };
+        let final_prompt = if self.project {
+            match project_context(&final_prompt, self.top_k)? {
+                Some(context) => format!("{}\n\n{}\n\n{}", final_prompt, PROJECT_HINT, context),
+                None => final_prompt,
+            }
+        } else {
+            final_prompt
+        };
+        let tools = if self.tools {
+            debug!("启用只读工具");
+            builtin_tools()?
+        } else {
+            Vec::new()
+        };
+        let final_prompt = if tools.is_empty() {
+            final_prompt
+        } else {
+            format!("{}\n\n{}", final_prompt, TOOLS_HINT)
+        };
         let mut context = ensure_active_provider().await?;
 
         loop {
             debug!("使用模型: {}/{}", context.0.provider, context.1);
-            match do_ask(&context.0, &context.1, &final_prompt, &config).await {
+            match do_ask(&context.0, &context.1, &final_prompt, &tools, &config).await {
                 Ok(markdown) => {
                     if !markdown.is_empty() {
                         if let Err(e) = history::save_history(&markdown) {
@@ -63,17 +104,43 @@ impl AskArgs {
     }
 }
 
-async fn do_ask(
+const PROJECT_HINT: &str = "以下是从当前项目中检索到的相关代码片段（路径:起止行），回答时请引用具体文件和行号：";
+
+/// 提示词中检索片段的最大字符数
+const MAX_PROJECT_CONTEXT_CHARS: usize = 24_000;
+
+/// 按问题检索项目索引，返回拼好的代码片段；没有命中时返回 None
+fn project_context(question: &str, top_k: usize) -> TaiResult<Option<String>> {
+    let root = project_root(&std::env::current_dir()?)?;
+    let spinner = Spinner::new("更新项目索引...");
+    let index = ProjectIndex::open(&root);
+    spinner.finish_and_clear();
+    let index = index?;
+    let hits = index.search(question, top_k);
+    if hits.is_empty() {
+        println!("  未在项目中找到相关代码片段");
+        return Ok(None);
+    }
+    debug!("检索到 {} 个片段", hits.len());
+    print!("参考片段:\n{}", describe_hits(&hits));
+    Ok(Some(index.render_hits(&hits, MAX_PROJECT_CONTEXT_CHARS)))
+}
+
+pub(crate) const TOOLS_HINT: &str = "你可以调用工具只读地查看当前项目（读取文件、列出目录、搜索代码、查看 git 状态）。\
+涉及项目代码时先用工具确认，不要猜测文件内容。";
+
+pub(crate) async fn do_ask(
     provider: &ProviderConfig,
     model: &str,
     prompt: &str,
+    tools: &[ChatTool],
     config: &TaiConfig,
 ) -> TaiResult<String> {
     let spinner = Spinner::new("AI 思考中...");
-    let mut renderer = TextRenderer::new();
+    let mut renderer = TextRenderer::new().with_copy_handler(copy_for_viewer);
     let mut first_chunk = true;
 
-    chat_stream(provider, model, prompt, |chunk| {
+    let on_chunk = |chunk| {
         if first_chunk {
             spinner.finish_and_clear();
             first_chunk = false;
@@ -89,10 +156,49 @@ async fn do_ask(
                 renderer.append_answer(&text);
                 renderer.render()?;
             }
+            StreamChunk::ToolCall {
+                name, arguments, ..
+            } => {
+                debug!("工具调用: {} {}", name, arguments);
+                renderer.print_tool_call(&name, &describe_arguments(&arguments))?;
+            }
+            StreamChunk::ToolResult { output, .. } => {
+                renderer.print_tool_result(&summarize_output(&output))?;
+            }
         }
         Ok(())
-    })
-    .await?;
+    };
+    if tools.is_empty() {
+        chat_stream(provider, model, prompt, on_chunk).await?;
+    } else {
+        chat_stream_with_tools(provider, model, prompt, tools, on_chunk).await?;
+    }
 
     Ok(renderer.finish(config.show_markdown_view)?)
 }
+
+/// 工具参数的简短展示：依次列出参数值
+fn describe_arguments(arguments: &serde_json::Value) -> String {
+    match arguments {
+        serde_json::Value::Object(map) => map
+            .values()
+            .map(|value| match value {
+                serde_json::Value::String(text) => text.clone(),
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        other => other.to_string(),
+    }
+}
+
+/// 工具结果的一行摘要：单行结果原样显示，多行结果显示行数
+fn summarize_output(output: &str) -> String {
+    let output = output.trim();
+    match output.lines().count() {
+        0 => "（无输出）".to_string(),
+        1 if output.chars().count() <= 80 => output.to_string(),
+        1 => format!("{}...", output.chars().take(80).collect::<String>()),
+        n => format!("{} 行", n),
+    }
+}
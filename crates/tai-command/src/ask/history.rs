@@ -14,6 +14,8 @@ use termimad::{
     },
 };
 
+use crate::clipboard::copy_for_viewer;
+
 const MAX_HISTORY_COUNT: usize = 50;
 
 /// 获取历史记录目录路径
@@ -110,7 +112,7 @@ fn list_history(count: usize) -> TaiResult<Vec<HistoryEntry>> {
         .collect();
     
     // 按修改时间排序（最新的在前）
-    entries.sort_by(|a, b| b.modified.cmp(&a.modified));
+    entries.sort_by_key(|e| std::cmp::Reverse(e.modified));
     
     // 限制数量
     entries.truncate(count);
@@ -150,7 +152,7 @@ pub fn show_history(count: usize) -> TaiResult<()> {
 
 /// 显示单条历史记录
 fn show_single_history(content: &str) -> TaiResult<()> {
-    show_markdown_view(content, make_default_skin())
+    show_markdown_view(content, make_default_skin(), Some(copy_for_viewer))
         .map_err(|e| TaiError::FileError(e.to_string()))
 }
 
@@ -188,16 +190,8 @@ fn show_history_list(entries: Vec<HistoryEntry>) -> TaiResult<()> {
                     break;
                 }
                 match code {
-                    KeyCode::Up => {
-                        if selected > 0 {
-                            selected -= 1;
-                        }
-                    }
-                    KeyCode::Down => {
-                        if selected < entries.len() - 1 {
-                            selected += 1;
-                        }
-                    }
+                    KeyCode::Up => selected = selected.saturating_sub(1),
+                    KeyCode::Down if selected < entries.len() - 1 => selected += 1,
                     KeyCode::Enter => {
                         // 退出列表，显示选中的历史记录
                         terminal::disable_raw_mode().map_err(|e| TaiError::FileError(e.to_string()))?;
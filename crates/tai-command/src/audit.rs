@@ -0,0 +1,124 @@
+use clap::{Args, Subcommand};
+use tai_core::audit::{audit_dir, load_entries};
+use tai_core::{AuditEntry, TaiError, TaiResult};
+
+/// list 中命令列的最大显示宽度（字符）
+const MAX_COMMAND_WIDTH: usize = 60;
+
+#[derive(Args, Debug)]
+pub struct AuditArgs {
+    #[command(subcommand)]
+    pub subcommand: AuditSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuditSubcommand {
+    /// 列出最近的审计记录，最新的在前
+    List {
+        /// 显示的条数
+        #[arg(short = 'n', long, default_value_t = 20)]
+        count: usize,
+    },
+    /// 显示一条审计记录的完整内容
+    Show {
+        /// list 中的序号，默认为最新一条
+        #[arg(default_value_t = 1)]
+        index: usize,
+    },
+}
+
+impl AuditArgs {
+    pub async fn handle(self) -> TaiResult<()> {
+        let entries = load_entries()?;
+        if entries.is_empty() {
+            println!("暂无审计记录（{}）", audit_dir().display());
+            return Ok(());
+        }
+        match self.subcommand {
+            AuditSubcommand::List { count } => list(&entries, count),
+            AuditSubcommand::Show { index } => {
+                let entry = index
+                    .checked_sub(1)
+                    .and_then(|i| entries.get(i))
+                    .ok_or_else(|| {
+                        TaiError::Other(format!(
+                            "序号 {} 不存在，共 {} 条记录",
+                            index,
+                            entries.len()
+                        ))
+                    })?;
+                show(entry);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 命令内容，或直接写入的文件路径
+fn subject(entry: &AuditEntry) -> String {
+    entry
+        .command
+        .clone()
+        .unwrap_or_else(|| format!("写入 {}", entry.files.join(", ")))
+}
+
+fn list(entries: &[AuditEntry], count: usize) {
+    for (i, entry) in entries.iter().take(count).enumerate() {
+        // 2024-05-01T12:30:45.123+08:00 -> 2024-05-01 12:30:45
+        let time = entry.timestamp.get(..19).unwrap_or(&entry.timestamp);
+        let exit = entry
+            .exit_code
+            .map_or_else(|| "-".to_string(), |code| code.to_string());
+        let subject = subject(entry).replace('\n', " ");
+        let subject = if subject.chars().count() > MAX_COMMAND_WIDTH {
+            let head: String = subject.chars().take(MAX_COMMAND_WIDTH - 3).collect();
+            head + "..."
+        } else {
+            subject
+        };
+        println!(
+            "{:>4}  {}  {:<5} {:<10} {:>4}  {}",
+            i + 1,
+            time.replace('T', " "),
+            entry.source,
+            entry.decision.label(),
+            exit,
+            subject
+        );
+    }
+    if entries.len() > count {
+        println!("  ...共 {} 条，使用 -n 显示更多", entries.len());
+    }
+}
+
+fn show(entry: &AuditEntry) {
+    let optional = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    println!("时间:     {}", entry.timestamp);
+    println!("来源:     tai {}", entry.source);
+    println!("请求:     {}", optional(&entry.prompt));
+    println!("模型:     {}", optional(&entry.model));
+    println!("目录:     {}", entry.cwd);
+    match &entry.command {
+        Some(command) => println!("命令:     {}", command),
+        None => println!("操作:     {}", entry.action),
+    }
+    println!("决定:     {}", entry.decision.label());
+    if let Some(reason) = &entry.reason {
+        println!("原因:     {}", reason);
+    }
+    println!(
+        "退出码:   {}",
+        entry
+            .exit_code
+            .map_or_else(|| "-".to_string(), |code| code.to_string())
+    );
+    println!("输出哈希: {}", optional(&entry.output_sha256));
+    if entry.files.is_empty() {
+        println!("涉及文件: -");
+    } else {
+        println!("涉及文件:");
+        for file in &entry.files {
+            println!("  {}", file);
+        }
+    }
+}
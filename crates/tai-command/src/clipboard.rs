@@ -0,0 +1,156 @@
+use arboard::Clipboard;
+use std::{
+    fmt,
+    fs::OpenOptions,
+    io::{IsTerminal, Write},
+    process::{Command, Stdio},
+};
+use tai_core::{TaiError, TaiResult};
+use tracing::debug;
+
+/// 实际完成复制的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardMethod {
+    Arboard,
+    /// 外部命令，如 wl-copy / xclip
+    Command(&'static str),
+    /// 终端 OSC 52 转义序列，由终端模拟器写入本地剪贴板
+    Osc52,
+}
+
+impl fmt::Display for ClipboardMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipboardMethod::Arboard => f.write_str("系统剪贴板"),
+            ClipboardMethod::Command(program) => write!(f, "{}", program),
+            ClipboardMethod::Osc52 => f.write_str("终端 OSC 52"),
+        }
+    }
+}
+
+/// 外部剪贴板命令，按顺序尝试；Wayland 优先 wl-copy
+const COPY_COMMANDS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+/// 复制文本：依次尝试 arboard、wl-copy/xclip/xsel、OSC 52，返回成功的方式
+pub fn copy_text(text: &str) -> TaiResult<ClipboardMethod> {
+    match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+        Ok(()) => return Ok(ClipboardMethod::Arboard),
+        Err(e) => debug!("arboard 不可用: {}", e),
+    }
+
+    if cfg!(unix) {
+        for (program, args) in COPY_COMMANDS {
+            match pipe_to(program, args, text) {
+                Ok(()) => return Ok(ClipboardMethod::Command(program)),
+                Err(e) => debug!("{} 不可用: {}", program, e),
+            }
+        }
+    }
+
+    write_osc52(text).map(|_| ClipboardMethod::Osc52)
+}
+
+/// 供 Markdown 查看器复制代码块使用，返回复制方式的说明
+pub fn copy_for_viewer(text: &str) -> std::io::Result<String> {
+    copy_text(text)
+        .map(|method| method.to_string())
+        .map_err(|e| std::io::Error::other(e.to_string()))
+}
+
+fn pipe_to(program: &str, args: &[&str], text: &str) -> std::io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("退出状态 {}", status)))
+    }
+}
+
+/// 通过 OSC 52 让终端写入剪贴板；优先写 /dev/tty，stdout 被重定向时也能送达终端
+fn write_osc52(text: &str) -> TaiResult<()> {
+    let sequence = osc52_sequence(
+        text,
+        std::env::var_os("TMUX").is_some(),
+        std::env::var("TERM").is_ok_and(|t| t.starts_with("screen")),
+    );
+
+    if let Ok(mut tty) = OpenOptions::new().write(true).open("/dev/tty") {
+        tty.write_all(sequence.as_bytes())
+            .and_then(|_| tty.flush())
+            .map_err(|e| TaiError::Other(format!("无法写入终端: {}", e)))?;
+        return Ok(());
+    }
+    let mut stdout = std::io::stdout();
+    if stdout.is_terminal() {
+        stdout
+            .write_all(sequence.as_bytes())
+            .and_then(|_| stdout.flush())
+            .map_err(|e| TaiError::Other(format!("无法写入终端: {}", e)))?;
+        return Ok(());
+    }
+    Err(TaiError::Other(
+        "无法访问剪贴板：arboard、wl-copy/xclip 均不可用，且没有可写入 OSC 52 的终端".to_string(),
+    ))
+}
+
+/// 构造 OSC 52 序列；tmux / screen 中需要用 DCS 透传包裹，内部的 ESC 需加倍
+fn osc52_sequence(text: &str, tmux: bool, screen: bool) -> String {
+    let osc = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", osc.replace('\x1b', "\x1b\x1b"))
+    } else if screen {
+        format!("\x1bP{}\x1b\\", osc)
+    } else {
+        osc
+    }
+}
+
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(base64(b"ls -la"), "bHMgLWxh");
+        assert_eq!(base64(b"rg"), "cmc=");
+        assert_eq!(osc52_sequence("rg", false, false), "\x1b]52;c;cmc=\x07");
+        assert_eq!(
+            osc52_sequence("rg", true, false),
+            "\x1bPtmux;\x1b\x1b]52;c;cmc=\x07\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use clap::Args;
+use tai_core::{audit, AuditDecision, TaiError, TaiResult};
+use tracing::debug;
+
+use crate::editor::edit_in_editor;
+use crate::git::{chunk_diff, git};
+use crate::provider::{chat_with_retry, ensure_active_provider};
+use crate::shell::capture_program;
+
+const PROMPT: &str = "\
+你是一名资深开发者，请根据下面暂存的改动写一条 git 提交信息。
+
+要求：
+1. 使用 Conventional Commits 格式：`类型(范围): 简述`，类型取 feat、fix、docs、refactor、test、chore、perf 等
+2. 语言、大小写、范围的写法与时态参照仓库最近的提交；最近的提交使用其他固定格式时以最近的提交为准
+3. 第一行不超过 72 个字符；改动较多时空一行后用列表简要说明要点，说明“做了什么、为什么”
+4. 只输出提交信息本身，不要使用 ``` 包裹，不要添加其他文字";
+
+const SUMMARY_PROMPT: &str = "\
+下面是一次提交中的部分改动。请用简短的中文列表概括这部分改动做了什么（涉及的文件、功能和原因），\
+不要逐行复述代码，不要输出其他内容。";
+
+/// 每段 diff 的最大字符数；超过时先分段概括再生成提交信息
+const MAX_DIFF_CHARS: usize = 12_000;
+/// 参照的最近提交数量
+const RECENT_COMMITS: usize = 10;
+
+const EDITOR_HELP: &str = "
+# 请编辑提交信息，以 # 开头的行会被忽略。
+# 保存并退出后提交；清空全部内容则取消提交。
+";
+
+#[derive(Args, Debug)]
+pub struct CommitArgs {
+    /// 不打开编辑器，直接使用生成的提交信息提交
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+impl CommitArgs {
+    pub async fn handle(self) -> TaiResult<()> {
+        let diff = git(&["diff", "--cached", "--no-color"])?;
+        if diff.trim().is_empty() {
+            return Err(TaiError::Other(
+                "没有暂存的改动，请先使用 git add 暂存要提交的文件".to_string(),
+            ));
+        }
+        let stat = git(&["diff", "--cached", "--stat", "--no-color"])?;
+        // 新仓库还没有提交时 git log 会失败
+        let recent = git(&[
+            "log",
+            "-n",
+            &RECENT_COMMITS.to_string(),
+            "--pretty=format:%s",
+        ])
+        .unwrap_or_default();
+        debug!("暂存改动 {} 字符", diff.len());
+
+        let mut context = ensure_active_provider().await?;
+        let model = format!("{}/{}", context.0.provider, context.1);
+        audit::set_context("commit", None, Some(&model));
+
+        let chunks = chunk_diff(&diff, MAX_DIFF_CHARS);
+        let changes = if chunks.len() == 1 {
+            format!("【改动】\n{}", diff)
+        } else {
+            let mut summaries = Vec::with_capacity(chunks.len());
+            for (i, chunk) in chunks.iter().enumerate() {
+                let prompt = format!("{}\n\n{}", SUMMARY_PROMPT, chunk);
+                let message = format!("AI 概括改动中（{}/{}）...", i + 1, chunks.len());
+                summaries.push(chat_with_retry(&mut context, &prompt, &message).await?);
+            }
+            format!(
+                "【改动概括】（改动较大，已分段概括）\n{}",
+                summaries.join("\n")
+            )
+        };
+        let mut prompt = format!(
+            "{}\n\n【改动统计】\n{}\n{}",
+            PROMPT,
+            stat.trim_end(),
+            changes
+        );
+        if !recent.trim().is_empty() {
+            prompt = format!("{}\n\n【最近的提交】\n{}", prompt, recent.trim_end());
+        }
+        let generated =
+            strip_fence(&chat_with_retry(&mut context, &prompt, "AI 生成提交信息中...").await?);
+
+        let (message, decision) = if self.yes {
+            println!("{}\n", generated);
+            (generated, AuditDecision::Auto)
+        } else {
+            let Some(edited) = edit_in_editor(&format!("{}\n{}", generated, EDITOR_HELP), ".txt")?
+            else {
+                println!("编辑器异常退出，已取消提交");
+                return Ok(());
+            };
+            let message = strip_comments(&edited);
+            let decision = if message == generated {
+                AuditDecision::Approved
+            } else {
+                AuditDecision::Edited
+            };
+            (message, decision)
+        };
+        if message.is_empty() {
+            println!("提交信息为空，已取消提交");
+            return Ok(());
+        }
+
+        // 提交信息从标准输入传给 git，不经过 shell，避免其中的引号、$ 等字符被解释
+        let input = format!("{}\n", message);
+        match capture_program(&["git", "commit", "-F", "-"], input.as_bytes(), decision)? {
+            Some((0, _)) => println!("✓ 已提交"),
+            Some((code, _)) => {
+                return Err(TaiError::Other(format!("git commit 失败，退出码 {}", code)))
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// 去掉模型可能加上的 ``` 包裹
+fn strip_fence(text: &str) -> String {
+    let text = text.trim();
+    match text.strip_prefix("```") {
+        Some(rest) => {
+            // 跳过语言标记所在的行
+            let body = rest.split_once('\n').map_or("", |(_, body)| body);
+            body.trim_end().trim_end_matches("```").trim().to_string()
+        }
+        None => text.to_string(),
+    }
+}
+
+/// 去掉编辑器中以 # 开头的说明行
+fn strip_comments(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_message() {
+        assert_eq!(
+            strip_fence("```text\nfeat(cli): add commit\n\n- body\n```"),
+            "feat(cli): add commit\n\n- body"
+        );
+        assert_eq!(strip_fence("fix: typo"), "fix: typo");
+        assert_eq!(
+            strip_comments(&format!("fix: typo\n{}", EDITOR_HELP)),
+            "fix: typo"
+        );
+    }
+}
@@ -1,31 +1,144 @@
-use tai_core::{TaiConfig, TaiError, TaiResult};
+use clap::{Args, Subcommand};
+use serde_json::Value;
+use std::path::PathBuf;
+use tai_ai::{providers_path, state_path, validate_providers_file, validate_state_file};
+use tai_core::schema::{Diagnostic, Severity};
+use tai_core::{
+    project_tai_dir, user_config_path, validate_config_file, ConfigOrigin, TaiConfig, TaiError,
+    TaiResult,
+};
 use tai_tui::{show_settings, SettingItem, SettingValue};
 use tracing::debug;
 
-pub struct ConfigCommand;
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub subcommand: Option<ConfigSubcommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigSubcommand {
+    /// 打印合并后的有效配置
+    Show {
+        /// 同时显示每一项的来源（默认值 / 用户配置 / 项目配置 / 环境变量）
+        #[arg(long)]
+        origin: bool,
+    },
+    /// 校验 config.json / providers.json / state.json，报告错误位置和修复建议
+    Validate,
+}
 
-impl ConfigCommand {
+impl ConfigArgs {
     pub async fn handle(self) -> TaiResult<()> {
-        let config = TaiConfig::load()?;
-        let items = config_to_items(&config);
-
-        match show_settings(items) {
-            Ok(Some(updated)) => {
-                let new_config = items_to_config(&config, &updated);
-                new_config.save()?;
-                debug!("配置已保存");
-                println!("  ✓ 配置已保存至 ~/.tai/config.json");
-            }
-            Ok(None) => {
-                debug!("用户取消了配置修改");
-            }
-            Err(e) => {
-                return Err(TaiError::Other(format!("TUI 错误: {}", e)));
-            }
+        match self.subcommand {
+            Some(ConfigSubcommand::Show { origin }) => show_config(origin),
+            Some(ConfigSubcommand::Validate) => validate_all(),
+            None => edit_config(),
+        }
+    }
+}
+
+fn show_config(with_origin: bool) -> TaiResult<()> {
+    let layered = TaiConfig::load_layered()?;
+    let entries = layered.entries();
+    let key_width = entries.iter().map(|(k, _, _)| k.len()).max().unwrap_or(0);
+
+    for (key, value, origin) in entries {
+        let value = match value {
+            Value::Null => "(未设置)".to_string(),
+            Value::String(s) => s,
+            other => other.to_string(),
+        };
+        if with_origin {
+            println!("{:<width$}  {}  [{}]", key, value, origin, width = key_width);
+        } else {
+            println!("{:<width$}  {}", key, value, width = key_width);
+        }
+    }
+    Ok(())
+}
+
+fn validate_all() -> TaiResult<()> {
+    let providers = providers_path();
+    let mut files: Vec<(PathBuf, Vec<Diagnostic>)> = Vec::new();
+
+    let user = user_config_path();
+    if user.exists() {
+        files.push((user.clone(), validate_config_file(&user)));
+    }
+    if let Some(project) = project_tai_dir().map(|d| d.join("config.json")) {
+        if project.exists() {
+            files.push((project.clone(), validate_config_file(&project)));
+        }
+    }
+    if providers.exists() {
+        files.push((providers.clone(), validate_providers_file(&providers)));
+    }
+    let state = state_path();
+    if state.exists() {
+        files.push((state.clone(), validate_state_file(&state, &providers)));
+    }
+
+    if files.is_empty() {
+        println!("未找到任何配置文件，将使用默认配置");
+        return Ok(());
+    }
+
+    let mut errors = 0;
+    for (path, diags) in &mut files {
+        diags.sort_by_key(|d| (d.line, d.column));
+        if diags.is_empty() {
+            println!("  ✓ {}", path.display());
+            continue;
         }
+        let file_errors = diags.iter().filter(|d| d.severity == Severity::Error).count();
+        errors += file_errors;
+        let mark = if file_errors > 0 { "✗" } else { "!" };
+        println!("  {} {}", mark, path.display());
+        for diag in diags {
+            println!("    {}", diag.to_string().replace('\n', "\n    "));
+        }
+    }
+
+    if errors > 0 {
+        return Err(TaiError::ConfigError(format!("发现 {} 个错误", errors)));
+    }
+    Ok(())
+}
 
-        Ok(())
+/// 交互式编辑用户级配置；项目配置和环境变量的覆盖不会被写回 ~/.tai/config.json
+fn edit_config() -> TaiResult<()> {
+    let config = TaiConfig::load_user()?;
+    let items = config_to_items(&config);
+
+    match show_settings(items) {
+        Ok(Some(updated)) => {
+            let new_config = items_to_config(&config, &updated);
+            new_config.save()?;
+            debug!("配置已保存");
+            println!("  ✓ 配置已保存至 ~/.tai/config.json");
+            print_overrides()?;
+        }
+        Ok(None) => {
+            debug!("用户取消了配置修改");
+        }
+        Err(e) => {
+            return Err(TaiError::Other(format!("TUI 错误: {}", e)));
+        }
+    }
+
+    Ok(())
+}
+
+/// 提示哪些配置项被项目配置或环境变量覆盖
+fn print_overrides() -> TaiResult<()> {
+    let layered = TaiConfig::load_layered()?;
+    for (key, origin) in &layered.origins {
+        if matches!(origin, ConfigOrigin::Project(_) | ConfigOrigin::Env(_)) {
+            println!("  注意: {} 当前被{}覆盖", key, origin);
+        }
     }
+    Ok(())
 }
 
 const THEMES: &[&str] = &["默认", "暗色", "亮色"];
@@ -0,0 +1,35 @@
+use tai_core::{diff_lines, hunks, DiffTag, Hunk, TaiError, TaiResult};
+use tai_tui::{print_diff, DiffKind, DiffRow};
+
+/// 差异预览中每个改动块前后保留的上下文行数
+const CONTEXT_LINES: usize = 3;
+
+/// 计算 old → new 的改动块
+pub fn file_hunks(old: &str, new: &str) -> Vec<Hunk> {
+    hunks(&diff_lines(old, new), CONTEXT_LINES)
+}
+
+/// 把改动块转换为预览行
+pub fn hunk_rows(hunk: &Hunk) -> Vec<DiffRow> {
+    let mut rows = vec![DiffRow::new(DiffKind::Header, hunk.header())];
+    rows.extend(hunk.lines.iter().map(|line| {
+        let kind = match line.tag {
+            DiffTag::Equal => DiffKind::Context,
+            DiffTag::Delete => DiffKind::Removed,
+            DiffTag::Insert => DiffKind::Added,
+        };
+        DiffRow::new(kind, line.text.as_str())
+    }));
+    rows
+}
+
+/// 打印 old → new 的差异预览，返回改动块数量
+pub fn show_diff(title: &str, old: &str, new: &str) -> TaiResult<usize> {
+    let hunks = file_hunks(old, new);
+    let rows: Vec<DiffRow> = hunks.iter().flat_map(hunk_rows).collect();
+    print_diff(title, &rows).map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+    if hunks.is_empty() {
+        println!("  （内容没有变化）");
+    }
+    Ok(hunks.len())
+}
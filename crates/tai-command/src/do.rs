@@ -1,21 +1,507 @@
 use clap::{Args, ValueHint};
-use tai_core::TaiResult;
-use tracing::debug;
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    fs,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
+use tai_core::{
+    analyze, audit, policy::real_path, risk, AuditDecision, RiskReport, TaiError, TaiResult,
+};
+use tai_tui::{edit_line, print_risk, select_action, ActionItem};
+use tracing::{debug, warn};
+
+use crate::diff::show_diff;
+use crate::editor::edit_in_editor;
+use crate::environment::EnvContext;
+use crate::fix::tail;
+use crate::go::{confirm_risk, risk_tone};
+use crate::provider::{chat_with_retry, ensure_active_provider};
+use crate::shell::{capture_with_shell, user_shell, write_file};
+
+const PROMPT: &str = "\
+你是一个在用户终端中分步完成任务的助手。你每次提出一个操作，用户批准后执行，执行结果会反馈给你，直到任务完成。
+
+【输出规范】
+每一轮只做一步，只返回一个 JSON 对象，不使用 ``` 包裹，不添加其他文字：
+执行命令：{\"thought\": \"对当前进度的判断和下一步打算\", \"action\": \"command\", \"command\": \"要执行的命令\"}
+写入文件：{\"thought\": \"...\", \"action\": \"write\", \"path\": \"相对当前目录的路径\", \"content\": \"文件的完整新内容\"}
+结束任务：{\"thought\": \"...\", \"action\": \"done\", \"summary\": \"完成情况总结\"}
+
+【规则】
+1. 命令在用户的 shell 中以非交互方式执行，你会收到退出码和输出（过长时只保留末尾）
+2. 修改文件前先用命令查看其内容；write 会用 content 覆盖整个文件，必须给出完整内容
+3. 用户可能拒绝或修改你的提议，请根据反馈调整方案，不要重复被拒绝的操作
+4. 不做与任务无关的修改；不执行删除大量文件、强制推送等危险操作，除非任务明确要求
+5. 任务完成、无法继续或需要用户决定时，返回 done 并在 summary 中说明
+";
+
+/// 每一步输出写入提示词的上限（字节），超出时只保留末尾
+const MAX_STEP_OUTPUT: usize = 3000;
+/// 单个 -f 文件写入提示词的上限（字节）
+const MAX_FILE_SIZE: usize = 100 * 1024;
 
 #[derive(Args, Debug)]
 pub struct DoArgs {
-    /// upload file
+    /// 附加为上下文的文件（可重复）
     #[arg(short, long, value_hint = ValueHint::FilePath)]
-    pub file: Option<String>,
+    pub file: Vec<String>,
+
+    /// 最多执行的步数
+    #[arg(long, default_value_t = 20)]
+    pub max_steps: usize,
 
     /// user requirement (if empty, enter editor)
     pub user_input: Option<String>,
 }
 
+/// 模型提出的一步操作
+#[derive(Debug, Clone)]
+enum Step {
+    Command(String),
+    Write { path: String, content: String },
+    Done(String),
+}
+
+/// 对提议的处理
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Approval {
+    Yes,
+    No,
+    Edit,
+    Always,
+    Abort,
+}
+
+const APPROVALS: &[(Approval, char, &str)] = &[
+    (Approval::Yes, 'y', "执行"),
+    (Approval::No, 'n', "拒绝"),
+    (Approval::Edit, 'e', "编辑"),
+    (Approval::Always, 'a', "本次会话总是允许"),
+    (Approval::Abort, 'q', "中止任务"),
+];
+
+/// 审核的结论
+enum Decision {
+    /// 执行（可能经过编辑）的操作，附带反馈给模型的说明和审计记录的批准方式
+    Run(Step, String, AuditDecision),
+    /// 未执行，附带原因
+    Skip(String),
+    Abort,
+}
+
+/// 已经过的一步：模型的想法、提议及执行结果，用于拼接下一轮提示词
+struct History {
+    thought: String,
+    proposal: String,
+    result: String,
+}
+
+/// 本次会话中用户选择“总是允许”的操作
+#[derive(Default)]
+struct SessionGrants {
+    /// 命令按程序名放行，如 cargo、git；命令启动的每个程序（包括 sudo、env 等前缀
+    /// 和 sh -c 中的命令）都已放行时才自动执行
+    programs: HashSet<String>,
+    /// 文件写入按路径放行
+    writes: HashSet<PathBuf>,
+}
+
+impl SessionGrants {
+    fn allows(&self, step: &Step) -> bool {
+        match step {
+            Step::Command(command) => risk::programs(command)
+                .is_some_and(|programs| programs.iter().all(|p| self.programs.contains(p))),
+            Step::Write { path, .. } => self.writes.contains(&write_key(path)),
+            Step::Done(_) => true,
+        }
+    }
+
+    fn grant(&mut self, step: &Step) {
+        match step {
+            Step::Command(command) => {
+                self.programs
+                    .extend(risk::programs(command).unwrap_or_default());
+            }
+            Step::Write { path, .. } => {
+                self.writes.insert(write_key(path));
+            }
+            Step::Done(_) => {}
+        }
+    }
+}
+
+/// “总是允许”选项的说明；命令无法按程序放行时返回 None，不提供该选项
+fn always_label(step: &Step) -> Option<String> {
+    match step {
+        Step::Command(command) => {
+            risk::programs(command).map(|programs| format!("总是允许 {}", programs.join("、")))
+        }
+        Step::Write { path, .. } => Some(format!("总是允许写入 {}", path)),
+        Step::Done(_) => None,
+    }
+}
+
+/// 写入按解析后的绝对路径放行，`./a.rs` 与 `a.rs` 视为同一文件
+fn write_key(path: &str) -> PathBuf {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    real_path(&cwd.join(path))
+}
+
 impl DoArgs {
     pub async fn handle(self) -> TaiResult<()> {
-        debug!("Do 命令被调用（功能开发中）");
-        println!("该功能开发中，请耐心等待更新");
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            return Err(TaiError::Other(
+                "tai do 的每一步都需要确认，请在终端中运行".to_string(),
+            ));
+        }
+        let task = match self.user_input {
+            Some(input) => input,
+            None => edit_in_editor("", ".md")?.unwrap_or_default(),
+        };
+        let task = task.trim().to_string();
+        if task.is_empty() {
+            return Err(TaiError::EmptyInput);
+        }
+        debug!("Do 命令: {}", task);
+
+        let files = self
+            .file
+            .iter()
+            .map(|path| read_context_file(path))
+            .collect::<TaiResult<Vec<String>>>()?;
+        let env = EnvContext::collect(None);
+        let mut base = format!("{}\n{}", PROMPT, env.to_prompt());
+        if !files.is_empty() {
+            base.push_str(&format!("\n\n【参考文件】\n{}", files.join("\n\n")));
+        }
+        base.push_str(&format!("\n\n【任务】\n{}", task));
+
+        let shell = user_shell();
+        let mut context = ensure_active_provider().await?;
+        let model = format!("{}/{}", context.0.provider, context.1);
+        audit::set_context("do", Some(&task), Some(&model));
+        let mut history: Vec<History> = Vec::new();
+        let mut grants = SessionGrants::default();
+
+        for index in 1..=self.max_steps {
+            let prompt = format!("{}{}", base, render_history(&history));
+            let text = chat_with_retry(&mut context, &prompt, "AI 规划中...").await?;
+            let Some((thought, step)) = parse_step(&text) else {
+                warn!("无法解析的回复: {}", text);
+                history.push(History {
+                    thought: String::new(),
+                    proposal: "（无效回复）".to_string(),
+                    result: "回复不是约定的 JSON 格式，请严格按输出规范返回".to_string(),
+                });
+                continue;
+            };
+            debug!("第 {} 步: {:?}", index, step);
+
+            println!();
+            println!("第 {}/{} 步", index, self.max_steps);
+            if !thought.is_empty() {
+                println!("  {}", thought);
+            }
+
+            let step = match step {
+                Step::Done(summary) => {
+                    println!("✓ 任务完成");
+                    if !summary.is_empty() {
+                        println!("{}", summary);
+                    }
+                    return Ok(());
+                }
+                step => step,
+            };
+
+            let (proposal, result) = match review(&step, &mut grants)? {
+                Decision::Run(step, note, decision) => {
+                    let result = match execute(&step, &shell, decision).await {
+                        // 被策略拒绝的操作反馈给模型，让它换一种做法
+                        Err(TaiError::PolicyViolation(reason)) => {
+                            println!("  ✗ 执行策略禁止: {}", reason);
+                            format!("被执行策略拒绝: {}", reason)
+                        }
+                        result => result?,
+                    };
+                    (describe(&step), format!("{}{}", note, result))
+                }
+                Decision::Skip(note) => (describe(&step), note),
+                Decision::Abort => {
+                    println!("已中止任务");
+                    return Ok(());
+                }
+            };
+            history.push(History {
+                thought,
+                proposal,
+                result,
+            });
+        }
+
+        println!("已达到最大步数 {}，任务未完成", self.max_steps);
         Ok(())
     }
 }
+
+/// 显示提议并请求批准
+fn review(step: &Step, grants: &mut SessionGrants) -> TaiResult<Decision> {
+    let report = preview(step)?;
+    let high = report.as_ref().is_some_and(|r| r.is_high());
+
+    // 高风险命令即使已放行同类操作也必须逐次确认
+    if grants.allows(step) && !high {
+        println!("  （本次会话已允许，自动执行）");
+        return Ok(Decision::Run(step.clone(), String::new(), AuditDecision::Session));
+    }
+
+    let always = always_label(step);
+    let options: Vec<&(Approval, char, &str)> = APPROVALS
+        .iter()
+        .filter(|(approval, ..)| *approval != Approval::Always || always.is_some())
+        .collect();
+    let items: Vec<ActionItem> = options
+        .iter()
+        .map(|(approval, key, label)| match (approval, &always) {
+            (Approval::Always, Some(always)) => ActionItem::new(*key, always.clone()),
+            _ => ActionItem::new(*key, *label),
+        })
+        .collect();
+    let choice = select_action(&items)
+        .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?
+        .map(|i| options[i].0)
+        .unwrap_or(Approval::Abort);
+
+    let confirmed = |step: Step| -> TaiResult<Decision> {
+        match &report {
+            Some(report) if !confirm_risk(report, "执行")? => {
+                Ok(Decision::Skip("用户拒绝了这一高风险操作。".to_string()))
+            }
+            _ => Ok(Decision::Run(step, String::new(), AuditDecision::Approved)),
+        }
+    };
+
+    match choice {
+        Approval::Yes => confirmed(step.clone()),
+        Approval::Always => {
+            let decision = confirmed(step.clone())?;
+            // 高风险确认被拒绝时不放行
+            if matches!(decision, Decision::Run(..)) {
+                grants.grant(step);
+            }
+            Ok(decision)
+        }
+        Approval::No => {
+            let reason = edit_line("拒绝原因（可留空）", "")
+                .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?
+                .unwrap_or_default();
+            let note = if reason.trim().is_empty() {
+                "用户拒绝了这一操作。".to_string()
+            } else {
+                format!("用户拒绝了这一操作，原因：{}", reason.trim())
+            };
+            Ok(Decision::Skip(note))
+        }
+        Approval::Edit => {
+            let edited = match step {
+                Step::Command(command) => edit_line("编辑命令", command)
+                    .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?
+                    .map(Step::Command),
+                Step::Write { path, content } => {
+                    let suffix = Path::new(path)
+                        .extension()
+                        .map(|e| format!(".{}", e.to_string_lossy()))
+                        .unwrap_or_default();
+                    edit_in_editor(content, &suffix)?.map(|content| Step::Write {
+                        path: path.clone(),
+                        content,
+                    })
+                }
+                Step::Done(_) => None,
+            };
+            match edited {
+                Some(edited) => {
+                    if let Step::Command(command) = &edited {
+                        let report = analyze(command);
+                        if !confirm_risk(&report, "执行")? {
+                            return Ok(Decision::Skip("用户拒绝了这一高风险操作。".to_string()));
+                        }
+                    }
+                    let note = format!("用户修改后执行：{}\n", describe(&edited));
+                    Ok(Decision::Run(edited, note, AuditDecision::Edited))
+                }
+                None => Ok(Decision::Skip("用户取消了编辑，未执行。".to_string())),
+            }
+        }
+        Approval::Abort => Ok(Decision::Abort),
+    }
+}
+
+/// 显示命令及风险，或文件改动的差异；命令返回其风险分析结果
+fn preview(step: &Step) -> TaiResult<Option<RiskReport>> {
+    match step {
+        Step::Command(command) => {
+            println!("$ {}", command);
+            let report = analyze(command);
+            let reasons: Vec<String> = report.findings.iter().map(|f| f.reason.clone()).collect();
+            print_risk(risk_tone(&report), report.level().label(), &reasons)
+                .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+            Ok(Some(report))
+        }
+        Step::Write { path, content } => {
+            let old = fs::read_to_string(path).ok();
+            let title = match old {
+                Some(_) => format!("修改 {}", path),
+                None => format!("新建 {}", path),
+            };
+            show_diff(&title, old.as_deref().unwrap_or_default(), content)?;
+            Ok(None)
+        }
+        Step::Done(_) => Ok(None),
+    }
+}
+
+/// 执行操作并返回反馈给模型的结果
+async fn execute(step: &Step, shell: &str, decision: AuditDecision) -> TaiResult<String> {
+    match step {
+        Step::Command(command) => {
+            let Some((code, output)) = capture_with_shell(shell, command, decision).await? else {
+                return Ok("dry-run 模式，命令未执行".to_string());
+            };
+            if code == 0 {
+                println!("  ✓ 执行完成");
+            } else {
+                println!("  ✗ 退出码 {}", code);
+            }
+            let output = tail(&output, MAX_STEP_OUTPUT);
+            Ok(if output.is_empty() {
+                format!("退出码 {}，无输出", code)
+            } else {
+                format!("退出码 {}，输出：\n{}", code, output)
+            })
+        }
+        Step::Write { path, content } => match write_file(path, content, decision) {
+            Ok(true) => {
+                println!("  ✓ 已写入 {}", path);
+                Ok(format!("已写入 {}", path))
+            }
+            Ok(false) => Ok(format!("dry-run 模式，未写入 {}", path)),
+            Err(e @ TaiError::PolicyViolation(_)) => Err(e),
+            // 写入失败反馈给模型，而不是中断整个任务
+            Err(e) => {
+                println!("  ✗ {}", e);
+                Ok(format!("写入 {} 失败: {}", path, e))
+            }
+        },
+        Step::Done(_) => Ok(String::new()),
+    }
+}
+
+fn describe(step: &Step) -> String {
+    match step {
+        Step::Command(command) => format!("执行命令 `{}`", command),
+        Step::Write { path, .. } => format!("写入文件 {}", path),
+        Step::Done(_) => "无".to_string(),
+    }
+}
+
+fn render_history(history: &[History]) -> String {
+    if history.is_empty() {
+        return String::new();
+    }
+    let mut text = "\n\n【已执行的步骤】".to_string();
+    for (i, step) in history.iter().enumerate() {
+        text.push_str(&format!("\n第 {} 步", i + 1));
+        if !step.thought.is_empty() {
+            text.push_str(&format!("\n想法：{}", step.thought));
+        }
+        text.push_str(&format!(
+            "\n操作：{}\n结果：{}\n",
+            step.proposal, step.result
+        ));
+    }
+    text.push_str("\n请给出下一步。");
+    text
+}
+
+/// 解析模型的 JSON 回复，返回 (thought, step)
+fn parse_step(text: &str) -> Option<(String, Step)> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    let value: Value = serde_json::from_str(text.get(start..=end)?).ok()?;
+    let field = |name: &str| value.get(name).and_then(Value::as_str).unwrap_or_default();
+
+    let step = match field("action") {
+        "command" if !field("command").trim().is_empty() => {
+            Step::Command(field("command").trim().to_string())
+        }
+        "write" if !field("path").trim().is_empty() => Step::Write {
+            path: field("path").trim().to_string(),
+            content: field("content").to_string(),
+        },
+        "done" => Step::Done(field("summary").trim().to_string()),
+        _ => return None,
+    };
+    Some((field("thought").trim().to_string(), step))
+}
+
+fn read_context_file(path: &str) -> TaiResult<String> {
+    let content =
+        fs::read(path).map_err(|e| TaiError::FileError(format!("无法读取 {}: {}", path, e)))?;
+    let truncated = content.len() > MAX_FILE_SIZE;
+    let content =
+        String::from_utf8_lossy(&content[..content.len().min(MAX_FILE_SIZE)]).into_owned();
+    debug!("附加文件: {} ({} 字节)", path, content.len());
+    Ok(format!(
+        "文件 {}{}：\n```\n{}\n```",
+        path,
+        if truncated { "（已截断）" } else { "" },
+        content
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_step() {
+        let (thought, step) =
+            parse_step(r#"{"thought": "先看看", "action": "command", "command": "ls src"}"#)
+                .unwrap();
+        assert_eq!(thought, "先看看");
+        assert!(matches!(step, Step::Command(c) if c == "ls src"));
+
+        let (_, step) = parse_step(
+            "```json\n{\"action\": \"write\", \"path\": \"a.txt\", \"content\": \"hi\\n\"}\n```",
+        )
+        .unwrap();
+        assert!(
+            matches!(step, Step::Write { path, content } if path == "a.txt" && content == "hi\n")
+        );
+
+        assert!(parse_step(r#"{"action": "command", "command": ""}"#).is_none());
+    }
+
+    #[test]
+    fn test_session_grants_cover_every_program_and_path() {
+        let command = |c: &str| Step::Command(c.to_string());
+        let write = |p: &str| Step::Write {
+            path: p.to_string(),
+            content: String::new(),
+        };
+        let mut grants = SessionGrants::default();
+        grants.grant(&command("RUST_LOG=debug cargo test"));
+        grants.grant(&write("src/lib.rs"));
+
+        assert!(grants.allows(&command("cargo build --release")));
+        assert!(!grants.allows(&command("cargo build && rm -rf src")));
+        assert!(!grants.allows(&command("env cargo build")));
+        assert!(!grants.allows(&command("bash -c 'cargo build'")));
+        assert!(grants.allows(&write("./src/lib.rs")));
+        assert!(!grants.allows(&write("src/main.rs")));
+        assert!(!grants.allows(&write("~/.bashrc")));
+    }
+}
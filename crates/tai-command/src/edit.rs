@@ -0,0 +1,314 @@
+use clap::{Args, ValueHint};
+use std::{fs, io::IsTerminal};
+use tai_core::{apply_hunks, audit, AuditDecision, TaiError, TaiResult};
+use tai_tui::{print_diff, select_action, ActionItem};
+use tracing::{debug, warn};
+
+use crate::diff::{file_hunks, hunk_rows, show_diff};
+use crate::editor::edit_in_editor;
+use crate::provider::{chat_with_retry, ensure_active_provider};
+use crate::shell::write_file;
+
+const PROMPT: &str = "\
+你是一名代码编辑助手。请按用户的要求修改下面的文件，只输出 SEARCH/REPLACE 块，不要输出解释或完整文件。
+
+每个块的格式：
+<<<<<<< SEARCH
+文件中原有的若干行（逐字符一致，包括缩进和空白）
+=======
+替换后的内容
+>>>>>>> REPLACE
+
+规则：
+1. SEARCH 部分必须与文件中的内容完全一致，并且在文件中只出现一次；需要时多包含几行上下文
+2. 每个块只覆盖需要修改的部分，多处修改使用多个块，按在文件中出现的顺序排列
+3. 删除代码时 REPLACE 部分留空
+4. 不要修改与要求无关的内容";
+
+/// 只编辑不超过该大小的文件（字节）
+const MAX_FILE_SIZE: usize = 256 * 1024;
+
+#[derive(Args, Debug)]
+pub struct EditArgs {
+    /// 要修改的文件
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub file: String,
+
+    /// 修改要求（为空时打开编辑器输入）
+    pub instruction: Option<String>,
+
+    /// 模型的修改无法应用时，最多请求的次数
+    #[arg(long, default_value_t = 3)]
+    pub max_attempts: usize,
+
+    /// 不逐块确认，接受全部改动
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+/// 一个 SEARCH/REPLACE 块
+#[derive(Debug, PartialEq)]
+struct Replacement {
+    search: String,
+    replace: String,
+}
+
+#[derive(Clone, Copy)]
+enum Review {
+    Accept,
+    Reject,
+    AcceptRest,
+    RejectRest,
+    Quit,
+}
+
+const REVIEWS: &[(Review, char, &str)] = &[
+    (Review::Accept, 'y', "接受"),
+    (Review::Reject, 'n', "拒绝"),
+    (Review::AcceptRest, 'a', "接受剩余全部"),
+    (Review::RejectRest, 'd', "拒绝剩余全部"),
+    (Review::Quit, 'q', "放弃修改"),
+];
+
+impl EditArgs {
+    pub async fn handle(self) -> TaiResult<()> {
+        if !self.yes && !std::io::stdin().is_terminal() {
+            return Err(TaiError::Other(
+                "逐块确认需要在终端中运行，或使用 --yes 接受全部改动".to_string(),
+            ));
+        }
+        let bytes = fs::read(&self.file)
+            .map_err(|e| TaiError::FileError(format!("无法读取 {}: {}", self.file, e)))?;
+        if bytes.len() > MAX_FILE_SIZE || bytes.contains(&0) {
+            return Err(TaiError::FileError(format!(
+                "{} 不是文本文件或超过 {} KB",
+                self.file,
+                MAX_FILE_SIZE / 1024
+            )));
+        }
+        let original = String::from_utf8(bytes)
+            .map_err(|_| TaiError::FileError(format!("{} 不是 UTF-8 文本", self.file)))?;
+        let instruction = match self.instruction {
+            Some(text) => text,
+            None => edit_in_editor("", ".md")?.unwrap_or_default(),
+        };
+        let instruction = instruction.trim().to_string();
+        if instruction.is_empty() {
+            return Err(TaiError::EmptyInput);
+        }
+        debug!("Edit 命令: {} {}", self.file, instruction);
+
+        let mut context = ensure_active_provider().await?;
+        let model = format!("{}/{}", context.0.provider, context.1);
+        audit::set_context("edit", Some(&instruction), Some(&model));
+
+        // SEARCH/REPLACE 在 \n 换行的文本上进行，apply_hunks 写回时恢复原来的换行风格
+        let content = original.replace("\r\n", "\n");
+        let base = format!(
+            "{}\n\n文件 {}：\n```\n{}\n```\n\n【修改要求】\n{}",
+            PROMPT, self.file, content, instruction
+        );
+        let mut prompt = base.clone();
+        let mut updated = None;
+        for attempt in 1..=self.max_attempts.max(1) {
+            let text = chat_with_retry(&mut context, &prompt, "AI 生成修改中...").await?;
+            match parse_replacements(&text).and_then(|blocks| apply_replacements(&content, &blocks))
+            {
+                Ok(new) => {
+                    updated = Some(new);
+                    break;
+                }
+                Err(reason) => {
+                    warn!("第 {} 次生成的修改无法应用: {}", attempt, reason);
+                    println!("  ✗ 修改无法应用: {}", reason);
+                    prompt = format!(
+                        "{}\n\n【上一次的回复】\n{}\n\n上一次的回复无法应用：{}\n请重新输出正确的 SEARCH/REPLACE 块。",
+                        base, text, reason
+                    );
+                }
+            }
+        }
+        let Some(updated) = updated else {
+            return Err(TaiError::Other(format!(
+                "{} 次尝试后仍无法得到可应用的修改",
+                self.max_attempts.max(1)
+            )));
+        };
+
+        let hunks = file_hunks(&content, &updated);
+        let accepted = if self.yes {
+            show_diff(&self.file, &content, &updated)?;
+            vec![true; hunks.len()]
+        } else {
+            match review_hunks(&self.file, &hunks)? {
+                Some(accepted) => accepted,
+                None => {
+                    println!("已放弃修改");
+                    return Ok(());
+                }
+            }
+        };
+
+        let count = accepted.iter().filter(|&&ok| ok).count();
+        if count == 0 {
+            println!("没有接受任何改动，文件未修改");
+            return Ok(());
+        }
+        let decision = if self.yes {
+            AuditDecision::Auto
+        } else if count < hunks.len() {
+            AuditDecision::Edited
+        } else {
+            AuditDecision::Approved
+        };
+        let result = apply_hunks(&original, &hunks, &accepted);
+        if write_file(&self.file, &result, decision)? {
+            println!(
+                "✓ 已写入 {}（接受 {}/{} 处改动）",
+                self.file,
+                count,
+                hunks.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// 逐块显示改动并询问是否接受；选择放弃时返回 None
+fn review_hunks(file: &str, hunks: &[tai_core::Hunk]) -> TaiResult<Option<Vec<bool>>> {
+    let items: Vec<ActionItem> = REVIEWS
+        .iter()
+        .map(|(_, key, label)| ActionItem::new(*key, *label))
+        .collect();
+    let mut accepted = Vec::with_capacity(hunks.len());
+    for (i, hunk) in hunks.iter().enumerate() {
+        println!();
+        print_diff(
+            &format!("{}（{}/{}）", file, i + 1, hunks.len()),
+            &hunk_rows(hunk),
+        )
+        .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+        let choice = select_action(&items)
+            .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?
+            .map(|i| REVIEWS[i].0)
+            .unwrap_or(Review::Quit);
+        match choice {
+            Review::Accept => accepted.push(true),
+            Review::Reject => accepted.push(false),
+            Review::AcceptRest => accepted.resize(hunks.len(), true),
+            Review::RejectRest => accepted.resize(hunks.len(), false),
+            Review::Quit => return Ok(None),
+        }
+        if accepted.len() == hunks.len() {
+            break;
+        }
+    }
+    Ok(Some(accepted))
+}
+
+/// 从回复中解析 SEARCH/REPLACE 块
+fn parse_replacements(text: &str) -> Result<Vec<Replacement>, String> {
+    let mut blocks = Vec::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        if !line.trim_start().starts_with("<<<<<<< SEARCH") {
+            continue;
+        }
+        let mut search = Vec::new();
+        let mut replace = Vec::new();
+        let mut in_replace = false;
+        let mut closed = false;
+        for line in lines.by_ref() {
+            let marker = line.trim();
+            if !in_replace && marker == "=======" {
+                in_replace = true;
+            } else if in_replace && marker.starts_with(">>>>>>> REPLACE") {
+                closed = true;
+                break;
+            } else if in_replace {
+                replace.push(line);
+            } else {
+                search.push(line);
+            }
+        }
+        if !closed {
+            return Err(format!(
+                "第 {} 个块没有以 >>>>>>> REPLACE 结束",
+                blocks.len() + 1
+            ));
+        }
+        blocks.push(Replacement {
+            search: search.join("\n"),
+            replace: replace.join("\n"),
+        });
+    }
+    if blocks.is_empty() {
+        return Err("回复中没有 SEARCH/REPLACE 块".to_string());
+    }
+    Ok(blocks)
+}
+
+/// 依次应用替换；SEARCH 找不到、不唯一或结果没有变化时返回原因
+fn apply_replacements(content: &str, blocks: &[Replacement]) -> Result<String, String> {
+    let mut result = content.to_string();
+    for (i, block) in blocks.iter().enumerate() {
+        if block.search.trim().is_empty() {
+            // 空文件或只追加内容时允许空的 SEARCH
+            if !result.trim().is_empty() {
+                return Err(format!("第 {} 个块的 SEARCH 为空", i + 1));
+            }
+            result = format!("{}\n", block.replace);
+            continue;
+        }
+        // 删除整行时连同行尾换行一起删除，不留下空行
+        let search = match block.search.clone() + "\n" {
+            line if block.replace.is_empty() && result.contains(&line) => line,
+            _ => block.search.clone(),
+        };
+        match result.matches(&search).count() {
+            0 => {
+                return Err(format!(
+                    "第 {} 个块的 SEARCH 内容在文件中找不到，请逐字符复制原文：\n{}",
+                    i + 1,
+                    block.search
+                ))
+            }
+            1 => result = result.replacen(&search, &block.replace, 1),
+            n => {
+                return Err(format!(
+                    "第 {} 个块的 SEARCH 内容在文件中出现了 {} 次，请包含更多上下文使其唯一",
+                    i + 1,
+                    n
+                ))
+            }
+        }
+    }
+    if result == content {
+        return Err("修改没有产生任何变化".to_string());
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_apply_replacements() {
+        let content = "fn main() {\n    println!(\"hi\");\n}\n";
+        let reply = "好的：\n```\n<<<<<<< SEARCH\n    println!(\"hi\");\n=======\n    println!(\"hello\");\n    println!(\"world\");\n>>>>>>> REPLACE\n```";
+        let blocks = parse_replacements(reply).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(
+            apply_replacements(content, &blocks).unwrap(),
+            "fn main() {\n    println!(\"hello\");\n    println!(\"world\");\n}\n"
+        );
+
+        let missing =
+            parse_replacements("<<<<<<< SEARCH\nfoo\n=======\nbar\n>>>>>>> REPLACE").unwrap();
+        assert!(apply_replacements(content, &missing)
+            .unwrap_err()
+            .contains("找不到"));
+        assert!(parse_replacements("没有改动").is_err());
+    }
+}
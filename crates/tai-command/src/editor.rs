@@ -0,0 +1,45 @@
+use std::{
+    fs,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tai_core::{TaiError, TaiResult};
+use tracing::debug;
+
+/// 用 $VISUAL / $EDITOR 编辑文本；编辑器异常退出时返回 None
+///
+/// suffix 为临时文件的扩展名（如 ".md"），便于编辑器选择语法高亮
+pub fn edit_in_editor(initial: &str, suffix: &str) -> TaiResult<Option<String>> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    let path = std::env::temp_dir().join(format!("tai-{}-{}{}", std::process::id(), nanos, suffix));
+    fs::write(&path, initial)
+        .map_err(|e| TaiError::FileError(format!("无法写入临时文件 {:?}: {}", path, e)))?;
+
+    // $EDITOR 可能带参数，如 "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    debug!("打开编辑器: {} {:?}", editor, path);
+    let status = Command::new(program).args(parts).arg(&path).status();
+
+    let result = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path)
+            .map(Some)
+            .map_err(|e| TaiError::FileError(format!("无法读取临时文件 {:?}: {}", path, e))),
+        Ok(status) => {
+            debug!("编辑器退出状态: {}", status);
+            Ok(None)
+        }
+        Err(e) => Err(TaiError::Other(format!("无法启动编辑器 {}: {}", editor, e))),
+    };
+    let _ = fs::remove_file(&path);
+    result
+}
@@ -0,0 +1,183 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::debug;
+
+use crate::shell::{user_shell, ShellKind};
+
+/// 生成命令时值得告诉模型的常用工具
+const TOOLS: &[&str] = &[
+    "fd", "rg", "jq", "yq", "fzf", "bat", "eza", "git", "docker", "kubectl", "python3", "node",
+    "gsed", "gawk", "curl", "wget",
+];
+
+/// 本地采集的运行环境，用于让生成的命令贴合用户的 shell 与系统
+#[derive(Debug, Clone)]
+pub struct EnvContext {
+    /// shell 名称，如 bash / zsh / fish / powershell
+    pub shell: String,
+    /// uname -srm，非 Unix 下为 std::env::consts::OS
+    pub os: String,
+    /// /etc/os-release 中的 PRETTY_NAME
+    pub distro: Option<String>,
+    pub tools: Vec<&'static str>,
+    pub cwd: PathBuf,
+    pub in_git_repo: bool,
+}
+
+impl EnvContext {
+    /// 采集当前环境；shell 优先使用 `--shell` 指定的值
+    pub fn collect(shell_override: Option<ShellKind>) -> Self {
+        let shell = match shell_override {
+            Some(kind) => kind.name().to_string(),
+            None => detect_shell(),
+        };
+        let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let context = Self {
+            shell,
+            os: uname(),
+            distro: os_release(),
+            tools: TOOLS.iter().copied().filter(|t| on_path(t)).collect(),
+            in_git_repo: cwd.ancestors().any(|dir| dir.join(".git").exists()),
+            cwd,
+        };
+        debug!("运行环境: {:?}", context);
+        context
+    }
+
+    /// 渲染为提示词中的【运行环境】段落
+    pub fn to_prompt(&self) -> String {
+        let mut lines = vec!["【运行环境】".to_string()];
+        lines.push(format!("- Shell: {}", self.shell));
+        match &self.distro {
+            Some(distro) => lines.push(format!("- 系统: {} ({})", self.os, distro)),
+            None => lines.push(format!("- 系统: {}", self.os)),
+        }
+        if let Some(userland) = self.userland_hint() {
+            lines.push(format!("- {}", userland));
+        }
+        if let Some(hint) = self.shell_hint() {
+            lines.push(format!("- {}", hint));
+        }
+        if self.tools.is_empty() {
+            lines.push("- 已安装的常用工具: 无".to_string());
+        } else {
+            lines.push(format!("- 已安装的常用工具: {}", self.tools.join(", ")));
+        }
+        lines.push(format!("- 当前目录: {}", self.cwd.display()));
+        lines.push(format!(
+            "- Git 仓库: {}",
+            if self.in_git_repo { "是" } else { "否" }
+        ));
+        lines.push(
+            "生成的命令必须能在上述 shell 和系统中直接运行，只使用已安装的工具。".to_string(),
+        );
+        lines.join("\n")
+    }
+
+    fn userland_hint(&self) -> Option<&'static str> {
+        let os = self.os.to_ascii_lowercase();
+        if os.starts_with("darwin") || os.contains("bsd") || os == "macos" {
+            Some("用户态为 BSD 工具集：sed -i 需要 '' 参数，不支持 GNU 长选项")
+        } else if os.starts_with("linux") {
+            Some("用户态为 GNU coreutils")
+        } else {
+            None
+        }
+    }
+
+    fn shell_hint(&self) -> Option<&'static str> {
+        match self.shell.as_str() {
+            "bash" => Some("bash 默认未开启 globstar，不要使用 ** 递归匹配"),
+            "fish" => {
+                Some("fish 语法：用 (cmd) 代替 $(cmd)，用 set 设置变量，不支持 [[ ]] 和 heredoc")
+            }
+            "powershell" | "pwsh" => Some("使用 PowerShell cmdlet 语法，而非 Unix 命令"),
+            _ => None,
+        }
+    }
+}
+
+fn detect_shell() -> String {
+    let shell = user_shell();
+    let name = Path::new(&shell)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(&shell)
+        .to_ascii_lowercase();
+    match name.as_str() {
+        "pwsh" => "powershell".to_string(),
+        _ => name,
+    }
+}
+
+fn uname() -> String {
+    if cfg!(unix) {
+        if let Ok(output) = Command::new("uname").arg("-srm").output() {
+            let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if output.status.success() && !text.is_empty() {
+                return text;
+            }
+        }
+    }
+    format!("{} {}", env::consts::OS, env::consts::ARCH)
+}
+
+fn os_release() -> Option<String> {
+    let content = fs::read_to_string("/etc/os-release").ok()?;
+    content.lines().find_map(|line| {
+        line.strip_prefix("PRETTY_NAME=")
+            .map(|v| v.trim_matches('"').to_string())
+    })
+}
+
+fn on_path(tool: &str) -> bool {
+    let Some(path) = env::var_os("PATH") else {
+        return false;
+    };
+    env::split_paths(&path).any(|dir| {
+        let candidate = dir.join(tool);
+        candidate.is_file() || (cfg!(windows) && dir.join(format!("{}.exe", tool)).is_file())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(shell: &str, os: &str) -> EnvContext {
+        EnvContext {
+            shell: shell.to_string(),
+            os: os.to_string(),
+            distro: None,
+            tools: vec!["rg", "git"],
+            cwd: PathBuf::from("/work"),
+            in_git_repo: true,
+        }
+    }
+
+    #[test]
+    fn test_prompt_hints() {
+        let prompt = context("fish", "Darwin 23.4.0 arm64").to_prompt();
+        assert!(prompt.contains("- Shell: fish"));
+        assert!(prompt.contains("BSD 工具集"));
+        assert!(prompt.contains("fish 语法"));
+        assert!(prompt.contains("- 已安装的常用工具: rg, git"));
+        assert!(prompt.contains("- Git 仓库: 是"));
+
+        let mut linux = context("zsh", "Linux 6.8.0 x86_64");
+        linux.distro = Some("Ubuntu 24.04 LTS".to_string());
+        linux.tools.clear();
+        let prompt = linux.to_prompt();
+        assert!(prompt.contains("- 系统: Linux 6.8.0 x86_64 (Ubuntu 24.04 LTS)"));
+        assert!(prompt.contains("GNU coreutils"));
+        assert!(prompt.contains("- 已安装的常用工具: 无"));
+        assert_eq!(linux.shell_hint(), None);
+
+        let windows = context("pwsh", "windows x86_64");
+        assert_eq!(windows.userland_hint(), None);
+        assert!(windows.shell_hint().unwrap().contains("PowerShell"));
+    }
+}
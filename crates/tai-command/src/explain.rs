@@ -0,0 +1,165 @@
+use clap::Args;
+use serde_json::Value;
+use std::io::{IsTerminal, Read};
+use tai_core::{analyze, TaiError, TaiResult};
+use tai_tui::{print_annotated, print_risk, Annotation, TokenKind};
+use tracing::{debug, warn};
+
+use crate::go::risk_tone;
+use crate::provider::{chat_with_retry, ensure_active_provider};
+
+const PROMPT: &str = "\
+你是一名 shell 专家，请逐个片段解释用户给出的命令。
+
+【输出格式】
+只返回一个 JSON 对象，不使用 ``` 包裹，不添加其他文字：
+{\"summary\": \"一到两句话说明整条命令的作用\",
+ \"parts\": [{\"text\": \"片段原文\", \"kind\": \"类别\", \"explanation\": \"该片段的含义\"}]}
+
+【拆分规则】
+1. parts 按片段在命令中出现的顺序排列，text 必须与命令中的原文完全一致
+2. kind 取值：program（程序或子命令名）、flag（选项及其值）、argument（参数）、\
+pipe（管道 |）、redirect（重定向及其目标）、operator（&& || ; & 等控制符）、subshell（$(...)、反引号、<(...)）
+3. 每个选项单独成为一个片段；选项的取值与选项合并，如 \"-name '*.rs'\"
+4. 管道的每一段都要拆到程序和选项级别
+5. explanation 使用中文，简洁说明作用；如有删除、覆盖、提权等风险请在对应片段中指出
+
+【命令】
+";
+
+#[derive(Args, Debug)]
+pub struct ExplainArgs {
+    /// 要解释的命令（为空时从标准输入读取）
+    pub command: Option<String>,
+}
+
+impl ExplainArgs {
+    pub async fn handle(self) -> TaiResult<()> {
+        let command = match self.command {
+            Some(command) => command,
+            None if !std::io::stdin().is_terminal() => {
+                let mut input = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut input)
+                    .map_err(|e| TaiError::Other(format!("无法读取标准输入: {}", e)))?;
+                input
+            }
+            None => return Err(TaiError::EmptyInput),
+        };
+        let command = command.trim().to_string();
+        if command.is_empty() {
+            return Err(TaiError::EmptyInput);
+        }
+        debug!("Explain 命令: {}", command);
+
+        let mut context = ensure_active_provider().await?;
+        let prompt = format!("{}{}", PROMPT, command);
+        let response = chat_with_retry(&mut context, &prompt, "AI 分析中...").await?;
+
+        match parse_explanation(&response) {
+            Some((summary, annotations)) => {
+                print_annotated(&command, &summary, &annotations)
+                    .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+            }
+            None => {
+                // 模型未按格式返回时直接显示原文
+                warn!("无法解析结构化解释: {}", response);
+                println!("{}", response.trim());
+            }
+        }
+
+        // 本地规则分析，不依赖模型是否指出风险
+        let report = analyze(&command);
+        let reasons: Vec<String> = report.findings.iter().map(|f| f.reason.clone()).collect();
+        println!();
+        print_risk(risk_tone(&report), report.level().label(), &reasons)
+            .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+
+        debug!("Explain 命令完成");
+        Ok(())
+    }
+}
+
+/// 解析 `{"summary": ..., "parts": [...]}`，容忍前后多余的文字或代码块
+fn parse_explanation(text: &str) -> Option<(String, Vec<Annotation>)> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    let value: Value = serde_json::from_str(text.get(start..=end)?).ok()?;
+
+    let summary = value
+        .get("summary")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    let annotations: Vec<Annotation> = value
+        .get("parts")?
+        .as_array()?
+        .iter()
+        .filter_map(|part| {
+            let text = part.get("text")?.as_str()?.trim();
+            let kind = match part.get("kind").and_then(Value::as_str).unwrap_or_default() {
+                "program" => TokenKind::Program,
+                "flag" => TokenKind::Flag,
+                "pipe" => TokenKind::Pipe,
+                "redirect" => TokenKind::Redirect,
+                "operator" => TokenKind::Operator,
+                "subshell" => TokenKind::Subshell,
+                _ => TokenKind::Argument,
+            };
+            let explanation = part
+                .get("explanation")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .trim();
+            (!text.is_empty()).then(|| Annotation::new(text, kind, explanation))
+        })
+        .collect();
+
+    (!annotations.is_empty()).then_some((summary, annotations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_explanation() {
+        let (summary, annotations) = parse_explanation(
+            "```json\n{\"summary\": \"列出文件\", \"parts\": [\
+             {\"text\": \"ls\", \"kind\": \"program\", \"explanation\": \"列出目录\"},\
+             {\"text\": \"-la\", \"kind\": \"flag\", \"explanation\": \"显示隐藏文件\"},\
+             {\"text\": \"|\", \"kind\": \"pipe\"},\
+             {\"text\": \"wc\", \"kind\": \"unknown\"}]}\n```",
+        )
+        .unwrap();
+        assert_eq!(summary, "列出文件");
+        let kinds: Vec<TokenKind> = annotations.iter().map(|a| a.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Program,
+                TokenKind::Flag,
+                TokenKind::Pipe,
+                TokenKind::Argument
+            ]
+        );
+        assert_eq!(annotations[1].explanation, "显示隐藏文件");
+        assert_eq!(annotations[2].explanation, "");
+
+        let (summary, annotations) = parse_explanation(
+            "解释如下：{\"parts\": [{\"text\": \"pwd\", \"kind\": \"program\"}]} 希望有帮助",
+        )
+        .unwrap();
+        assert_eq!(summary, "");
+        assert_eq!(annotations[0].text, "pwd");
+    }
+
+    #[test]
+    fn test_parse_explanation_rejects_malformed() {
+        assert!(parse_explanation("ls 用于列出文件").is_none());
+        assert!(parse_explanation("{\"summary\": \"列出文件\", \"parts\": [").is_none());
+        assert!(parse_explanation("{\"summary\": \"列出文件\"}").is_none());
+        assert!(parse_explanation("{\"summary\": \"x\", \"parts\": [{\"text\": \"\"}]}").is_none());
+    }
+}
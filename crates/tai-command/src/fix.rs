@@ -0,0 +1,300 @@
+use clap::Args;
+use std::{env, fs, io::IsTerminal, path::PathBuf};
+use tai_core::{analyze, audit, AuditDecision, TaiError, TaiResult};
+use tai_tui::{print_risk, select_action, ActionItem};
+use tracing::debug;
+
+use crate::environment::EnvContext;
+use crate::go::{
+    command_actions, confirm_risk, parse_response, refusal_message, risk_tone, url_actions, Next,
+    ResponseKind,
+};
+use crate::provider::{chat_with_retry, ensure_active_provider};
+use crate::shell::{capture_with_shell, user_shell, ShellKind};
+
+const PROMPT: &str = "\
+你是一名命令行排错助手。用户的上一条命令执行失败了，请根据命令输出找出原因并给出修正后的命令。
+
+【输出规范】
+只返回一个 JSON 对象，不使用 ``` 包裹，不添加其他文字：
+{\"kind\": \"command\", \"command\": \"修正后的命令\", \"explanation\": \"一句话说明失败原因和修改点\"}
+1. kind 取值：command（修正后的命令）、url（需要查阅的文档链接）、refusal（无法修正）
+2. 修正后的命令应尽量保持原命令的意图和写法，只修改出错的部分
+3. 如果失败原因是缺少工具，给出适合当前系统的安装命令
+4. 无法判断原因时 kind 为 refusal，并在 explanation 中说明
+
+【安全约束】
+- 不生成危险命令（如 rm -rf /、格式化磁盘等）
+- 不要通过 sudo、chmod 777 或关闭校验等方式绕过错误，除非这正是问题所在
+";
+
+/// 提示词中保留的命令输出上限（字节），超出时只保留末尾
+const MAX_OUTPUT: usize = 4000;
+
+#[derive(Args, Debug)]
+pub struct FixArgs {
+    /// 要修复的命令（为空时取 shell 中的上一条命令）
+    pub command: Option<String>,
+
+    /// 目标 shell（默认根据 $SHELL 检测）
+    #[arg(long, value_enum)]
+    pub shell: Option<ShellKind>,
+}
+
+/// 重新运行前的确认
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RerunAction {
+    Rerun,
+    Skip,
+    Cancel,
+}
+
+const RERUN_ACTIONS: &[(RerunAction, char, &str)] = &[
+    (RerunAction::Rerun, 'r', "重新运行以获取输出"),
+    (RerunAction::Skip, 's', "不运行，直接分析"),
+    (RerunAction::Cancel, 'q', "取消"),
+];
+
+impl FixArgs {
+    pub async fn handle(self) -> TaiResult<()> {
+        let (command, mut status) = match self.command {
+            Some(command) => (command.trim().to_string(), None),
+            None => last_command(self.shell).ok_or_else(|| {
+                TaiError::Other(
+                    "找不到上一条命令，请直接传入命令或启用 shell 集成（tai init）".to_string(),
+                )
+            })?,
+        };
+        if command.is_empty() {
+            return Err(TaiError::EmptyInput);
+        }
+        debug!("Fix 命令: {} (退出码 {:?})", command, status);
+        // 重新运行发生在请求模型之前，此时还没有模型信息
+        audit::set_context("fix", Some(&command), None);
+
+        let shell = self
+            .shell
+            .map(|kind| kind.program())
+            .unwrap_or_else(user_shell);
+        let interactive = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+
+        println!("上一条命令: {}", command);
+        if let Some(code) = status {
+            println!("  退出码 {}", code);
+        }
+        let report = analyze(&command);
+        let reasons: Vec<String> = report.findings.iter().map(|f| f.reason.clone()).collect();
+        print_risk(risk_tone(&report), report.level().label(), &reasons)
+            .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+
+        // 重新运行前必须确认；非交互模式下不运行，只根据已知信息分析
+        let mut output = String::new();
+        if interactive {
+            let items: Vec<ActionItem> = RERUN_ACTIONS
+                .iter()
+                .map(|(_, key, label)| ActionItem::new(*key, *label))
+                .collect();
+            let choice = select_action(&items)
+                .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?
+                .map(|i| RERUN_ACTIONS[i].0)
+                .unwrap_or(RerunAction::Cancel);
+            match choice {
+                RerunAction::Rerun => {
+                    let rerun = if confirm_risk(&report, "重新运行")? {
+                        capture_with_shell(&shell, &command, AuditDecision::Approved).await?
+                    } else {
+                        None
+                    };
+                    // 未确认或 dry-run 模式下没有新的输出，按已知信息分析
+                    if let Some((code, captured)) = rerun {
+                        if code == 0 {
+                            println!("  ✓ 命令执行成功，没有需要修复的错误");
+                            return Ok(());
+                        }
+                        println!("  ✗ 退出码 {}", code);
+                        status = Some(code);
+                        output = captured;
+                    }
+                }
+                RerunAction::Skip => {}
+                RerunAction::Cancel => {
+                    debug!("用户取消");
+                    return Ok(());
+                }
+            }
+        }
+
+        let env = EnvContext::collect(self.shell);
+        let prompt = format!(
+            "{}\n{}\n\n【失败的命令】\n{}\n\n【退出码】\n{}\n\n【命令输出】\n{}",
+            PROMPT,
+            env.to_prompt(),
+            command,
+            status.map_or_else(|| "未知".to_string(), |code| code.to_string()),
+            if output.trim().is_empty() {
+                "（未捕获）"
+            } else {
+                tail(&output, MAX_OUTPUT)
+            }
+        );
+
+        let mut context = ensure_active_provider().await?;
+        let model = format!("{}/{}", context.0.provider, context.1);
+        audit::set_context("fix", Some(&command), Some(&model));
+        loop {
+            let text = chat_with_retry(&mut context, &prompt, "AI 分析中...").await?;
+            let response = parse_response(&text);
+            debug!("解析结果: {:?}", response);
+
+            if !interactive {
+                match response.kind {
+                    ResponseKind::Refusal => eprintln!("{}", refusal_message(&response)),
+                    _ => println!("{}", response.content),
+                }
+                return Ok(());
+            }
+            let next = match response.kind {
+                ResponseKind::Refusal => {
+                    println!("{}", refusal_message(&response));
+                    Next::Done
+                }
+                ResponseKind::Url => url_actions(&response.content, &response.explanation)?,
+                ResponseKind::Command => {
+                    command_actions(
+                        &mut context,
+                        &shell,
+                        None,
+                        &response.content,
+                        &response.explanation,
+                    )
+                    .await?
+                }
+            };
+            if let Next::Done = next {
+                break;
+            }
+        }
+
+        debug!("Fix 命令完成");
+        Ok(())
+    }
+}
+
+/// 上一条命令及其退出码：优先读取 shell 集成写入的环境变量，否则读取历史文件
+fn last_command(shell: Option<ShellKind>) -> Option<(String, Option<i32>)> {
+    if let Ok(command) = env::var("TAI_LAST_COMMAND") {
+        let command = command.trim().to_string();
+        if !command.is_empty() && !is_fix(&command) {
+            let status = env::var("TAI_LAST_STATUS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok());
+            return Some((command, status));
+        }
+    }
+
+    let shell = match shell {
+        Some(kind) => kind.name().to_string(),
+        None => EnvContext::collect(None).shell,
+    };
+    let command = history_entries(&shell)?
+        .into_iter()
+        .rev()
+        .find(|command| !is_fix(command))?;
+    Some((command, None))
+}
+
+fn is_fix(command: &str) -> bool {
+    let mut words = command.split_whitespace();
+    words.next() == Some("tai") && words.next() == Some("fix")
+}
+
+/// 读取 shell 历史文件中的命令（按时间顺序）
+fn history_entries(shell: &str) -> Option<Vec<String>> {
+    let home = dirs::home_dir()?;
+    let histfile = env::var_os("HISTFILE").map(PathBuf::from);
+    let (path, parse): (PathBuf, fn(&str) -> Vec<String>) = match shell {
+        "zsh" => (
+            histfile.unwrap_or_else(|| home.join(".zsh_history")),
+            parse_zsh_history,
+        ),
+        "fish" => (
+            home.join(".local/share/fish/fish_history"),
+            parse_fish_history,
+        ),
+        _ => (
+            histfile.unwrap_or_else(|| home.join(".bash_history")),
+            parse_bash_history,
+        ),
+    };
+    debug!("读取历史文件: {:?}", path);
+    // zsh 历史可能包含非 UTF-8 字节
+    let content = fs::read(&path).ok()?;
+    Some(parse(&String::from_utf8_lossy(&content)))
+}
+
+/// bash：每行一条，`#1700000000` 为时间戳行
+fn parse_bash_history(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// zsh：EXTENDED_HISTORY 格式为 `: 1700000000:0;command`
+fn parse_zsh_history(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| match line.strip_prefix(": ") {
+            Some(rest) => rest.split_once(';').map_or(rest, |(_, command)| command),
+            None => line,
+        })
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// fish：YAML 风格，`- cmd: command`
+fn parse_fish_history(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.strip_prefix("- cmd: "))
+        .map(|command| command.replace("\\n", "\n").replace("\\\\", "\\"))
+        .collect()
+}
+
+/// 保留字符串末尾最多 max 字节，不截断 UTF-8 字符
+pub(crate) fn tail(text: &str, max: usize) -> &str {
+    let text = text.trim_end();
+    if text.len() <= max {
+        return text;
+    }
+    let mut start = text.len() - max;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_history() {
+        let zsh = ": 1700000000:0;cargo build\n: 1700000005:0;tai fix\n";
+        assert_eq!(parse_zsh_history(zsh), ["cargo build", "tai fix"]);
+
+        let bash = "#1700000000\ngit pus origin main\n";
+        assert_eq!(parse_bash_history(bash), ["git pus origin main"]);
+
+        let fish = "- cmd: ls /nope\n  when: 1700000000\n- cmd: tai fix\n  when: 1700000001\n";
+        assert_eq!(parse_fish_history(fish), ["ls /nope", "tai fix"]);
+
+        assert!(is_fix("tai fix"));
+        assert!(!is_fix("tai go fix the build"));
+        assert_eq!(tail("错误输出", 6), "输出");
+    }
+}
@@ -0,0 +1,179 @@
+//! `tai commit` / `tai review` 共用的 git 调用和 diff 处理
+
+use std::process::Command;
+use tai_core::{TaiError, TaiResult};
+use tracing::debug;
+
+/// 运行 git 并返回标准输出；失败时返回 stderr 的第一行（其余多为用法说明）
+pub fn git(args: &[&str]) -> TaiResult<String> {
+    debug!("git {}", args.join(" "));
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| TaiError::Other(format!("无法运行 git: {}", e)))?;
+    if !output.status.success() {
+        return Err(TaiError::Other(format!(
+            "git {} 失败: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr)
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 按文件把 diff 切成不超过 max_chars 的若干段；单个文件过大时按改动块再切，
+/// 每段都保留文件头，单个改动块仍然过大时截断
+pub fn chunk_diff(diff: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for file in split_before(diff, "diff --git ") {
+        for piece in split_file(&file, max_chars) {
+            if !current.is_empty() && current.len() + piece.len() > max_chars {
+                chunks.push(std::mem::take(&mut current));
+            }
+            current.push_str(&piece);
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// 把单个文件的 diff 按改动块切开，每段带上文件头
+fn split_file(file: &str, max_chars: usize) -> Vec<String> {
+    if file.len() <= max_chars {
+        return vec![file.to_string()];
+    }
+    let mut parts = split_before(file, "@@ ");
+    let header = if parts.first().is_some_and(|p| !p.starts_with("@@ ")) {
+        parts.remove(0)
+    } else {
+        String::new()
+    };
+
+    let mut pieces: Vec<String> = Vec::new();
+    let mut current = header.clone();
+    for hunk in parts {
+        let hunk = if header.len() + hunk.len() > max_chars {
+            truncate(&hunk, max_chars.saturating_sub(header.len()))
+        } else {
+            hunk
+        };
+        if current.len() > header.len() && current.len() + hunk.len() > max_chars {
+            pieces.push(std::mem::replace(&mut current, header.clone()));
+        }
+        current.push_str(&hunk);
+    }
+    if current.len() > header.len() || pieces.is_empty() {
+        pieces.push(current);
+    }
+    pieces
+}
+
+/// 在每个以 marker 开头的行之前切开
+fn split_before(text: &str, marker: &str) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for line in text.split_inclusive('\n') {
+        match parts.last_mut() {
+            Some(part) if !line.starts_with(marker) => part.push_str(line),
+            _ => parts.push(line.to_string()),
+        }
+    }
+    parts
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let mut end = max_chars.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let cut = text[..end].rfind('\n').map_or(end, |i| i + 1);
+    format!("{}...（改动过长，已截断）\n", &text[..cut])
+}
+
+/// 给 diff 中的新文件行加上行号，便于模型引用 `路径:行号`
+///
+/// 新增行显示为 `  12 +内容`，上下文行为 `  12  内容`，删除行不编号
+pub fn annotate_diff(diff: &str) -> String {
+    let mut out = String::with_capacity(diff.len() + diff.len() / 4);
+    let mut line_no: Option<usize> = None;
+    for line in diff.lines() {
+        if line.starts_with("@@") {
+            line_no = new_start(line);
+            out.push_str(line);
+        } else if let Some(n) = line_no.as_mut() {
+            match line.chars().next() {
+                Some('+') => {
+                    out.push_str(&format!("{:>5} {}", n, line));
+                    *n += 1;
+                }
+                Some('-') => out.push_str(&format!("      {}", line)),
+                Some(' ') | None => {
+                    out.push_str(&format!("{:>5} {}", n, line));
+                    *n += 1;
+                }
+                // 下一个文件头或 "\ No newline at end of file"
+                _ => {
+                    if line.starts_with("diff --git ") {
+                        line_no = None;
+                    }
+                    out.push_str(line);
+                }
+            }
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// `@@ -10,7 +12,8 @@` 中新文件的起始行号
+fn new_start(header: &str) -> Option<usize> {
+    let plus = header
+        .split_whitespace()
+        .find(|part| part.starts_with('+'))?;
+    plus[1..].split(',').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/a.rs b/src/a.rs
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1,3 +1,3 @@
+ fn a() {
+-    old();
++    new();
+ }
+diff --git a/src/b.rs b/src/b.rs
+--- a/src/b.rs
++++ b/src/b.rs
+@@ -10,2 +10,3 @@ impl B {
+     fn b() {}
++    fn c() {}
+ }
+";
+
+    #[test]
+    fn test_chunk_and_annotate_diff() {
+        let chunks = chunk_diff(DIFF, 150);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].starts_with("diff --git a/src/a.rs"));
+        assert!(chunks[1].starts_with("diff --git a/src/b.rs"));
+        assert_eq!(chunk_diff(DIFF, 10_000), vec![DIFF.to_string()]);
+
+        let annotated = annotate_diff(DIFF);
+        assert!(annotated.contains("    1  fn a() {\n      -    old();\n    2 +    new();\n"));
+        assert!(annotated.contains("   11 +    fn c() {}\n"));
+        assert!(annotated.contains("\ndiff --git a/src/b.rs b/src/b.rs\n--- a/src/b.rs\n"));
+    }
+}
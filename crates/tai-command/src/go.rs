@@ -1,11 +1,26 @@
-use arboard::Clipboard;
 use clap::Args;
-use tai_ai::chat;
-use tai_core::{TaiError, TaiResult};
-use tai_tui::Spinner;
+use std::{
+    io::IsTerminal,
+    process::{Command, Stdio},
+};
+use tai_ai::{chat_stream, ProviderConfig, StreamChunk};
+use tai_core::{
+    analyze, audit, AuditDecision, RiskLevel, RiskReport, TaiConfig, TaiError, TaiResult,
+};
+use tai_tui::{
+    confirm_typed, confirm_typed_on_tty, edit_line, print_risk, select_action, select_candidate,
+    ActionItem, CandidateItem, RiskTone, Spinner, TextRenderer,
+};
 use tracing::{debug, warn};
 
-use crate::provider::{ensure_active_provider, recover_auth_error};
+use crate::provider::{chat_with_retry, ensure_active_provider, recover_auth_error};
+use crate::clipboard::{copy_text, ClipboardMethod};
+use crate::environment::EnvContext;
+use crate::learning::{self, Outcome};
+use crate::shell::{capture_with_shell, user_shell, ShellKind};
+
+mod response;
+pub(crate) use response::{parse_response, GoResponse, ResponseKind};
 
 const PROMPT: &str = "\
 你是一名命令行助手，请严格遵循以下规则：
@@ -14,15 +29,16 @@ const PROMPT: &str = "\
 根据用户描述生成对应的命令行命令。
 
 【输出规范】
-1. 只返回命令本身，无任何解释、注释或额外文字
-2. 单行纯文本，不使用 ``` 或其他标记包裹
-3. 优先使用一行流命令（管道、链式操作）
+只返回一个 JSON 对象，不使用 ``` 包裹，不添加其他文字：
+{\"kind\": \"command\", \"command\": \"命令\", \"explanation\": \"一句话说明\"}
+1. kind 取值：command（命令）、url（文档/官网/博客链接）、refusal（无法或不应生成命令）
+2. kind 为 url 时 command 字段填写链接；为 refusal 时 command 为空，在 explanation 中说明原因
+3. command 为单行命令，优先使用一行流命令（管道、链式操作）
 
 【优先级规则】
-1. 若任务无法通过单行命令简单完成 → 返回相关文档/官网/博客链接（仅URL）
-2. 若用户描述与命令执行无关 → 返回：ls
-3. 以上两条互斥，仅满足其一时执行
-4. 如果能用相关文档/官网/博客链接解决，尽量返回对应的链接而不是执行ls命令
+1. 若任务无法通过单行命令简单完成 → 返回相关文档/官网/博客链接（kind 为 url）
+2. 若用户描述与命令执行无关 → kind 为 refusal
+3. 能用链接解决时优先返回链接，而不是拒绝
 
 【安全约束】
 - 不生成危险命令（如 rm -rf /、格式化磁盘等）
@@ -31,63 +47,262 @@ const PROMPT: &str = "\
 
 【示例】
 用户：列出当前目录下所有 .txt 文件
-返回：find . -name \"*.txt\"
+返回：{\"kind\": \"command\", \"command\": \"find . -name \\\"*.txt\\\"\", \"explanation\": \"递归查找 .txt 文件\"}
 
 用户：如何配置 Kubernetes 集群
-返回：https://kubernetes.io/docs/setup/
+返回：{\"kind\": \"url\", \"command\": \"https://kubernetes.io/docs/setup/\", \"explanation\": \"官方安装与配置文档\"}
 
 用户：今天天气怎么样
-返回：ls
+返回：{\"kind\": \"refusal\", \"command\": \"\", \"explanation\": \"与命令行无关\"}
+";
 
-【用户描述】
+const EXPLAIN_PROMPT: &str = "\
+请用中文简要解释下面这条命令：先用一句话说明整体作用，再逐项说明各部分（程序、参数、管道、重定向）的含义，\
+如有潜在风险（删除、覆盖、提权等）请单独指出。使用 Markdown 列表，不要重复输出命令本身。
+
+命令：
 ";
 
+const CANDIDATES_FORMAT: &str = "\
+【候选输出格式】（优先于上面的输出规范）
+给出 {n} 个不同思路的候选命令，只返回 JSON 数组，不使用 ``` 包裹，不添加其他文字：
+[{\"command\": \"命令\", \"explanation\": \"一句话说明\"}]";
+
+/// 注入提示词的历史示例数量上限
+const MAX_EXAMPLES: usize = 3;
+
 #[derive(Args, Debug)]
 pub struct GoArgs {
-    pub user_input: String,
+    /// 生成后直接在当前 shell 中执行，不显示操作栏
+    #[arg(long)]
+    pub run: bool,
+
+    /// 只把命令输出到标准输出（供 shell 集成使用），不复制也不显示操作栏
+    #[arg(long, conflicts_with_all = ["run", "candidates"])]
+    pub print: bool,
+
+    /// 生成 N 个候选命令供选择（1-9）
+    #[arg(
+        short = 'n',
+        long = "candidates",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u8).range(1..=9)
+    )]
+    pub candidates: u8,
+
+    /// 目标 shell（默认根据 $SHELL 检测）
+    #[arg(long, value_enum)]
+    pub shell: Option<ShellKind>,
+
+    /// 清空 tai go 的学习记录
+    #[arg(long, conflicts_with_all = ["run", "print", "candidates"])]
+    pub forget: bool,
+
+    #[arg(required_unless_present = "forget")]
+    pub user_input: Option<String>,
+}
+
+/// 生成命令后的操作
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GoAction {
+    Run,
+    Edit,
+    Copy,
+    Explain,
+    Regenerate,
+    Cancel,
+}
+
+const ACTIONS: &[(GoAction, char, &str)] = &[
+    (GoAction::Run, 'r', "运行"),
+    (GoAction::Edit, 'e', "编辑"),
+    (GoAction::Copy, 'c', "复制"),
+    (GoAction::Explain, 'x', "解释"),
+    (GoAction::Regenerate, 'g', "重新生成"),
+    (GoAction::Cancel, 'q', "取消"),
+];
+
+/// 命令操作栏结束后的去向
+pub(crate) enum Next {
+    Done,
+    /// 用户要求重新生成
+    Regenerate,
 }
 
 impl GoArgs {
     pub async fn handle(self) -> TaiResult<()> {
-        debug!("Go 命令: 用户输入 = {}", self.user_input);
+        if self.forget {
+            let count = learning::forget()?;
+            println!("✓ 已清空 {} 条学习记录", count);
+            return Ok(());
+        }
+        let user_input = self.user_input.unwrap_or_default();
+        debug!("Go 命令: 用户输入 = {}", user_input);
 
-        let prompt = format!("{} {}", PROMPT, self.user_input);
+        let env = EnvContext::collect(self.shell);
+        let count = self.candidates as usize;
+        let output_format = if count > 1 {
+            format!("\n\n{}", CANDIDATES_FORMAT.replace("{n}", &count.to_string()))
+        } else {
+            String::new()
+        };
+        // 参考过去采纳过的相似命令，学习用户的工具和写法偏好
+        let examples = learning::similar_examples(&user_input, MAX_EXAMPLES);
+        let examples = if examples.is_empty() {
+            String::new()
+        } else {
+            debug!("注入 {} 条历史示例", examples.len());
+            format!("\n\n{}", learning::examples_prompt(&examples))
+        };
+        let prompt = format!(
+            "{}\n{}{}{}\n\n【用户描述】\n{}",
+            PROMPT,
+            env.to_prompt(),
+            examples,
+            output_format,
+            user_input
+        );
+        // --shell 指定时在该 shell 中运行，否则使用用户的 $SHELL
+        let shell = self
+            .shell
+            .map(|kind| kind.program())
+            .unwrap_or_else(user_shell);
         let mut context = ensure_active_provider().await?;
+        let model = format!("{}/{}", context.0.provider, context.1);
+        audit::set_context("go", Some(&user_input), Some(&model));
 
-        let command = loop {
-            debug!("使用模型: {}/{}", context.0.provider, context.1);
-            let spinner = Spinner::new("AI 思考中...");
-            match chat(&context.0, &context.1, &prompt).await {
-                Ok(cmd) => {
-                    spinner.finish_and_clear();
-                    break cmd;
+        if self.print {
+            // 只有命令会写到标准输出，链接和拒绝说明输出到 stderr，避免被插入命令行
+            let response = generate(&mut context, &prompt).await?;
+            match response.kind {
+                ResponseKind::Command => {
+                    let report = analyze(&response.content);
+                    if report.level() != RiskLevel::Low {
+                        eprintln!("警告: {}", describe_risk(&report));
+                    }
+                    // 插入命令行后回车即执行，高风险命令必须先在终端中确认
+                    if report.is_high() {
+                        let confirmed =
+                            confirm_typed_on_tty("这是高风险命令，输入 yes 确认插入命令行", "yes")
+                                .unwrap_or_else(|e| {
+                                    warn!("无法在终端中确认: {}", e);
+                                    false
+                                });
+                        if !confirmed {
+                            learning::record(
+                                &user_input,
+                                &response.content,
+                                &response.content,
+                                Outcome::Discarded,
+                            );
+                            return Err(TaiError::Other("高风险命令未确认，已取消".to_string()));
+                        }
+                    }
+                    learning::record(
+                        &user_input,
+                        &response.content,
+                        &response.content,
+                        Outcome::Printed,
+                    );
+                    println!("{}", response.content);
                 }
-                Err(TaiError::AuthError(ref name)) => {
-                    spinner.finish_and_clear();
-                    context = recover_auth_error(name).await?;
+                ResponseKind::Url => eprintln!("请参考: {}", response.content),
+                ResponseKind::Refusal => eprintln!("{}", refusal_message(&response)),
+            }
+            return Ok(());
+        }
+
+        // 输出被重定向（管道、脚本）时保持原有行为：只输出并复制，高风险命令和链接不复制
+        if !self.run && !std::io::stdout().is_terminal() {
+            let command = if count > 1 {
+                let candidates = generate_candidates(&mut context, &prompt).await?;
+                for candidate in &candidates {
+                    println!("{}", candidate.command);
                 }
-                Err(e) => {
-                    spinner.finish_and_clear();
-                    return Err(e);
+                candidates[0].command.clone()
+            } else {
+                let response = generate(&mut context, &prompt).await?;
+                match response.kind {
+                    ResponseKind::Command => {}
+                    ResponseKind::Url => {
+                        println!("{}", response.content);
+                        return Ok(());
+                    }
+                    ResponseKind::Refusal => {
+                        eprintln!("{}", refusal_message(&response));
+                        return Ok(());
+                    }
                 }
-            }
-        };
+                println!("{}", response.content);
+                response.content
+            };
+            let report = analyze(&command);
+            let copied = if !TaiConfig::load()?.auto_copy_command {
+                debug!("auto_copy_command 已关闭，不复制");
+                false
+            } else if report.is_high() {
+                eprintln!("警告: {}，未复制到剪贴板", describe_risk(&report));
+                false
+            } else {
+                copy_to_clipboard(&command)
+            };
+            let outcome = if copied {
+                Outcome::Copied
+            } else {
+                Outcome::Printed
+            };
+            learning::record(&user_input, &command, &command, outcome);
+            debug!("Go 命令完成");
+            return Ok(());
+        }
 
-        println!("{}", command);
+        loop {
+            let Some(response) = obtain(&mut context, &prompt, count).await? else {
+                debug!("用户取消");
+                return Ok(());
+            };
 
-        match Clipboard::new() {
-            Ok(mut clipboard) => {
-                if let Err(e) = clipboard.set_text(&command) {
-                    warn!("无法复制到剪贴板: {}", e);
-                    eprintln!("警告: 无法复制到剪贴板: {}", e);
-                } else {
-                    debug!("命令已复制到剪贴板");
-                    println!("✓ 已复制到剪贴板");
+            let next = match response.kind {
+                ResponseKind::Refusal => {
+                    println!("{}", refusal_message(&response));
+                    Next::Done
                 }
-            }
-            Err(e) => {
-                warn!("无法访问剪贴板: {}", e);
-                eprintln!("警告: 无法访问剪贴板: {}", e);
+                ResponseKind::Url if self.run => {
+                    println!("请参考: {}", response.content);
+                    Next::Done
+                }
+                ResponseKind::Url => url_actions(&response.content, &response.explanation)?,
+                ResponseKind::Command if self.run => {
+                    let command = &response.content;
+                    let report = show_command(command, &response.explanation)?;
+                    if report.is_high() && !std::io::stdin().is_terminal() {
+                        return Err(TaiError::Other(format!(
+                            "{}，需要在终端中确认后才能执行",
+                            describe_risk(&report)
+                        )));
+                    }
+                    if confirm_risk(&report, "执行")? {
+                        let result = run_command(&shell, command, AuditDecision::Auto).await;
+                        learning::record(&user_input, command, command, outcome_of(&result));
+                        result?;
+                    } else {
+                        learning::record(&user_input, command, command, Outcome::Discarded);
+                    }
+                    Next::Done
+                }
+                ResponseKind::Command => {
+                    command_actions(
+                        &mut context,
+                        &shell,
+                        Some(&user_input),
+                        &response.content,
+                        &response.explanation,
+                    )
+                    .await?
+                }
+            };
+            if let Next::Done = next {
+                break;
             }
         }
 
@@ -95,3 +310,420 @@ impl GoArgs {
         Ok(())
     }
 }
+
+/// 显示命令并进入操作栏（运行/编辑/复制/解释/重新生成/取消）；
+/// request 不为空时把用户的处理结果记入学习库
+pub(crate) async fn command_actions(
+    context: &mut (ProviderConfig, String),
+    shell: &str,
+    request: Option<&str>,
+    generated: &str,
+    explanation: &str,
+) -> TaiResult<Next> {
+    let record = |command: &str, outcome| {
+        if let Some(request) = request {
+            learning::record(request, generated, command, outcome);
+        }
+    };
+    let mut command = generated.to_string();
+    let mut report = show_command(&command, explanation)?;
+
+    let items: Vec<ActionItem> = ACTIONS
+        .iter()
+        .map(|(_, key, label)| ActionItem::new(*key, *label))
+        .collect();
+
+    loop {
+        let choice = select_action(&items)
+            .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?
+            .map(|i| ACTIONS[i].0)
+            .unwrap_or(GoAction::Cancel);
+
+        match choice {
+            GoAction::Run => {
+                if confirm_risk(&report, "执行")? {
+                    let decision = if command == generated {
+                        AuditDecision::Approved
+                    } else {
+                        AuditDecision::Edited
+                    };
+                    let result = run_command(shell, &command, decision).await;
+                    record(&command, outcome_of(&result));
+                    result?;
+                    return Ok(Next::Done);
+                }
+            }
+            GoAction::Edit => {
+                let edited = edit_line("编辑命令", &command)
+                    .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+                if let Some(edited) = edited {
+                    debug!("用户编辑了命令: {}", edited);
+                    command = edited;
+                    report = show_command(&command, "")?;
+                }
+            }
+            GoAction::Copy => {
+                if confirm_risk(&report, "复制")? {
+                    let outcome = if copy_to_clipboard(&command) {
+                        Outcome::Copied
+                    } else {
+                        Outcome::Printed
+                    };
+                    record(&command, outcome);
+                    return Ok(Next::Done);
+                }
+            }
+            GoAction::Explain => {
+                explain(context, &command).await?;
+            }
+            GoAction::Regenerate => {
+                record(&command, Outcome::Discarded);
+                return Ok(Next::Regenerate);
+            }
+            GoAction::Cancel => {
+                debug!("用户取消");
+                record(&command, Outcome::Discarded);
+                return Ok(Next::Done);
+            }
+        }
+    }
+}
+
+/// 模型返回链接时的操作
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum UrlAction {
+    Open,
+    Copy,
+    Regenerate,
+    Cancel,
+}
+
+const URL_ACTIONS: &[(UrlAction, char, &str)] = &[
+    (UrlAction::Open, 'o', "打开"),
+    (UrlAction::Copy, 'c', "复制"),
+    (UrlAction::Regenerate, 'g', "重新生成"),
+    (UrlAction::Cancel, 'q', "取消"),
+];
+
+/// 显示链接并提供打开/复制操作
+pub(crate) fn url_actions(url: &str, explanation: &str) -> TaiResult<Next> {
+    println!("{}", url);
+    if !explanation.is_empty() {
+        println!("  {}", explanation);
+    }
+    let items: Vec<ActionItem> = URL_ACTIONS
+        .iter()
+        .map(|(_, key, label)| ActionItem::new(*key, *label))
+        .collect();
+    let choice = select_action(&items)
+        .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?
+        .map(|i| URL_ACTIONS[i].0)
+        .unwrap_or(UrlAction::Cancel);
+
+    match choice {
+        UrlAction::Open => {
+            if let Err(e) = open_url(url) {
+                warn!("打开链接失败: {}", e);
+                eprintln!("警告: {}", e);
+            }
+        }
+        UrlAction::Copy => {
+            copy_to_clipboard(url);
+        }
+        UrlAction::Regenerate => return Ok(Next::Regenerate),
+        UrlAction::Cancel => debug!("用户取消"),
+    }
+    Ok(Next::Done)
+}
+
+/// 用系统默认程序打开链接
+fn open_url(url: &str) -> TaiResult<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    let status = command
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| TaiError::Other(format!("无法打开链接 {}: {}", url, e)))?;
+    if !status.success() {
+        return Err(TaiError::Other(format!("无法打开链接 {}: 退出状态 {}", url, status)));
+    }
+    println!("✓ 已在浏览器中打开");
+    Ok(())
+}
+
+pub(crate) fn refusal_message(response: &GoResponse) -> String {
+    if response.explanation.is_empty() {
+        "无法根据该描述生成命令".to_string()
+    } else {
+        format!("无法生成命令：{}", response.explanation)
+    }
+}
+
+/// 生成命令；多候选时让用户在选择器中挑选一个，取消返回 None
+async fn obtain(
+    context: &mut (ProviderConfig, String),
+    prompt: &str,
+    count: usize,
+) -> TaiResult<Option<GoResponse>> {
+    if count <= 1 {
+        return generate(context, prompt).await.map(Some);
+    }
+
+    let candidates = generate_candidates(context, prompt).await?;
+    let items: Vec<CandidateItem> = candidates
+        .iter()
+        .map(|c| {
+            let report = analyze(&c.command);
+            let reasons: Vec<&str> = report.findings.iter().map(|f| f.reason.as_str()).collect();
+            let label = if reasons.is_empty() {
+                report.level().label().to_string()
+            } else {
+                format!("{}：{}", report.level().label(), reasons.join("；"))
+            };
+            CandidateItem::new(&c.command, &c.explanation, risk_tone(&report), label)
+        })
+        .collect();
+    let selected = select_candidate(&items)
+        .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+    Ok(selected.map(|i| GoResponse::command(&candidates[i].command, &candidates[i].explanation)))
+}
+
+/// 一个候选命令及其一句话说明
+struct Candidate {
+    command: String,
+    explanation: String,
+}
+
+/// 请求多个候选命令并解析模型返回的 JSON 数组
+async fn generate_candidates(
+    context: &mut (ProviderConfig, String),
+    prompt: &str,
+) -> TaiResult<Vec<Candidate>> {
+    let text = chat_with_retry(context, prompt, "AI 思考中...").await?;
+    let candidates = parse_candidates(&text);
+    if candidates.is_empty() {
+        warn!("无法解析候选命令: {}", text);
+        return Err(TaiError::Other(format!("模型未返回有效的候选命令: {}", text)));
+    }
+    Ok(candidates)
+}
+
+/// 解析 `[{"command": ..., "explanation": ...}]`，容忍代码块包裹；
+/// 不是 JSON 时按行把每条非空文本视为一个命令
+fn parse_candidates(text: &str) -> Vec<Candidate> {
+    let json = match (text.find('['), text.rfind(']')) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        _ => "",
+    };
+    if let Ok(serde_json::Value::Array(values)) = serde_json::from_str(json) {
+        return values
+            .iter()
+            .filter_map(|v| {
+                let command = v.get("command")?.as_str()?.trim();
+                let explanation = v
+                    .get("explanation")
+                    .and_then(|e| e.as_str())
+                    .unwrap_or_default()
+                    .trim();
+                (!command.is_empty()).then(|| Candidate {
+                    command: command.to_string(),
+                    explanation: explanation.to_string(),
+                })
+            })
+            .collect();
+    }
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("```"))
+        .map(|l| Candidate {
+            command: l.to_string(),
+            explanation: String::new(),
+        })
+        .collect()
+}
+
+/// 调用模型生成命令并解析回复
+async fn generate(context: &mut (ProviderConfig, String), prompt: &str) -> TaiResult<GoResponse> {
+    let text = chat_with_retry(context, prompt, "AI 思考中...").await?;
+    let response = parse_response(&text);
+    debug!("解析结果: {:?}", response);
+    Ok(response)
+}
+
+/// 流式输出命令解释
+async fn explain(context: &mut (ProviderConfig, String), command: &str) -> TaiResult<()> {
+    let prompt = format!("{}{}", EXPLAIN_PROMPT, command);
+    loop {
+        let spinner = Spinner::new("AI 解释中...");
+        let mut renderer = TextRenderer::new();
+        let mut first_chunk = true;
+
+        let result = chat_stream(&context.0, &context.1, &prompt, |chunk| {
+            if first_chunk {
+                spinner.finish_and_clear();
+                first_chunk = false;
+            }
+            if let StreamChunk::Answer(text) = chunk {
+                renderer.append_answer(&text);
+                renderer.render()?;
+            }
+            Ok(())
+        })
+        .await;
+        spinner.finish_and_clear();
+
+        match result {
+            Ok(_) => {
+                renderer.finish(false)?;
+                println!();
+                return Ok(());
+            }
+            Err(TaiError::AuthError(ref name)) => {
+                *context = recover_auth_error(name).await?;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 打印命令、说明及其本地风险分析结果
+fn show_command(command: &str, explanation: &str) -> TaiResult<RiskReport> {
+    let report = analyze(command);
+    debug!("风险分析: {:?}", report);
+    println!("{}", command);
+    if !explanation.is_empty() {
+        println!("  {}", explanation);
+    }
+    let reasons: Vec<String> = report.findings.iter().map(|f| f.reason.clone()).collect();
+    print_risk(risk_tone(&report), report.level().label(), &reasons)
+        .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+    Ok(report)
+}
+
+pub(crate) fn risk_tone(report: &RiskReport) -> RiskTone {
+    match report.level() {
+        RiskLevel::Low => RiskTone::Low,
+        RiskLevel::Medium => RiskTone::Medium,
+        RiskLevel::High => RiskTone::High,
+    }
+}
+
+pub(crate) fn describe_risk(report: &RiskReport) -> String {
+    let reasons: Vec<&str> = report.findings.iter().map(|f| f.reason.as_str()).collect();
+    format!("{}命令（{}）", report.level().label(), reasons.join("；"))
+}
+
+/// 高风险命令需要输入 yes 确认，其余直接放行
+pub(crate) fn confirm_risk(report: &RiskReport, action: &str) -> TaiResult<bool> {
+    if !report.is_high() {
+        return Ok(true);
+    }
+    let confirmed = confirm_typed(&format!("这是高风险命令，输入 yes 确认{}", action), "yes")
+        .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+    if !confirmed {
+        warn!("用户未确认高风险命令");
+        println!("  已取消{}", action);
+    }
+    Ok(confirmed)
+}
+
+/// 执行命令，返回退出码；dry-run 模式下未执行，返回 None
+async fn run_command(
+    shell: &str,
+    command: &str,
+    decision: AuditDecision,
+) -> TaiResult<Option<i32>> {
+    let Some((code, _)) = capture_with_shell(shell, command, decision).await? else {
+        return Ok(None);
+    };
+    if code == 0 {
+        println!("  ✓ 执行完成");
+    } else {
+        println!("  ✗ 退出码 {}", code);
+    }
+    Ok(Some(code))
+}
+
+/// 按执行结果决定记入学习库的结果：只有执行成功的命令才作为采纳的示例
+fn outcome_of(result: &TaiResult<Option<i32>>) -> Outcome {
+    match result {
+        Ok(Some(0)) => Outcome::Ran,
+        Ok(None) => Outcome::Printed,
+        _ => Outcome::Failed,
+    }
+}
+
+/// 复制到剪贴板，返回是否成功
+fn copy_to_clipboard(command: &str) -> bool {
+    match copy_text(command) {
+        Ok(ClipboardMethod::Arboard) => {
+            debug!("命令已复制到剪贴板");
+            println!("✓ 已复制到剪贴板");
+            true
+        }
+        Ok(method) => {
+            debug!("命令已通过 {} 复制", method);
+            println!("✓ 已通过 {} 复制到剪贴板", method);
+            true
+        }
+        Err(e) => {
+            warn!("复制失败: {}", e);
+            eprintln!("警告: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(text: &str) -> Vec<String> {
+        parse_candidates(text)
+            .into_iter()
+            .map(|c| c.command)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_candidates() {
+        let candidates = parse_candidates(
+            "```json\n[{\"command\": \"rg TODO\", \"explanation\": \"搜索 TODO\"},\n {\"command\": \"grep -rn TODO .\"}]\n```",
+        );
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].command, "rg TODO");
+        assert_eq!(candidates[0].explanation, "搜索 TODO");
+        assert_eq!(candidates[1].explanation, "");
+
+        assert_eq!(
+            commands(
+                "以下是候选命令：\n[{\"command\": \"ls -la\"}, {\"command\": \"  \"}]\n请选择。"
+            ),
+            vec!["ls -la"]
+        );
+    }
+
+    #[test]
+    fn test_parse_candidates_fallback() {
+        // 不是 JSON 时按行拆分，跳过代码块标记
+        assert_eq!(
+            commands("```bash\nls -la\n\nfind . -type f\n```"),
+            vec!["ls -la", "find . -type f"]
+        );
+        // JSON 不完整时同样退回按行解析
+        assert_eq!(
+            commands("[{\"command\": \"ls\""),
+            vec!["[{\"command\": \"ls\""]
+        );
+        assert!(parse_candidates("").is_empty());
+    }
+}
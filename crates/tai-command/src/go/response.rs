@@ -0,0 +1,200 @@
+use serde_json::Value;
+
+/// 模型回复的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseKind {
+    Command,
+    Url,
+    Refusal,
+}
+
+/// 解析后的 go 回复
+#[derive(Debug, Clone)]
+pub struct GoResponse {
+    pub kind: ResponseKind,
+    /// 命令或链接；拒绝时为空
+    pub content: String,
+    pub explanation: String,
+}
+
+impl GoResponse {
+    /// 由命令文本构造，是链接时自动识别为 Url
+    pub fn command(content: &str, explanation: &str) -> Self {
+        let content = content.trim().to_string();
+        let kind = if content.is_empty() {
+            ResponseKind::Refusal
+        } else if is_url(&content) {
+            ResponseKind::Url
+        } else {
+            ResponseKind::Command
+        };
+        Self {
+            kind,
+            content,
+            explanation: explanation.trim().to_string(),
+        }
+    }
+
+    fn refusal(explanation: &str) -> Self {
+        Self {
+            kind: ResponseKind::Refusal,
+            content: String::new(),
+            explanation: explanation.trim().to_string(),
+        }
+    }
+}
+
+/// 解析模型回复：优先按约定的 JSON 解析，失败时去掉代码块和说明文字后提取命令
+pub fn parse_response(text: &str) -> GoResponse {
+    let text = text.trim();
+    if let Some(response) = parse_json(text) {
+        return response;
+    }
+
+    let (body, fenced) = match fenced_block(text) {
+        Some(block) => (block, true),
+        None => (text, false),
+    };
+    let lines: Vec<&str> = body
+        .lines()
+        .map(clean_line)
+        .filter(|line| !line.is_empty() && !is_prose(line))
+        .collect();
+
+    match lines.as_slice() {
+        [] => GoResponse::refusal(text),
+        [first, ..] if !fenced => GoResponse::command(first, ""),
+        lines => GoResponse::command(&join_lines(lines), ""),
+    }
+}
+
+fn parse_json(text: &str) -> Option<GoResponse> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    let value: Value = serde_json::from_str(text.get(start..=end)?).ok()?;
+    // 命令本身可能含有 {}（如 find -exec），只接受带约定字段的对象
+    if ["kind", "command", "url"]
+        .iter()
+        .all(|key| value.get(key).is_none())
+    {
+        return None;
+    }
+    let field = |name: &str| value.get(name).and_then(Value::as_str).unwrap_or_default();
+
+    let content = [field("command"), field("url")]
+        .into_iter()
+        .find(|s| !s.trim().is_empty())
+        .unwrap_or_default();
+    let explanation = field("explanation");
+
+    let response = match field("kind") {
+        "refusal" => GoResponse::refusal(explanation),
+        "url" if !content.is_empty() => GoResponse {
+            kind: ResponseKind::Url,
+            content: content.trim().to_string(),
+            explanation: explanation.trim().to_string(),
+        },
+        _ => GoResponse::command(content, explanation),
+    };
+    Some(response)
+}
+
+/// 第一个 ``` 代码块的内容（去掉语言标记）
+fn fenced_block(text: &str) -> Option<&str> {
+    let start = text.find("```")?;
+    let after = &text[start + 3..];
+    let body_start = after.find('\n')? + 1;
+    let body = &after[body_start..];
+    let end = body.find("```").unwrap_or(body.len());
+    Some(&body[..end])
+}
+
+/// 去掉提示符、常见前缀和行内代码的反引号
+fn clean_line(line: &str) -> &str {
+    let mut line = line.trim();
+    for prefix in ["返回：", "返回:", "命令：", "命令:", "$ "] {
+        if let Some(rest) = line.strip_prefix(prefix) {
+            line = rest.trim();
+        }
+    }
+    if line.len() > 2 && line.starts_with('`') && line.ends_with('`') {
+        line = line.trim_matches('`').trim();
+    }
+    line
+}
+
+/// 判断是否为说明文字而不是命令
+fn is_prose(line: &str) -> bool {
+    const PROSE_STARTS: &[&str] = &[
+        "Here", "This", "You ", "The ", "To ", "Use ", "Note", "If ", "#",
+    ];
+    line.ends_with(['。', '：', ':'])
+        || line.chars().next().is_some_and(is_cjk)
+        || PROSE_STARTS.iter().any(|p| line.starts_with(p))
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{4e00}'..='\u{9fff}' | '\u{3000}'..='\u{303f}' | '\u{ff00}'..='\u{ffef}')
+}
+
+fn is_url(text: &str) -> bool {
+    (text.starts_with("http://") || text.starts_with("https://"))
+        && !text.contains(char::is_whitespace)
+}
+
+/// 代码块中的多行命令：续行符合并为一行，其余按 && 串联
+fn join_lines(lines: &[&str]) -> String {
+    let mut joined = String::new();
+    let mut continued = true;
+    for line in lines {
+        if !joined.is_empty() {
+            joined.push_str(if continued { " " } else { " && " });
+        }
+        continued = line.ends_with('\\');
+        joined.push_str(line.trim_end_matches('\\').trim_end());
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structured_response() {
+        let r = parse_response(
+            r#"{"kind": "command", "command": "rg TODO", "explanation": "搜索 TODO"}"#,
+        );
+        assert_eq!(r.kind, ResponseKind::Command);
+        assert_eq!(r.content, "rg TODO");
+        assert_eq!(r.explanation, "搜索 TODO");
+
+        let r = parse_response(
+            "```json\n{\"kind\": \"url\", \"command\": \"https://kubernetes.io/docs/setup/\"}\n```",
+        );
+        assert_eq!(r.kind, ResponseKind::Url);
+
+        let r =
+            parse_response(r#"{"kind": "refusal", "command": "", "explanation": "与命令无关"}"#);
+        assert_eq!(r.kind, ResponseKind::Refusal);
+        assert_eq!(r.explanation, "与命令无关");
+    }
+
+    #[test]
+    fn test_tolerant_fallback() {
+        let r = parse_response("Here is the command:\n```bash\nls -la\n```\nThis lists files.");
+        assert_eq!(r.content, "ls -la");
+
+        let r = parse_response("你可以使用以下命令：\n`rg -n \"错误\" src`\n它会显示行号。");
+        assert_eq!(r.content, "rg -n \"错误\" src");
+
+        let r = parse_response("```sh\ncd build \\\n  --verbose\nmake\n```");
+        assert_eq!(r.content, "cd build --verbose && make");
+
+        let r = parse_response("find . -name '*.rs' -exec wc -l {} +");
+        assert_eq!(r.content, "find . -name '*.rs' -exec wc -l {} +");
+
+        let r = parse_response("https://docs.docker.com/");
+        assert_eq!(r.kind, ResponseKind::Url);
+    }
+}
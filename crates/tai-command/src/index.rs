@@ -0,0 +1,534 @@
+//! 项目文本文件的 BM25 倒排索引，存放在 `<项目>/.tai/index/`，按修改时间增量更新
+//!
+//! 文件按行切成重叠的片段，每个文件保存自己的倒排表（词 → 片段与词频），
+//! 这样某个文件变化时只需替换它自己的条目；查询时汇总各文件的倒排表计算 BM25
+
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+use tai_core::{find_project_tai_dir, user_tai_dir, TaiError, TaiResult};
+use tracing::{debug, warn};
+
+/// 索引格式版本，变化时整体重建
+const INDEX_VERSION: u32 = 1;
+const INDEX_FILE: &str = "bm25.json";
+/// 每个片段的行数
+const CHUNK_LINES: usize = 40;
+/// 相邻片段重叠的行数
+const CHUNK_OVERLAP: usize = 10;
+/// 跳过超过该大小的文件（字节）
+const MAX_FILE_SIZE: u64 = 512 * 1024;
+/// 最多索引的文件数
+const MAX_FILES: usize = 20_000;
+/// BM25 参数
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "are", "is", "was", "of", "to", "in", "on", "at", "by", "an", "or", "it",
+    "this", "that", "with", "from", "as", "be", "do", "does", "how", "what", "where", "which",
+    "who", "why", "when", "there", "here", "can", "use",
+];
+
+/// 一个片段：起止行号（从 1 开始，含）和词数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Chunk {
+    start: usize,
+    end: usize,
+    length: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileEntry {
+    /// 修改时间（Unix 毫秒）
+    mtime: u64,
+    size: u64,
+    chunks: Vec<Chunk>,
+    /// 词 → [(片段序号, 词频)]
+    postings: BTreeMap<String, Vec<(u32, u32)>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexData {
+    version: u32,
+    /// 相对项目根目录的路径 → 条目
+    files: BTreeMap<String, FileEntry>,
+}
+
+/// 检索命中的片段
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub path: String,
+    pub start: usize,
+    pub end: usize,
+    pub score: f64,
+}
+
+/// 项目索引
+pub struct ProjectIndex {
+    root: PathBuf,
+    data: IndexData,
+}
+
+/// 项目根目录：含 `.tai/` 的目录，其次是 git 仓库根目录
+///
+/// 都没有时返回错误，而不是把 cwd（可能是整个主目录）编入索引；
+/// 根目录的 `.tai/` 就是用户配置目录 `~/.tai/` 时同样拒绝，索引不写到用户配置目录中
+pub fn project_root(cwd: &Path) -> TaiResult<PathBuf> {
+    let root = find_project_tai_dir(cwd)
+        .as_deref()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .or_else(|| {
+            cwd.ancestors()
+                .find(|dir| dir.join(".git").exists())
+                .map(Path::to_path_buf)
+        })
+        .ok_or_else(|| {
+            TaiError::Other(
+                "当前目录不在项目中，无法建立索引：请在 git 仓库中运行，或在项目根目录创建 .tai/ 目录"
+                    .to_string(),
+            )
+        })?;
+    if root.join(".tai") == user_tai_dir() {
+        return Err(TaiError::Other(format!(
+            "{} 是用户主目录，不为其建立项目索引",
+            root.display()
+        )));
+    }
+    Ok(root)
+}
+
+impl ProjectIndex {
+    /// 加载索引并按修改时间增量更新，有变化时写回磁盘
+    pub fn open(root: &Path) -> TaiResult<Self> {
+        let dir = root.join(".tai").join("index");
+        let path = dir.join(INDEX_FILE);
+        let data = match fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str::<IndexData>(&content) {
+                Ok(data) if data.version == INDEX_VERSION => data,
+                Ok(_) => {
+                    debug!("索引版本变化，重建");
+                    IndexData::default()
+                }
+                Err(e) => {
+                    warn!("索引 {:?} 无法解析，重建: {}", path, e);
+                    IndexData::default()
+                }
+            },
+            Err(_) => IndexData::default(),
+        };
+
+        let mut index = Self {
+            root: root.to_path_buf(),
+            data,
+        };
+        if index.update() {
+            fs::create_dir_all(&dir)?;
+            // 索引是本地生成的缓存，不应提交到仓库
+            let ignore = dir.join(".gitignore");
+            if !ignore.exists() {
+                fs::write(&ignore, "*\n")?;
+            }
+            fs::write(&path, serde_json::to_string(&index.data)?)?;
+        }
+        Ok(index)
+    }
+
+    /// 与磁盘上的文件同步，返回是否有变化
+    fn update(&mut self) -> bool {
+        let mut changed = self.data.version != INDEX_VERSION;
+        self.data.version = INDEX_VERSION;
+        let mut old = std::mem::take(&mut self.data.files);
+        let (mut added, mut reused) = (0, 0);
+
+        // 遵循 .gitignore，跳过隐藏文件（包括 .tai 自身）
+        let walker = WalkBuilder::new(&self.root).require_git(false).build();
+        for entry in walker.filter_map(Result::ok) {
+            if self.data.files.len() >= MAX_FILES {
+                warn!("项目文件超过 {} 个，其余文件未编入索引", MAX_FILES);
+                break;
+            }
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.len() > MAX_FILE_SIZE {
+                continue;
+            }
+            let Ok(relative) = entry.path().strip_prefix(&self.root) else {
+                continue;
+            };
+            let key = relative.to_string_lossy().replace('\\', "/");
+            let mtime = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_millis() as u64);
+
+            if let Some(entry) = old
+                .remove(&key)
+                .filter(|e| e.mtime == mtime && e.size == meta.len())
+            {
+                reused += 1;
+                self.data.files.insert(key, entry);
+                continue;
+            }
+            changed = true;
+            let Ok(bytes) = fs::read(entry.path()) else {
+                continue;
+            };
+            added += 1;
+            // 二进制文件只记录修改时间，不编入索引，避免每次重新读取
+            let entry = if bytes.contains(&0) {
+                FileEntry {
+                    mtime,
+                    size: meta.len(),
+                    chunks: Vec::new(),
+                    postings: BTreeMap::new(),
+                }
+            } else {
+                index_file(&key, &String::from_utf8_lossy(&bytes), mtime, meta.len())
+            };
+            self.data.files.insert(key, entry);
+        }
+        // old 中剩下的是已删除的文件
+        changed |= !old.is_empty();
+        debug!(
+            "索引更新: 复用 {} 个文件，重新索引 {} 个，移除 {} 个",
+            reused,
+            added,
+            old.len()
+        );
+        changed
+    }
+
+    /// BM25 检索，返回得分最高的 k 个片段
+    pub fn search(&self, query: &str, k: usize) -> Vec<Hit> {
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let total_chunks: usize = self.data.files.values().map(|f| f.chunks.len()).sum();
+        if total_chunks == 0 {
+            return Vec::new();
+        }
+        let total_length: u64 = self
+            .data
+            .files
+            .values()
+            .flat_map(|f| f.chunks.iter())
+            .map(|c| c.length as u64)
+            .sum();
+        let average = (total_length as f64 / total_chunks as f64).max(1.0);
+
+        let mut scores: HashMap<(&str, u32), f64> = HashMap::new();
+        for term in &terms {
+            let df: usize = self
+                .data
+                .files
+                .values()
+                .filter_map(|f| f.postings.get(term))
+                .map(Vec::len)
+                .sum();
+            if df == 0 {
+                continue;
+            }
+            let idf = (1.0 + (total_chunks as f64 - df as f64 + 0.5) / (df as f64 + 0.5)).ln();
+            for (path, file) in &self.data.files {
+                let Some(postings) = file.postings.get(term) else {
+                    continue;
+                };
+                for &(chunk, tf) in postings {
+                    let length = file.chunks[chunk as usize].length as f64;
+                    let tf = tf as f64;
+                    let score =
+                        idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * length / average));
+                    *scores.entry((path.as_str(), chunk)).or_default() += score;
+                }
+            }
+        }
+
+        let mut hits: Vec<Hit> = scores
+            .into_iter()
+            .map(|((path, chunk), score)| {
+                let chunk = &self.data.files[path].chunks[chunk as usize];
+                Hit {
+                    path: path.to_string(),
+                    start: chunk.start,
+                    end: chunk.end,
+                    score,
+                }
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.path.cmp(&b.path))
+                .then_with(|| a.start.cmp(&b.start))
+        });
+        hits.truncate(k);
+        hits
+    }
+
+    /// 读取命中片段的内容，拼成提示词中的上下文，超过 max_chars 的部分丢弃
+    pub fn render_hits(&self, hits: &[Hit], max_chars: usize) -> String {
+        let mut out = String::new();
+        for hit in hits {
+            let Ok(content) = fs::read_to_string(self.root.join(&hit.path)) else {
+                continue;
+            };
+            let lines: Vec<&str> = content
+                .lines()
+                .skip(hit.start - 1)
+                .take(hit.end + 1 - hit.start)
+                .collect();
+            let block = format!(
+                "--- {}:{}-{} ---\n{}\n\n",
+                hit.path,
+                hit.start,
+                hit.end,
+                lines.join("\n")
+            );
+            if out.len() + block.len() > max_chars {
+                break;
+            }
+            out.push_str(&block);
+        }
+        out
+    }
+}
+
+fn index_file(path: &str, text: &str, mtime: u64, size: u64) -> FileEntry {
+    let lines: Vec<&str> = text.lines().collect();
+    // 路径中的词加到每个片段里，让文件名本身也能命中
+    let path_terms = tokenize(path);
+    let mut chunks = Vec::new();
+    let mut postings: BTreeMap<String, Vec<(u32, u32)>> = BTreeMap::new();
+
+    let mut start = 0;
+    while start < lines.len().max(1) {
+        let end = (start + CHUNK_LINES).min(lines.len());
+        let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+        let mut length = 0;
+        for term in lines[start..end]
+            .iter()
+            .flat_map(|line| tokenize(line))
+            .chain(path_terms.iter().cloned())
+        {
+            *counts.entry(term).or_default() += 1;
+            length += 1;
+        }
+        let id = chunks.len() as u32;
+        chunks.push(Chunk {
+            start: start + 1,
+            end: end.max(start + 1),
+            length,
+        });
+        for (term, tf) in counts {
+            postings.entry(term).or_default().push((id, tf));
+        }
+        if end >= lines.len() {
+            break;
+        }
+        start += CHUNK_LINES - CHUNK_OVERLAP;
+    }
+
+    FileEntry {
+        mtime,
+        size,
+        chunks,
+        postings,
+    }
+}
+
+/// 分词：英文按非字母数字切分，标识符再按驼峰和下划线拆开，统一小写并做简单词干化；
+/// 中文等连续的 CJK 字符取二元组（单字时取单字）
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut cjk: Vec<char> = Vec::new();
+
+    let flush_word = |word: &mut String, tokens: &mut Vec<String>| {
+        if !word.is_empty() {
+            push_identifier(word, tokens);
+            word.clear();
+        }
+    };
+    let flush_cjk = |cjk: &mut Vec<char>, tokens: &mut Vec<String>| {
+        match cjk.len() {
+            0 => {}
+            1 => tokens.push(cjk[0].to_string()),
+            _ => tokens.extend(cjk.windows(2).map(|pair| pair.iter().collect::<String>())),
+        }
+        cjk.clear();
+    };
+
+    for c in text.chars() {
+        if is_cjk(c) {
+            flush_word(&mut word, &mut tokens);
+            cjk.push(c);
+        } else if c.is_alphanumeric() || c == '_' {
+            flush_cjk(&mut cjk, &mut tokens);
+            word.push(c);
+        } else {
+            flush_word(&mut word, &mut tokens);
+            flush_cjk(&mut cjk, &mut tokens);
+        }
+    }
+    flush_word(&mut word, &mut tokens);
+    flush_cjk(&mut cjk, &mut tokens);
+    tokens
+}
+
+/// 标识符本身和拆出的各部分：`handleAuthRequest` → handleauthrequest、handl、auth、request
+fn push_identifier(word: &str, tokens: &mut Vec<String>) {
+    let mut parts: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in word.chars() {
+        if c == '_' {
+            if !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            parts.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_numeric();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+
+    let whole = word.to_lowercase();
+    if parts.len() > 1 {
+        push_token(whole.trim_matches('_'), tokens);
+    }
+    for part in &parts {
+        push_token(part, tokens);
+    }
+}
+
+fn push_token(token: &str, tokens: &mut Vec<String>) {
+    if token.chars().count() < 2
+        || STOPWORDS.contains(&token)
+        || token.chars().all(|c| c.is_ascii_digit())
+    {
+        return;
+    }
+    tokens.push(stem(token));
+}
+
+/// 去掉常见英文词尾，让 handle / handled / handling 归到同一个词
+fn stem(word: &str) -> String {
+    if !word.is_ascii() || word.len() <= 4 {
+        return word.to_string();
+    }
+    let mut stem = word;
+    for suffix in ["ing", "ed", "es", "s"] {
+        if stem.ends_with(suffix)
+            && !(suffix == "s" && stem.ends_with("ss"))
+            && stem.len() - suffix.len() >= 3
+        {
+            stem = &stem[..stem.len() - suffix.len()];
+            break;
+        }
+    }
+    if stem.len() > 3 {
+        stem = stem.strip_suffix('e').unwrap_or(stem);
+    }
+    stem.to_string()
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{AC00}'..='\u{D7AF}')
+}
+
+/// 检索结果的简短列表，用于终端展示
+pub fn describe_hits(hits: &[Hit]) -> String {
+    let mut out = String::new();
+    for hit in hits {
+        let _ = writeln!(out, "  {}:{}-{}", hit.path, hit.start, hit.end);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("fn handleAuthRequest(user_id)"),
+            vec![
+                "fn",
+                "handleauthrequest",
+                "handl",
+                "auth",
+                "request",
+                "user_id",
+                "user",
+                "id"
+            ]
+        );
+        assert_eq!(tokenize("where is auth handled"), vec!["auth", "handl"]);
+        assert_eq!(tokenize("用户登录"), vec!["用户", "户登", "登录"]);
+    }
+
+    #[test]
+    fn test_index_search_and_update() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/auth.rs"),
+            "/// 校验请求中的 token\npub fn check_token(token: &str) -> bool {\n    !token.is_empty()\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {\n    serve();\n}\n").unwrap();
+        // 既没有 .tai/ 也不在 git 仓库中时不建立索引
+        assert!(project_root(&root.join("src")).is_err());
+
+        let index = ProjectIndex::open(root).unwrap();
+        assert!(root.join(".tai/index").join(INDEX_FILE).is_file());
+        assert_eq!(project_root(&root.join("src")).unwrap(), root);
+        let hits = index.search("where is the token checked", 3);
+        assert_eq!(hits[0].path, "src/auth.rs");
+        assert_eq!((hits[0].start, hits[0].end), (1, 4));
+        assert!(index
+            .render_hits(&hits[..1], 1000)
+            .starts_with("--- src/auth.rs:1-4 ---\n/// 校验请求中的 token"));
+
+        // 修改后重新打开只更新变化的文件
+        fs::write(
+            root.join("src/main.rs"),
+            "fn main() {\n    check_token(\"x\");\n}\n",
+        )
+        .unwrap();
+        fs::remove_file(root.join("src/auth.rs")).unwrap();
+        let mut index = ProjectIndex::open(root).unwrap();
+        assert!(!index.update());
+        assert_eq!(index.search("check_token", 3)[0].path, "src/main.rs");
+        assert_eq!(index.data.files.len(), 1);
+    }
+}
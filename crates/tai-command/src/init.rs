@@ -0,0 +1,49 @@
+use clap::Args;
+use tai_core::{TaiError, TaiResult};
+use tracing::debug;
+
+use crate::shell::ShellKind;
+
+const BASH_SCRIPT: &str = include_str!("../../../assets/shell/tai.bash");
+const ZSH_SCRIPT: &str = include_str!("../../../assets/shell/tai.zsh");
+const FISH_SCRIPT: &str = include_str!("../../../assets/shell/tai.fish");
+const POWERSHELL_SCRIPT: &str = include_str!("../../../assets/shell/tai.ps1");
+
+#[derive(Args, Debug)]
+pub struct InitArgs {
+    /// 目标 shell
+    #[arg(value_enum)]
+    pub shell: ShellKind,
+
+    /// 触发键，与 Ctrl 组合（默认 g，即 Ctrl-G）
+    #[arg(long, default_value_t = 'g')]
+    pub key: char,
+}
+
+impl InitArgs {
+    pub async fn handle(self) -> TaiResult<()> {
+        let key = self.key.to_ascii_lowercase();
+        if !key.is_ascii_lowercase() {
+            return Err(TaiError::Other(format!(
+                "无效的触发键 {:?}，请使用 a-z 之间的字母",
+                self.key
+            )));
+        }
+        debug!("输出 {} 集成脚本，触发键 Ctrl-{}", self.shell.name(), key);
+
+        let script = match self.shell {
+            ShellKind::Bash => BASH_SCRIPT,
+            ShellKind::Zsh => ZSH_SCRIPT,
+            ShellKind::Fish => FISH_SCRIPT,
+            ShellKind::Powershell => POWERSHELL_SCRIPT,
+        };
+        let script = script
+            .replace("{key_label}", &format!("Ctrl-{}", key.to_ascii_uppercase()))
+            .replace("{bash_key}", &format!("\\C-{}", key))
+            .replace("{zsh_key}", &format!("^{}", key.to_ascii_uppercase()))
+            .replace("{fish_key}", &format!("\\c{}", key))
+            .replace("{ps_key}", &format!("Ctrl+{}", key));
+        print!("{}", script);
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+use tai_core::{user_tai_dir, TaiError, TaiResult};
+use tracing::{debug, warn};
+
+/// 学习库最多保留的记录数，超出后丢弃最早的记录
+const MAX_RECORDS: usize = 2000;
+/// 相似度低于该值的历史记录不作为示例
+const MIN_SIMILARITY: f64 = 0.2;
+
+/// 用户对生成命令的处理结果
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    /// 执行成功
+    Ran,
+    Copied,
+    /// 输出到标准输出（shell 集成插入命令行、管道），未经 tai 执行
+    Printed,
+    /// 执行失败（退出码非 0 或被策略拒绝）
+    Failed,
+    Discarded,
+}
+
+/// 一次 `tai go` 的记录
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct GoRecord {
+    time: String,
+    request: String,
+    /// 模型生成的原始命令
+    generated: String,
+    /// 用户编辑后的命令（未编辑时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    edited: Option<String>,
+    outcome: Outcome,
+}
+
+impl GoRecord {
+    /// 被采纳时最终使用的命令，优先取编辑后的版本
+    fn accepted_command(&self) -> Option<&str> {
+        match self.outcome {
+            Outcome::Failed | Outcome::Discarded => None,
+            _ => Some(self.edited.as_deref().unwrap_or(&self.generated)),
+        }
+    }
+}
+
+/// 学习库 ~/.tai/learn/go.jsonl
+fn store_path() -> PathBuf {
+    user_tai_dir().join("learn").join("go.jsonl")
+}
+
+/// 追加一条记录；写入失败只记录日志，不影响命令本身
+pub fn record(request: &str, generated: &str, final_command: &str, outcome: Outcome) {
+    let record = GoRecord {
+        time: Local::now().to_rfc3339(),
+        request: request.trim().to_string(),
+        generated: generated.to_string(),
+        edited: (final_command != generated).then(|| final_command.to_string()),
+        outcome,
+    };
+    if let Err(e) = append(&record) {
+        warn!("保存 go 学习记录失败: {}", e);
+    }
+}
+
+fn append(record: &GoRecord) -> TaiResult<()> {
+    let path = store_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| TaiError::FileError(format!("无法创建目录 {:?}: {}", dir, e)))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| TaiError::FileError(format!("无法打开 {:?}: {}", path, e)))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)
+        .map_err(|e| TaiError::FileError(format!("无法写入 {:?}: {}", path, e)))?;
+    debug!("go 学习记录: {:?}", record.outcome);
+
+    let records = load();
+    if records.len() > MAX_RECORDS {
+        let keep = &records[records.len() - MAX_RECORDS..];
+        let content: Vec<String> = keep
+            .iter()
+            .filter_map(|r| serde_json::to_string(r).ok())
+            .collect();
+        fs::write(&path, content.join("\n") + "\n")
+            .map_err(|e| TaiError::FileError(format!("无法写入 {:?}: {}", path, e)))?;
+    }
+    Ok(())
+}
+
+/// 读取全部记录，跳过无法解析的行
+fn load() -> Vec<GoRecord> {
+    let Ok(content) = fs::read_to_string(store_path()) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// 清空学习库，返回删除的记录数
+pub fn forget() -> TaiResult<usize> {
+    let path = store_path();
+    let count = load().len();
+    if path.exists() {
+        fs::remove_file(&path)
+            .map_err(|e| TaiError::FileError(format!("无法删除 {:?}: {}", path, e)))?;
+    }
+    Ok(count)
+}
+
+/// 查找与 request 最相似的已采纳记录，返回 (描述, 命令)，同一命令只保留一次
+pub fn similar_examples(request: &str, limit: usize) -> Vec<(String, String)> {
+    let query = tokens(request);
+    let mut scored: Vec<(f64, usize, GoRecord)> = load()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, r)| r.accepted_command().is_some())
+        .map(|(i, r)| (similarity(&query, &tokens(&r.request)), i, r))
+        .filter(|(score, _, _)| *score >= MIN_SIMILARITY)
+        .collect();
+    // 相似度相同时新的记录优先
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+
+    let mut seen = HashSet::new();
+    scored
+        .into_iter()
+        .filter_map(|(_, _, r)| {
+            let command = r.accepted_command()?.to_string();
+            seen.insert(command.clone()).then_some((r.request, command))
+        })
+        .take(limit)
+        .collect()
+}
+
+/// 渲染为提示词中的示例段落
+pub fn examples_prompt(examples: &[(String, String)]) -> String {
+    let mut text = "【用户习惯】\n以下是用户过去采纳过的类似命令，请参考其中的工具和写法偏好：".to_string();
+    for (request, command) in examples {
+        let response = serde_json::json!({"kind": "command", "command": command});
+        text.push_str(&format!("\n用户：{}\n返回：{}", request, response));
+    }
+    text
+}
+
+/// 英文按单词切分，中文按单字和相邻双字切分
+fn tokens(text: &str) -> HashSet<String> {
+    let mut set = HashSet::new();
+    let mut word = String::new();
+    let mut previous_cjk: Option<char> = None;
+
+    for c in text.to_lowercase().chars() {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                set.insert(std::mem::take(&mut word));
+            }
+            set.insert(c.to_string());
+            if let Some(p) = previous_cjk {
+                set.insert(format!("{}{}", p, c));
+            }
+            previous_cjk = Some(c);
+        } else {
+            previous_cjk = None;
+            if c.is_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                word.push(c);
+            } else if !word.is_empty() {
+                set.insert(std::mem::take(&mut word));
+            }
+        }
+    }
+    if !word.is_empty() {
+        set.insert(word);
+    }
+    set
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{4e00}'..='\u{9fff}' | '\u{3400}'..='\u{4dbf}')
+}
+
+/// Jaccard 相似度
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity_prefers_related_requests() {
+        let query = tokens("查找包含 TODO 的文件");
+        let related = tokens("查找包含 FIXME 的文件");
+        let unrelated = tokens("查看磁盘使用情况");
+        assert!(similarity(&query, &related) >= MIN_SIMILARITY);
+        assert!(similarity(&query, &related) > similarity(&query, &unrelated));
+        assert!(similarity(&query, &unrelated) < MIN_SIMILARITY);
+    }
+}
@@ -1,15 +1,41 @@
 mod ask;
+mod audit;
+mod clipboard;
+mod commit;
 mod config;
 mod r#do;
+mod diff;
+mod edit;
+mod editor;
+mod environment;
+mod explain;
+mod fix;
+mod git;
 mod go;
+mod index;
+mod init;
+mod learning;
 mod model;
+mod project;
 mod provider;
+mod review;
+mod shell;
+mod tools;
+mod undo;
 
 pub use ask::AskArgs;
-pub use config::ConfigCommand;
+pub use audit::AuditArgs;
+pub use commit::CommitArgs;
+pub use config::ConfigArgs;
 pub use r#do::DoArgs;
+pub use edit::EditArgs;
+pub use explain::ExplainArgs;
+pub use fix::FixArgs;
 pub use go::GoArgs;
+pub use init::InitArgs;
 pub use model::ModelArgs;
+pub use review::ReviewArgs;
+pub use undo::UndoArgs;
 
 use clap::{Parser, Subcommand};
 use tai_core::TaiResult;
@@ -32,8 +58,7 @@ impl Cli {
             None => {
                 let path = self.dir_path.unwrap_or_else(|| ".".to_string());
                 debug!("打开目录: {}", path);
-                println!("open directory: {}", path);
-                Ok(())
+                project::open(&path).await
             }
         }
     }
@@ -43,9 +68,17 @@ impl Cli {
 pub enum Commands {
     Model(ModelArgs),
     Do(DoArgs),
+    Edit(EditArgs),
+    Commit(CommitArgs),
+    Review(ReviewArgs),
     Ask(AskArgs),
     Go(GoArgs),
-    Config,
+    Explain(ExplainArgs),
+    Fix(FixArgs),
+    Init(InitArgs),
+    Config(ConfigArgs),
+    Audit(AuditArgs),
+    Undo(UndoArgs),
 }
 
 impl Commands {
@@ -53,9 +86,17 @@ impl Commands {
         match self {
             Commands::Model(args) => args.handle().await,
             Commands::Do(args) => args.handle().await,
+            Commands::Edit(args) => args.handle().await,
+            Commands::Commit(args) => args.handle().await,
+            Commands::Review(args) => args.handle().await,
             Commands::Ask(args) => args.handle().await,
             Commands::Go(args) => args.handle().await,
-            Commands::Config => ConfigCommand.handle().await,
+            Commands::Explain(args) => args.handle().await,
+            Commands::Fix(args) => args.handle().await,
+            Commands::Init(args) => args.handle().await,
+            Commands::Config(args) => args.handle().await,
+            Commands::Audit(args) => args.handle().await,
+            Commands::Undo(args) => args.handle().await,
         }
     }
 }
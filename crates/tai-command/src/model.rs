@@ -1,6 +1,6 @@
 use clap::{Args, Subcommand};
 use tai_ai::{load_active_model, load_providers, resolve_active, save_active_model, save_providers, ActiveModel};
-use tai_core::{TaiError, TaiResult};
+use tai_core::{TaiConfig, TaiError, TaiResult};
 use tai_tui::{config_providers, select_model, ModelItem, ProviderEntry};
 use tracing::debug;
 
@@ -73,6 +73,7 @@ impl ModelArgs {
                 })?;
                 debug!("已切换到模型: {}/{}", provider.provider, item.model);
                 println!("已切换到 {}/{}", provider.provider, item.model);
+                warn_model_pinned();
             }
             Ok(None) => {
                 debug!("用户取消了模型选择");
@@ -136,8 +137,17 @@ fn switch_model(providers: &[tai_ai::ProviderConfig], model_name: &str) -> TaiRe
             })?;
             debug!("已切换到模型: {}/{}", provider.provider, model);
             println!("已切换到 {}/{}", provider.provider, model);
+            warn_model_pinned();
             return Ok(());
         }
     }
     Err(TaiError::ModelNotFound(model_name.to_string()))
 }
+
+/// 项目配置或 TAI_MODEL 固定了模型时，state.json 中的切换不会生效
+fn warn_model_pinned() {
+    let Ok(layered) = TaiConfig::load_layered() else { return };
+    if let (Some(model), Some(origin)) = (&layered.config.model, layered.origins.get("model")) {
+        println!("  注意: 当前生效的模型由{}固定为 {}", origin, model);
+    }
+}
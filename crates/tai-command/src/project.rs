@@ -0,0 +1,164 @@
+//! `tai <dir>`：项目会话，每个问题都自动附带项目概况（文件结构、语言、关键文件）
+
+use std::{fmt::Write as _, io::IsTerminal, path::Path};
+use tai_ai::ChatTool;
+use tai_core::{TaiConfig, TaiError, TaiResult};
+use tai_tui::edit_line;
+use tracing::{debug, warn};
+
+use crate::ask::{do_ask, TOOLS_HINT};
+use crate::provider::{ensure_active_provider, recover_auth_error};
+use crate::tools::builtin_tools;
+
+mod summary;
+pub(crate) use summary::ProjectSummary;
+
+const PROMPT: &str = "你是熟悉当前项目的开发助手。下面是项目概况，请结合它回答用户的问题；\
+概况中没有的文件内容不要猜测，需要时请说明应查看哪些文件。";
+
+/// 提示词中保留的最近对话轮数
+const MAX_HISTORY_TURNS: usize = 6;
+/// 历史回答写入提示词的最大字符数
+const MAX_HISTORY_ANSWER_CHARS: usize = 2000;
+
+const HELP: &str = "\
+  /tools   开启或关闭只读工具（读取文件、列目录、搜索代码、git 状态）
+  /clear   清空对话记录
+  /exit    退出（空行或 Esc 同样退出）";
+
+struct Session {
+    summary: ProjectSummary,
+    /// 项目概况的提示词，打开会话时生成一次
+    context: String,
+    /// (问题, 回答)
+    turns: Vec<(String, String)>,
+    tools: Vec<ChatTool>,
+}
+
+impl Session {
+    fn prompt(&self, question: &str, role: Option<&str>) -> String {
+        let mut prompt = String::from(PROMPT);
+        if let Some(role) = role {
+            let _ = write!(prompt, "\n\n{}", role);
+        }
+        let _ = write!(prompt, "\n\n{}", self.context);
+        let start = self.turns.len().saturating_sub(MAX_HISTORY_TURNS);
+        if start < self.turns.len() {
+            prompt.push_str("\n【之前的对话】\n");
+            for (q, a) in &self.turns[start..] {
+                let answer = if a.chars().count() > MAX_HISTORY_ANSWER_CHARS {
+                    let head: String = a.chars().take(MAX_HISTORY_ANSWER_CHARS).collect();
+                    head + "...（已截断）"
+                } else {
+                    a.clone()
+                };
+                let _ = write!(prompt, "问：{}\n答：{}\n\n", q, answer.trim());
+            }
+        }
+        let _ = write!(prompt, "\n【问题】\n{}", question);
+        if !self.tools.is_empty() {
+            let _ = write!(prompt, "\n\n{}", TOOLS_HINT);
+        }
+        prompt
+    }
+
+    fn toggle_tools(&mut self) -> TaiResult<()> {
+        if self.tools.is_empty() {
+            self.tools = builtin_tools()?;
+            println!("  ✓ 已开启只读工具");
+        } else {
+            self.tools.clear();
+            println!("  已关闭只读工具");
+        }
+        Ok(())
+    }
+}
+
+/// 扫描目录并进入交互式项目会话
+pub async fn open(path: &str) -> TaiResult<()> {
+    let summary = ProjectSummary::scan(Path::new(path))?;
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err(TaiError::Other(
+            "项目会话需要交互输入，请在终端中运行".to_string(),
+        ));
+    }
+    // 之后的工具调用、相对路径都以项目目录为准
+    std::env::set_current_dir(&summary.root)?;
+    print_overview(&summary);
+
+    let config = TaiConfig::load()?;
+    let role = config.role.clone().filter(|r| !r.trim().is_empty());
+    let mut context = ensure_active_provider().await?;
+    let mut session = Session {
+        context: summary.to_prompt(),
+        summary,
+        turns: Vec::new(),
+        tools: Vec::new(),
+    };
+    debug!("项目上下文 {} 字符", session.context.len());
+
+    let title = format!("{} › 输入问题（/help 查看命令）", session.summary.name);
+    while let Some(input) =
+        edit_line(&title, "").map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?
+    {
+        match input.as_str() {
+            "/exit" | "/quit" => break,
+            "/help" => {
+                println!("{}", HELP);
+                continue;
+            }
+            "/clear" => {
+                session.turns.clear();
+                println!("  已清空对话记录");
+                continue;
+            }
+            "/tools" => {
+                session.toggle_tools()?;
+                continue;
+            }
+            _ => {}
+        }
+
+        println!("> {}", input);
+        let prompt = session.prompt(&input, role.as_deref());
+        loop {
+            debug!("使用模型: {}/{}", context.0.provider, context.1);
+            match do_ask(&context.0, &context.1, &prompt, &session.tools, &config).await {
+                Ok(answer) => {
+                    session.turns.push((input, answer));
+                    break;
+                }
+                Err(TaiError::AuthError(ref name)) => {
+                    context = recover_auth_error(name).await?;
+                }
+                // 单个问题失败不结束会话
+                Err(e) => {
+                    warn!("回答失败: {}", e);
+                    println!("  ✗ {}", e);
+                    break;
+                }
+            }
+        }
+        println!();
+    }
+    println!("已退出项目会话");
+    Ok(())
+}
+
+fn print_overview(summary: &ProjectSummary) {
+    println!("项目 {}（{}）", summary.name, summary.root.display());
+    println!(
+        "  {}{} 个文件{}",
+        if summary.truncated { "超过 " } else { "" },
+        summary.files.len(),
+        match summary.language_line() {
+            line if line.is_empty() => String::new(),
+            line => format!("，{}", line),
+        }
+    );
+    if !summary.manifests.is_empty() {
+        let names: Vec<&str> = summary.manifests.iter().map(|(p, _)| p.as_str()).collect();
+        println!("  关键文件: {}", names.join("、"));
+    }
+    println!();
+}
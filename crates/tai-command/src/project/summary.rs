@@ -0,0 +1,348 @@
+use ignore::WalkBuilder;
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+use tai_core::{TaiError, TaiResult};
+use tracing::{debug, warn};
+
+/// 扫描的文件数上限，超过后不再继续
+const MAX_SCAN_FILES: usize = 20_000;
+/// 文件结构中展开的目录层数
+const MAX_TREE_DEPTH: usize = 4;
+/// 每个目录最多列出的条目数
+const MAX_DIR_ENTRIES: usize = 20;
+/// 文件结构的总行数上限
+const MAX_TREE_LINES: usize = 300;
+/// 关键文件最多收录的个数
+const MAX_MANIFESTS: usize = 8;
+/// 每个关键文件收录的最大字符数
+const MAX_MANIFEST_CHARS: usize = 3000;
+/// 关键文件最多查找到第几层目录（根目录为 0）
+const MAX_MANIFEST_DEPTH: usize = 2;
+
+/// 描述项目依赖和构建方式的文件
+const MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "Gemfile",
+    "composer.json",
+    "CMakeLists.txt",
+    "Makefile",
+];
+
+/// 扩展名 → 语言
+const LANGUAGES: &[(&str, &str)] = &[
+    ("rs", "Rust"),
+    ("py", "Python"),
+    ("js", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("cjs", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("go", "Go"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("swift", "Swift"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cc", "C++"),
+    ("cpp", "C++"),
+    ("cxx", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("rb", "Ruby"),
+    ("php", "PHP"),
+    ("lua", "Lua"),
+    ("sh", "Shell"),
+    ("bash", "Shell"),
+    ("zsh", "Shell"),
+    ("fish", "Shell"),
+    ("ps1", "PowerShell"),
+    ("html", "HTML"),
+    ("css", "CSS"),
+    ("scss", "CSS"),
+    ("vue", "Vue"),
+    ("svelte", "Svelte"),
+    ("sql", "SQL"),
+    ("md", "Markdown"),
+    ("toml", "TOML"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("json", "JSON"),
+];
+
+/// 项目概况：文件结构、语言分布和关键文件，作为项目会话中每个问题的上下文
+#[derive(Debug)]
+pub struct ProjectSummary {
+    pub root: PathBuf,
+    pub name: String,
+    /// 相对根目录的文件路径，已按 .gitignore 过滤
+    pub files: Vec<PathBuf>,
+    /// 扫描是否因文件过多而提前结束
+    pub truncated: bool,
+    /// (语言, 文件数, 字节数)，按字节数从大到小
+    pub languages: Vec<(String, usize, u64)>,
+    /// (相对路径, 内容)
+    pub manifests: Vec<(String, String)>,
+}
+
+impl ProjectSummary {
+    pub fn scan(root: &Path) -> TaiResult<Self> {
+        let root = root
+            .canonicalize()
+            .map_err(|e| TaiError::FileError(format!("{}: {}", root.display(), e)))?;
+        if !root.is_dir() {
+            return Err(TaiError::FileError(format!("{} 不是目录", root.display())));
+        }
+
+        let mut files = Vec::new();
+        let mut truncated = false;
+        let mut languages: BTreeMap<&str, (usize, u64)> = BTreeMap::new();
+        // .gitignore 不要求位于 git 仓库中也生效；隐藏文件默认跳过
+        let walker = WalkBuilder::new(&root)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build();
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!("扫描项目时跳过: {}", e);
+                    continue;
+                }
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            if files.len() >= MAX_SCAN_FILES {
+                truncated = true;
+                break;
+            }
+            let path = entry.path();
+            if let Some(language) = language_of(path) {
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                let stat = languages.entry(language).or_default();
+                stat.0 += 1;
+                stat.1 += size;
+            }
+            if let Ok(relative) = path.strip_prefix(&root) {
+                files.push(relative.to_path_buf());
+            }
+        }
+        debug!("扫描项目 {:?}: {} 个文件", root, files.len());
+
+        let mut languages: Vec<(String, usize, u64)> = languages
+            .into_iter()
+            .map(|(name, (count, bytes))| (name.to_string(), count, bytes))
+            .collect();
+        languages.sort_by_key(|l| std::cmp::Reverse(l.2));
+
+        let manifests = read_manifests(&root, &files);
+        let name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| root.display().to_string());
+        Ok(Self {
+            root,
+            name,
+            files,
+            truncated,
+            languages,
+            manifests,
+        })
+    }
+
+    /// 一行概况，如 `Rust 80%、Markdown 12%`
+    pub fn language_line(&self) -> String {
+        let total: u64 = self.languages.iter().map(|l| l.2).sum::<u64>().max(1);
+        self.languages
+            .iter()
+            .map(|(name, _, bytes)| (name, bytes * 100 / total))
+            .filter(|(_, percent)| *percent > 0)
+            .take(5)
+            .map(|(name, percent)| format!("{} {}%", name, percent))
+            .collect::<Vec<_>>()
+            .join("、")
+    }
+
+    /// 写入提示词的项目上下文
+    pub fn to_prompt(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "【项目】{}（{}）", self.name, self.root.display());
+        let _ = writeln!(
+            out,
+            "共 {}{} 个文件（已按 .gitignore 过滤）",
+            if self.truncated { "超过 " } else { "" },
+            self.files.len()
+        );
+        if !self.languages.is_empty() {
+            let languages: Vec<String> = self
+                .languages
+                .iter()
+                .map(|(name, count, _)| format!("{} {} 个文件", name, count))
+                .collect();
+            let _ = writeln!(out, "\n【语言】{}", languages.join("、"));
+        }
+        let _ = writeln!(out, "\n【文件结构】\n{}", self.render_tree());
+        if !self.manifests.is_empty() {
+            let _ = writeln!(out, "【关键文件】");
+            for (path, content) in &self.manifests {
+                let _ = writeln!(out, "--- {} ---\n{}\n", path, content.trim_end());
+            }
+        }
+        out
+    }
+
+    /// 缩进的文件树：目录在前，超过层数或条目数的部分折叠为计数
+    pub fn render_tree(&self) -> String {
+        let mut tree = Dir::default();
+        for path in &self.files {
+            tree.insert(path);
+        }
+        let mut lines = Vec::new();
+        tree.render(0, &mut lines);
+        if lines.len() > MAX_TREE_LINES {
+            let rest = lines.len() - MAX_TREE_LINES;
+            lines.truncate(MAX_TREE_LINES);
+            lines.push(format!("...（还有 {} 行未列出）", rest));
+        }
+        lines.join("\n")
+    }
+}
+
+#[derive(Default)]
+struct Dir {
+    dirs: BTreeMap<String, Dir>,
+    files: Vec<String>,
+}
+
+impl Dir {
+    fn insert(&mut self, path: &Path) {
+        let parts: Vec<String> = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let Some((file, dirs)) = parts.split_last() else {
+            return;
+        };
+        let mut dir = self;
+        for name in dirs {
+            dir = dir.dirs.entry(name.clone()).or_default();
+        }
+        dir.files.push(file.clone());
+    }
+
+    fn count(&self) -> usize {
+        self.files.len() + self.dirs.values().map(Dir::count).sum::<usize>()
+    }
+
+    fn render(&self, depth: usize, lines: &mut Vec<String>) {
+        let indent = "  ".repeat(depth);
+        let entries = self.dirs.len() + self.files.len();
+        for (name, dir) in self.dirs.iter().take(MAX_DIR_ENTRIES) {
+            if depth + 1 >= MAX_TREE_DEPTH {
+                lines.push(format!("{}{}/（{} 个文件）", indent, name, dir.count()));
+            } else {
+                lines.push(format!("{}{}/", indent, name));
+                dir.render(depth + 1, lines);
+            }
+        }
+        let room = MAX_DIR_ENTRIES.saturating_sub(self.dirs.len());
+        for name in self.files.iter().take(room) {
+            lines.push(format!("{}{}", indent, name));
+        }
+        if entries > MAX_DIR_ENTRIES {
+            lines.push(format!(
+                "{}...（还有 {} 项）",
+                indent,
+                entries - MAX_DIR_ENTRIES
+            ));
+        }
+    }
+}
+
+fn language_of(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(e, _)| *e == ext)
+        .map(|(_, name)| *name)
+}
+
+fn is_manifest(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let depth = path.components().count() - 1;
+    // README 只取根目录的
+    if depth == 0 && name.to_ascii_uppercase().starts_with("README") {
+        return true;
+    }
+    depth <= MAX_MANIFEST_DEPTH && MANIFESTS.contains(&name)
+}
+
+/// 读取关键文件：根目录的优先，超长的截断
+fn read_manifests(root: &Path, files: &[PathBuf]) -> Vec<(String, String)> {
+    let mut found: Vec<&PathBuf> = files.iter().filter(|p| is_manifest(p)).collect();
+    found.sort_by_key(|p| p.components().count());
+    found
+        .into_iter()
+        .take(MAX_MANIFESTS)
+        .filter_map(|path| {
+            let content = fs::read_to_string(root.join(path)).ok()?;
+            let content = if content.chars().count() > MAX_MANIFEST_CHARS {
+                let head: String = content.chars().take(MAX_MANIFEST_CHARS).collect();
+                head + "\n...（已截断）"
+            } else {
+                content
+            };
+            Some((path.display().to_string(), content))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_respects_gitignore() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        fs::write(root.join("README.md"), "# demo\n").unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("target/debug/demo"), "binary").unwrap();
+
+        let summary = ProjectSummary::scan(root).unwrap();
+        assert_eq!(
+            summary.files,
+            vec![
+                PathBuf::from("Cargo.toml"),
+                PathBuf::from("README.md"),
+                PathBuf::from("src/main.rs"),
+            ]
+        );
+        assert_eq!(
+            summary.render_tree(),
+            "src/\n  main.rs\nCargo.toml\nREADME.md"
+        );
+        assert_eq!(summary.manifests.len(), 2);
+        assert!(summary.to_prompt().contains("--- Cargo.toml ---"));
+        assert_eq!(summary.languages[0].0, "TOML");
+    }
+}
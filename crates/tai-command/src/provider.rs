@@ -1,15 +1,68 @@
-use tai_ai::{load_active_model, load_providers, resolve_active, update_provider_api_key, ProviderConfig};
-use tai_core::{TaiError, TaiResult};
-use tai_tui::prompt_api_key;
+use tai_ai::{
+    chat, find_model, forget_cached_api_key, load_active_model, load_providers, resolve_active,
+    update_provider_api_key, ActiveModel, ApiKeySource, ProviderConfig,
+};
+use tai_core::{TaiConfig, TaiError, TaiResult};
+use tai_tui::{prompt_api_key, Spinner};
 use tracing::debug;
 
-/// API Key 认证失败后，清空旧 key 并重新引导用户输入
+/// API Key 认证失败后，清空旧 key 并重新引导用户输入。
+/// Key 来自环境变量或外部命令时不改动 providers.json，提示用户检查对应来源。
 pub async fn recover_auth_error(provider_name: &str) -> TaiResult<(ProviderConfig, String)> {
+    let providers = load_providers()?;
+    if let Some(provider) = providers.iter().find(|p| p.provider == provider_name) {
+        let source = provider.api_key_source();
+        if source != ApiKeySource::Plain {
+            forget_cached_api_key(provider);
+            debug!("{} 的 API Key 来自 {}，不清空配置", provider_name, source.describe());
+            return Err(TaiError::ConfigError(format!(
+                "{} 的 API Key 认证失败，该 Key 来自{}，请检查后重试",
+                provider_name,
+                source.describe()
+            )));
+        }
+    }
+
     eprintln!("\n  ✗ API Key 认证失败，请重新输入");
     update_provider_api_key(provider_name, "")?;
     ensure_active_provider().await
 }
 
+/// 带 Spinner 的一次性对话，认证失败时引导重新输入 API Key 后重试
+pub async fn chat_with_retry(
+    context: &mut (ProviderConfig, String),
+    prompt: &str,
+    message: &str,
+) -> TaiResult<String> {
+    loop {
+        debug!("使用模型: {}/{}", context.0.provider, context.1);
+        let spinner = Spinner::new(message);
+        let result = chat(&context.0, &context.1, prompt).await;
+        spinner.finish_and_clear();
+        match result {
+            Ok(text) => return Ok(text),
+            Err(TaiError::AuthError(ref name)) => {
+                *context = recover_auth_error(name).await?;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 项目配置或 TAI_MODEL 指定的模型优先于 state.json
+fn configured_model(providers: &[ProviderConfig]) -> TaiResult<Option<ActiveModel>> {
+    if let Some(spec) = TaiConfig::load()?.model {
+        match find_model(providers, &spec) {
+            Some(active) => {
+                debug!("使用配置指定的模型: {}/{}", active.provider, active.model);
+                return Ok(Some(active));
+            }
+            None => return Err(TaiError::ModelNotFound(spec)),
+        }
+    }
+    load_active_model()
+}
+
 /// 解析当前激活的 provider 和模型，若 API Key 为空则引导用户填写
 /// 返回所有权的 (ProviderConfig, model_name)
 pub async fn ensure_active_provider() -> TaiResult<(ProviderConfig, String)> {
@@ -18,11 +71,11 @@ pub async fn ensure_active_provider() -> TaiResult<(ProviderConfig, String)> {
         return Err(TaiError::NoProviderConfig);
     }
 
-    let active = load_active_model()?;
+    let active = configured_model(&providers)?;
     let (provider, model) = resolve_active(&providers, active.as_ref())
         .ok_or(TaiError::NoActiveModel)?;
 
-    if !provider.api_key.is_empty() {
+    if provider.has_api_key() {
         return Ok((provider.clone(), model.to_string()));
     }
 
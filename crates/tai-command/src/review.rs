@@ -0,0 +1,87 @@
+use clap::Args;
+use std::io::IsTerminal;
+use tai_core::{TaiError, TaiResult};
+use tai_tui::{make_default_skin, show_markdown_view};
+use tracing::debug;
+
+use crate::clipboard::copy_for_viewer;
+use crate::git::{annotate_diff, chunk_diff, git};
+use crate::provider::{chat_with_retry, ensure_active_provider};
+
+const PROMPT: &str = "\
+你是一名严格而务实的代码审查者。请审查下面的 git diff，用中文输出 Markdown 格式的审查意见。
+
+diff 中新文件的行前标注了行号（删除的行没有行号）。引用代码时使用 `路径:行号` 的形式，如 `src/main.rs:42`。
+
+输出结构：
+## 概述
+一两句话说明这次改动做了什么。
+
+## 问题
+按严重程度（🔴 严重、🟡 建议、🔵 细节）列出，每条包含位置、问题和修改建议；没有问题时写“未发现明显问题”。
+重点关注：逻辑错误、边界情况、错误处理、安全隐患、并发与资源泄漏、性能、可读性，以及缺少的测试。
+
+## 总结
+是否可以合并，以及合并前必须处理的事项。
+
+只评论 diff 中的改动，不要臆测看不到的代码。";
+
+const MERGE_PROMPT: &str = "\
+下面是对同一次改动分段审查得到的意见。请合并为一份完整的审查报告：保持相同的结构（概述、问题、总结），\
+去掉重复的条目，保留所有 `路径:行号` 引用，问题按严重程度排序。只输出报告本身。";
+
+/// 每段 diff 的最大字符数；超过时分段审查后再合并
+const MAX_DIFF_CHARS: usize = 16_000;
+
+#[derive(Args, Debug)]
+pub struct ReviewArgs {
+    /// 要审查的提交范围，如 main..HEAD、HEAD~3（默认审查工作区相对 HEAD 的改动）
+    pub range: Option<String>,
+}
+
+impl ReviewArgs {
+    pub async fn handle(self) -> TaiResult<()> {
+        let range = self.range.as_deref().unwrap_or("HEAD");
+        let diff = git(&["diff", "--no-color", range, "--"])?;
+        if diff.trim().is_empty() {
+            return Err(TaiError::Other(format!("{} 没有需要审查的改动", range)));
+        }
+        let stat = git(&["diff", "--stat", "--no-color", range, "--"])?;
+        debug!("审查 {}: diff {} 字符", range, diff.len());
+
+        let mut context = ensure_active_provider().await?;
+        let chunks = chunk_diff(&diff, MAX_DIFF_CHARS);
+        let mut reviews = Vec::with_capacity(chunks.len());
+        for (i, chunk) in chunks.iter().enumerate() {
+            let prompt = format!(
+                "{}\n\n【改动统计】\n{}\n【diff】\n{}",
+                PROMPT,
+                stat.trim_end(),
+                annotate_diff(chunk)
+            );
+            let message = if chunks.len() == 1 {
+                "AI 审查中...".to_string()
+            } else {
+                format!("AI 审查中（{}/{}）...", i + 1, chunks.len())
+            };
+            reviews.push(chat_with_retry(&mut context, &prompt, &message).await?);
+        }
+        let review = if reviews.len() == 1 {
+            reviews.remove(0)
+        } else {
+            let prompt = format!("{}\n\n{}", MERGE_PROMPT, reviews.join("\n\n---\n\n"));
+            chat_with_retry(&mut context, &prompt, "AI 合并审查意见中...").await?
+        };
+        let review = format!("# 代码审查：{}\n\n{}", range, review.trim());
+
+        if !std::io::stdout().is_terminal() {
+            println!("{}", review);
+            return Ok(());
+        }
+        show_markdown_view(&review, make_default_skin(), Some(copy_for_viewer))
+            .map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+        // 退出查看器后保留在终端中，便于对照修改
+        make_default_skin().print_text(&review);
+        Ok(())
+    }
+}
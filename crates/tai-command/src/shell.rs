@@ -0,0 +1,299 @@
+use clap::ValueEnum;
+use std::{fs, path::Path, process::ExitStatus};
+use tai_core::{checkpoint, AuditDecision, AuditEntry, Policy, TaiError, TaiResult};
+use tracing::{debug, error, info};
+
+/// 可通过 `--shell` 指定的目标 shell
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShellKind {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl ShellKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShellKind::Bash => "bash",
+            ShellKind::Zsh => "zsh",
+            ShellKind::Fish => "fish",
+            ShellKind::Powershell => "powershell",
+        }
+    }
+
+    /// 执行该 shell 的程序名（PowerShell 优先使用跨平台的 pwsh）
+    pub fn program(&self) -> String {
+        match self {
+            ShellKind::Powershell if cfg!(windows) => "powershell".to_string(),
+            ShellKind::Powershell => "pwsh".to_string(),
+            other => other.name().to_string(),
+        }
+    }
+}
+
+/// 用户的 shell：Unix 取 $SHELL（缺省 /bin/sh），Windows 使用 cmd
+pub fn user_shell() -> String {
+    if cfg!(windows) {
+        std::env::var("COMSPEC").unwrap_or_else(|_| "cmd".to_string())
+    } else {
+        std::env::var("SHELL")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "/bin/sh".to_string())
+    }
+}
+
+/// 启动进程前按执行策略检查命令：违反策略时返回 PolicyViolation，
+/// dry-run 模式下只打印命令并返回 false；这两种情况都写入审计日志
+fn enforce_policy(command: &str) -> TaiResult<bool> {
+    let policy = Policy::load()?;
+    let cwd = std::env::current_dir().unwrap_or_else(|_| policy.root.clone());
+    if let Err(e) = policy.check_command(command, &cwd) {
+        AuditEntry::command(command, AuditDecision::Denied)
+            .with_reason(&e.to_string())
+            .record();
+        return Err(e);
+    }
+    if policy.is_dry_run() {
+        info!("dry-run 模式，跳过执行: {}", command);
+        AuditEntry::command(command, AuditDecision::DryRun).record();
+        println!("  [dry-run] 未执行: {}", command);
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// 在指定 shell 中于当前目录执行命令，返回退出码和输出内容
+///
+/// 命令先经过执行策略检查，dry-run 模式下不执行、返回 None；
+/// 执行前为命令会写入的文件创建检查点（`tai undo` 可恢复），
+/// 执行结果连同 decision（操作是如何被批准的）写入审计日志。
+/// Unix 上在伪终端中运行：命令看到的是终端（保留颜色、进度条，可以交互输入），
+/// 捕获的输出去掉 ANSI 控制序列；其他平台通过管道捕获 stdout 和 stderr
+pub async fn capture_with_shell(
+    shell: &str,
+    command: &str,
+    decision: AuditDecision,
+) -> TaiResult<Option<(i32, String)>> {
+    if !enforce_policy(command)? {
+        return Ok(None);
+    }
+    let cwd = std::env::current_dir()?;
+    checkpoint::snapshot_command(command, &cwd)?;
+    debug!("执行命令并捕获输出: {} -c {}", shell, command);
+    let (code, output) = spawn_and_capture(shell, command).await?;
+    AuditEntry::command(command, decision)
+        .with_exit_code(code)
+        .with_output(output.as_bytes())
+        .record();
+    Ok(Some((code, output)))
+}
+
+/// 不经过 shell 直接运行程序，把 input 写入其标准输入，返回退出码和输出内容
+///
+/// 用于参数由 tai 自己构造、不应交给 shell 解释的命令（如 `git commit -F -`）；
+/// 与 capture_with_shell 一样先经过执行策略检查和检查点，结果写入审计日志。
+/// args 应是不含空白的简单参数，审计日志和策略检查使用它们以空格连接后的命令行
+pub fn capture_program(
+    args: &[&str],
+    input: &[u8],
+    decision: AuditDecision,
+) -> TaiResult<Option<(i32, String)>> {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    let command = args.join(" ");
+    if !enforce_policy(&command)? {
+        return Ok(None);
+    }
+    let cwd = std::env::current_dir()?;
+    checkpoint::snapshot_command(&command, &cwd)?;
+    debug!("执行程序: {}", command);
+    let (program, rest) = args
+        .split_first()
+        .ok_or_else(|| TaiError::Other("没有要执行的程序".to_string()))?;
+    let mut child = Command::new(program)
+        .args(rest)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| TaiError::Other(format!("无法启动 {}: {}", program, e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input)?;
+    }
+    let result = child
+        .wait_with_output()
+        .map_err(|e| TaiError::Other(format!("等待命令结束失败: {}", e)))?;
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    print!("{}", stdout);
+    eprint!("{}", stderr);
+    let output = format!("{}{}", stdout, stderr);
+    let code = exit_code(&result.status);
+    AuditEntry::command(&command, decision)
+        .with_exit_code(code)
+        .with_output(output.as_bytes())
+        .record();
+    Ok(Some((code, output)))
+}
+
+/// 代为写入文件：检查执行策略、创建检查点并写入审计日志，dry-run 模式下不写入、返回 false
+///
+/// 违反策略时返回 PolicyViolation，写入失败时返回 FileError，两者都会记录到审计日志
+pub fn write_file(path: &str, content: &str, decision: AuditDecision) -> TaiResult<bool> {
+    let policy = Policy::load()?;
+    let cwd = std::env::current_dir().unwrap_or_else(|_| policy.root.clone());
+    if let Err(e) = policy.check_write(Path::new(path), &cwd) {
+        AuditEntry::write(path, AuditDecision::Denied)
+            .with_reason(&e.to_string())
+            .record();
+        return Err(e);
+    }
+    if policy.is_dry_run() {
+        info!("dry-run 模式，跳过写入: {}", path);
+        AuditEntry::write(path, AuditDecision::DryRun)
+            .with_output(content.as_bytes())
+            .record();
+        println!("  [dry-run] 未写入: {}", path);
+        return Ok(false);
+    }
+    checkpoint::snapshot(&[cwd.join(path)])?;
+
+    let written = Path::new(path)
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(path, content));
+    let entry = AuditEntry::write(path, decision).with_output(content.as_bytes());
+    match written {
+        Ok(()) => {
+            entry.record();
+            Ok(true)
+        }
+        Err(e) => {
+            entry.with_reason(&format!("写入失败: {}", e)).record();
+            Err(TaiError::FileError(format!("无法写入 {}: {}", path, e)))
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn spawn_and_capture(shell: &str, command: &str) -> TaiResult<(i32, String)> {
+    let process = tai_pty::PtyCommand::new(shell)
+        .args(shell_args(shell))
+        .arg(command)
+        .spawn()
+        .map_err(|e| {
+            error!("无法启动 shell {}: {}", shell, e);
+            TaiError::Other(format!("无法启动 shell {}: {}", shell, e))
+        })?;
+    let result = process
+        .attach(None)
+        .await
+        .map_err(|e| TaiError::Other(format!("命令执行失败: {}", e)))?;
+    Ok((exit_code(&result.status), result.output.stripped_text()))
+}
+
+#[cfg(not(unix))]
+async fn spawn_and_capture(shell: &str, command: &str) -> TaiResult<(i32, String)> {
+    capture_with_pipes(shell, command)
+}
+
+/// 通过管道捕获 stdout 和 stderr，两个流按行交错写入同一缓冲区并实时输出
+#[cfg(not(unix))]
+fn capture_with_pipes(shell: &str, command: &str) -> TaiResult<(i32, String)> {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        process::Stdio,
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    fn tee_lines(stream: impl Read, captured: &Mutex<String>, to_stderr: bool) {
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line).unwrap_or(0) > 0 {
+            let text = String::from_utf8_lossy(&line);
+            if to_stderr {
+                eprint!("{}", text);
+            } else {
+                print!("{}", text);
+                let _ = std::io::stdout().flush();
+            }
+            if let Ok(mut captured) = captured.lock() {
+                captured.push_str(&text);
+            }
+            line.clear();
+        }
+    }
+
+    let mut child = shell_command(shell, command)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            error!("无法启动 shell {}: {}", shell, e);
+            TaiError::Other(format!("无法启动 shell {}: {}", shell, e))
+        })?;
+
+    let captured = Arc::new(Mutex::new(String::new()));
+    let stderr_reader = child.stderr.take().map(|stderr| {
+        let captured = Arc::clone(&captured);
+        thread::spawn(move || tee_lines(stderr, &captured, true))
+    });
+    if let Some(stdout) = child.stdout.take() {
+        tee_lines(stdout, &captured, false);
+    }
+    if let Some(reader) = stderr_reader {
+        let _ = reader.join();
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| TaiError::Other(format!("等待命令结束失败: {}", e)))?;
+    let output = captured.lock().map(|s| s.clone()).unwrap_or_default();
+    Ok((exit_code(&status), output))
+}
+
+/// 让 shell 执行一条命令字符串所需的参数
+fn shell_args(shell: &str) -> &'static [&'static str] {
+    let name = std::path::Path::new(shell)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(shell)
+        .to_ascii_lowercase();
+    match name.as_str() {
+        "cmd" => &["/C"],
+        "pwsh" | "powershell" => &["-NoProfile", "-Command"],
+        _ => &["-c"],
+    }
+}
+
+#[cfg(not(unix))]
+fn shell_command(shell: &str, command: &str) -> std::process::Command {
+    let mut cmd = std::process::Command::new(shell);
+    cmd.args(shell_args(shell)).arg(command);
+    cmd
+}
+
+/// 被信号终止时没有退出码，按 shell 惯例记为 128 + 信号
+fn exit_code(status: &ExitStatus) -> i32 {
+    let code = status.code().unwrap_or_else(|| signal_code(status));
+    debug!("命令退出码: {}", code);
+    code
+}
+
+#[cfg(unix)]
+fn signal_code(status: &ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+    128 + status.signal().unwrap_or(0)
+}
+
+#[cfg(not(unix))]
+fn signal_code(_status: &ExitStatus) -> i32 {
+    1
+}
@@ -0,0 +1,307 @@
+//! `tai ask --tools` 提供给模型的只读工具，只能访问项目目录内的文件
+
+use regex::RegexBuilder;
+use serde_json::{json, Value};
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+};
+use tai_ai::ChatTool;
+use tai_core::{find_project_tai_dir, TaiError, TaiResult};
+
+/// read_file 单次返回的上限（字节）
+const MAX_READ_BYTES: usize = 64 * 1024;
+/// list_dir 最多列出的条目数
+const MAX_ENTRIES: usize = 500;
+/// grep 最多返回的匹配行数
+const MAX_MATCHES: usize = 200;
+/// grep 跳过超过该大小的文件（字节）
+const MAX_GREP_FILE_SIZE: u64 = 1024 * 1024;
+/// grep 不进入的目录
+const SKIP_DIRS: &[&str] = &[".git", "target", "node_modules", "dist", "build"];
+
+/// 工具可以访问的范围：相对路径按 cwd 解析，且必须位于项目根目录内
+struct Workspace {
+    cwd: PathBuf,
+    root: PathBuf,
+}
+
+impl Workspace {
+    fn current() -> TaiResult<Self> {
+        let cwd = std::env::current_dir()?.canonicalize()?;
+        let root = find_project_tai_dir(&cwd)
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(&cwd)
+            .to_path_buf();
+        Ok(Self { cwd, root })
+    }
+
+    fn resolve(&self, path: &str) -> TaiResult<PathBuf> {
+        let full = self
+            .cwd
+            .join(path)
+            .canonicalize()
+            .map_err(|e| TaiError::FileError(format!("{}: {}", path, e)))?;
+        if !full.starts_with(&self.root) {
+            return Err(TaiError::Other(format!(
+                "{} 不在项目目录 {} 内，不允许访问",
+                path,
+                self.root.display()
+            )));
+        }
+        Ok(full)
+    }
+
+    /// 相对 cwd 的显示路径
+    fn display(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.cwd).unwrap_or(path);
+        match relative.to_string_lossy() {
+            text if text.is_empty() => ".".to_string(),
+            text => text.into_owned(),
+        }
+    }
+}
+
+/// 内置的只读工具：read_file、list_dir、grep、git_status
+pub fn builtin_tools() -> TaiResult<Vec<ChatTool>> {
+    let workspace = Arc::new(Workspace::current()?);
+    let tool = |name: &str,
+                description: &str,
+                parameters: Value,
+                run: fn(&Workspace, &Value) -> TaiResult<String>| {
+        let workspace = Arc::clone(&workspace);
+        ChatTool::new(name, description, parameters, move |args| {
+            let workspace = Arc::clone(&workspace);
+            async move { run(&workspace, &args) }
+        })
+    };
+
+    Ok(vec![
+        tool(
+            "read_file",
+            "读取项目内的文本文件，返回带行号的内容；大文件请用 start_line / end_line 分段读取",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": {"type": "string", "description": "相对当前目录的文件路径"},
+                    "start_line": {"type": "integer", "description": "起始行号（从 1 开始，含）"},
+                    "end_line": {"type": "integer", "description": "结束行号（含）"}
+                },
+                "required": ["path"]
+            }),
+            read_file,
+        ),
+        tool(
+            "list_dir",
+            "列出项目内目录的条目，目录以 / 结尾",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": {"type": "string", "description": "相对当前目录的路径，默认为当前目录"}
+                }
+            }),
+            list_dir,
+        ),
+        tool(
+            "grep",
+            "在项目文件中按正则表达式搜索，返回 `路径:行号: 内容`；跳过 .git、target 等目录和二进制文件",
+            json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {"type": "string", "description": "正则表达式"},
+                    "path": {"type": "string", "description": "搜索的文件或目录，默认为当前目录"},
+                    "ignore_case": {"type": "boolean", "description": "是否忽略大小写"}
+                },
+                "required": ["pattern"]
+            }),
+            grep,
+        ),
+        tool(
+            "git_status",
+            "查看 git 仓库的当前分支和工作区改动（git status --short --branch）",
+            json!({"type": "object", "properties": {}}),
+            git_status,
+        ),
+    ])
+}
+
+fn string_arg<'a>(args: &'a Value, name: &str) -> TaiResult<&'a str> {
+    args[name]
+        .as_str()
+        .ok_or_else(|| TaiError::Other(format!("缺少参数 {}", name)))
+}
+
+fn read_file(workspace: &Workspace, args: &Value) -> TaiResult<String> {
+    let path = workspace.resolve(string_arg(args, "path")?)?;
+    let bytes = fs::read(&path)?;
+    if bytes.contains(&0) {
+        return Err(TaiError::Other(format!(
+            "{} 是二进制文件",
+            workspace.display(&path)
+        )));
+    }
+    let content = String::from_utf8_lossy(&bytes);
+    let total = content.lines().count();
+    let start = args["start_line"].as_u64().unwrap_or(1).max(1) as usize;
+    let end = args["end_line"]
+        .as_u64()
+        .map_or(total, |n| n as usize)
+        .min(total);
+
+    let mut output = String::new();
+    for (i, line) in content.lines().enumerate().take(end).skip(start - 1) {
+        if output.len() + line.len() > MAX_READ_BYTES {
+            let _ = write!(
+                output,
+                "...（内容过长，已截断；共 {} 行，请用 start_line={} 继续读取）",
+                total,
+                i + 1
+            );
+            break;
+        }
+        let _ = writeln!(output, "{:>5}  {}", i + 1, line);
+    }
+    if output.is_empty() {
+        output = format!("（没有内容，文件共 {} 行）", total);
+    }
+    Ok(output)
+}
+
+fn list_dir(workspace: &Workspace, args: &Value) -> TaiResult<String> {
+    let path = workspace.resolve(args["path"].as_str().unwrap_or("."))?;
+    let mut entries: Vec<String> = fs::read_dir(&path)?
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                name + "/"
+            } else {
+                name
+            }
+        })
+        .collect();
+    entries.sort();
+    let total = entries.len();
+    entries.truncate(MAX_ENTRIES);
+    if total > MAX_ENTRIES {
+        entries.push(format!(
+            "...（共 {} 项，只列出前 {} 项）",
+            total, MAX_ENTRIES
+        ));
+    }
+    if entries.is_empty() {
+        return Ok("（空目录）".to_string());
+    }
+    Ok(entries.join("\n"))
+}
+
+fn grep(workspace: &Workspace, args: &Value) -> TaiResult<String> {
+    let pattern = string_arg(args, "pattern")?;
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(args["ignore_case"].as_bool().unwrap_or(false))
+        .build()
+        .map_err(|e| TaiError::Other(format!("无效的正则表达式: {}", e)))?;
+    let start = workspace.resolve(args["path"].as_str().unwrap_or("."))?;
+
+    let mut matches = Vec::new();
+    let mut pending = vec![start];
+    while let Some(path) = pending.pop() {
+        if path.is_dir() {
+            let Ok(entries) = fs::read_dir(&path) else {
+                continue;
+            };
+            let mut children: Vec<PathBuf> = entries
+                .filter_map(Result::ok)
+                .filter(|entry| {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    // 不跟随符号链接，避免读到项目目录之外
+                    let symlink = entry.file_type().is_ok_and(|t| t.is_symlink());
+                    !(symlink || name.starts_with('.') || SKIP_DIRS.contains(&name.as_ref()))
+                })
+                .map(|entry| entry.path())
+                .collect();
+            // 逆序入栈，按文件名顺序搜索
+            children.sort_by(|a, b| b.cmp(a));
+            pending.extend(children);
+            continue;
+        }
+        if fs::metadata(&path).map_or(true, |m| m.len() > MAX_GREP_FILE_SIZE) {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        for (i, line) in content.lines().enumerate() {
+            if regex.is_match(line) {
+                matches.push(format!(
+                    "{}:{}: {}",
+                    workspace.display(&path),
+                    i + 1,
+                    line.trim()
+                ));
+                if matches.len() >= MAX_MATCHES {
+                    matches.push(format!("...（匹配过多，只显示前 {} 条）", MAX_MATCHES));
+                    return Ok(matches.join("\n"));
+                }
+            }
+        }
+    }
+    if matches.is_empty() {
+        return Ok("没有匹配".to_string());
+    }
+    Ok(matches.join("\n"))
+}
+
+fn git_status(workspace: &Workspace, _args: &Value) -> TaiResult<String> {
+    let output = Command::new("git")
+        .args(["status", "--short", "--branch"])
+        .current_dir(&workspace.cwd)
+        .output()
+        .map_err(|e| TaiError::Other(format!("无法运行 git: {}", e)))?;
+    if !output.status.success() {
+        return Err(TaiError::Other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tools_stay_inside_root() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp.path().join("src")).unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::write(
+            root.join("src/main.rs"),
+            "fn main() {\n    println!(\"hi\");\n}\n",
+        )
+        .unwrap();
+        let workspace = Workspace {
+            cwd: root.clone(),
+            root: root.clone(),
+        };
+
+        let text = read_file(&workspace, &json!({"path": "src/main.rs", "start_line": 2})).unwrap();
+        assert_eq!(text, "    2      println!(\"hi\");\n    3  }\n");
+        assert_eq!(list_dir(&workspace, &json!({})).unwrap(), "src/");
+        assert_eq!(
+            grep(
+                &workspace,
+                &json!({"pattern": "PRINTLN", "ignore_case": true})
+            )
+            .unwrap(),
+            "src/main.rs:2: println!(\"hi\");"
+        );
+        assert!(read_file(&workspace, &json!({"path": "../"})).is_err());
+        assert!(read_file(&workspace, &json!({"path": "/etc/hostname"})).is_err());
+    }
+}
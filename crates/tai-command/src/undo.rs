@@ -0,0 +1,256 @@
+use clap::Args;
+use std::{fs, io::IsTerminal, path::PathBuf};
+use tai_core::{
+    audit, checkpoint, AuditDecision, AuditEntry, Checkpoint, SnapshotFile, TaiError, TaiResult,
+};
+use tai_tui::{select_action, ActionItem};
+use tracing::{debug, warn};
+
+use crate::diff::show_diff;
+
+/// list 中请求内容的最大显示宽度（字符）
+const MAX_PROMPT_WIDTH: usize = 50;
+
+#[derive(Args, Debug)]
+pub struct UndoArgs {
+    /// 要恢复的检查点 id（默认为最近一个尚未恢复的检查点）
+    #[arg(long)]
+    pub session: Option<String>,
+
+    /// 列出所有检查点及其中文件的变化，不恢复
+    #[arg(long, conflicts_with = "yes")]
+    pub list: bool,
+
+    /// 不确认，直接恢复
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+/// 文件当前内容相对检查点的变化
+enum Change {
+    /// 与快照相同
+    Unchanged,
+    /// 内容被修改，恢复为快照内容
+    Modified { current: Vec<u8>, snapshot: Vec<u8> },
+    /// 文件被删除，恢复为快照内容
+    Deleted(Vec<u8>),
+    /// 修改前不存在的文件，恢复时删除
+    Created,
+}
+
+impl Change {
+    fn of(checkpoint: &Checkpoint, file: &SnapshotFile) -> TaiResult<Self> {
+        let current = match fs::symlink_metadata(&file.path) {
+            Ok(meta) if meta.is_file() => Some(fs::read(&file.path)?),
+            Ok(_) => Some(Vec::new()),
+            Err(_) => None,
+        };
+        Ok(match (checkpoint.snapshot_content(file)?, current) {
+            (Some(snapshot), Some(current)) if snapshot == current => Change::Unchanged,
+            (Some(snapshot), Some(current)) => Change::Modified { current, snapshot },
+            (Some(snapshot), None) => Change::Deleted(snapshot),
+            (None, Some(_)) => Change::Created,
+            (None, None) => Change::Unchanged,
+        })
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Change::Unchanged => "未变化",
+            Change::Modified { .. } => "已修改",
+            Change::Deleted(_) => "已删除",
+            Change::Created => "新建",
+        }
+    }
+}
+
+impl UndoArgs {
+    pub async fn handle(self) -> TaiResult<()> {
+        if self.list {
+            return list_checkpoints();
+        }
+
+        let mut checkpoint = match &self.session {
+            Some(id) => checkpoint::load(id)?,
+            None => checkpoint::list()?
+                .into_iter()
+                .find(|c| c.restored.is_none())
+                .ok_or_else(|| TaiError::Other("没有可恢复的检查点".to_string()))?,
+        };
+        debug!("恢复检查点 {}", checkpoint.id);
+        // 仓库快照展开为快照之后有变化的文件
+        checkpoint.expand_repos()?;
+        print_header(&checkpoint);
+        if let Some(time) = &checkpoint.restored {
+            println!("  注意：该检查点已于 {} 恢复过", short_time(time));
+        }
+
+        let mut changed: Vec<PathBuf> = Vec::new();
+        for file in &checkpoint.files {
+            let change = Change::of(&checkpoint, file)?;
+            if preview(file, &change)? {
+                changed.push(file.path.clone());
+            }
+        }
+        if !checkpoint.skipped.is_empty() {
+            println!();
+            println!("  以下路径或命令的改动没有快照，无法恢复:");
+            for path in &checkpoint.skipped {
+                println!("    {}", path);
+            }
+        }
+        if changed.is_empty() {
+            println!();
+            println!("  文件已是检查点时的状态，无需恢复");
+            return Ok(());
+        }
+
+        let decision = if self.yes {
+            AuditDecision::Auto
+        } else {
+            if !std::io::stdin().is_terminal() {
+                return Err(TaiError::Other(
+                    "恢复前需要确认，请在终端中运行或使用 --yes".to_string(),
+                ));
+            }
+            let items = [
+                ActionItem::new('r', format!("恢复 {} 个文件", changed.len())),
+                ActionItem::new('q', "取消"),
+            ];
+            let choice =
+                select_action(&items).map_err(|e| TaiError::Other(format!("TUI 错误: {}", e)))?;
+            if choice != Some(0) {
+                println!("已取消");
+                return Ok(());
+            }
+            AuditDecision::Approved
+        };
+
+        // 恢复本身也创建检查点，再次 tai undo 即可撤销这次恢复
+        audit::set_context("undo", Some(&checkpoint.id), None);
+        checkpoint::snapshot(&changed)?;
+        let failures = checkpoint.restore()?;
+        for path in &changed {
+            let path = path.display().to_string();
+            let entry = AuditEntry::write(&path, decision);
+            match failures
+                .iter()
+                .find(|(p, _)| p.display().to_string() == path)
+            {
+                Some((_, reason)) => entry.with_reason(reason).record(),
+                None => entry.record(),
+            }
+        }
+
+        for (path, reason) in &failures {
+            warn!("恢复 {} 失败: {}", path.display(), reason);
+            println!("  ✗ {}: {}", path.display(), reason);
+        }
+        println!(
+            "✓ 已恢复检查点 {}（{} 个文件）",
+            checkpoint.id,
+            changed.len() - failures.len()
+        );
+        Ok(())
+    }
+}
+
+fn print_header(checkpoint: &Checkpoint) {
+    println!(
+        "检查点 {}  {}  tai {}",
+        checkpoint.id,
+        short_time(&checkpoint.created),
+        checkpoint.source
+    );
+    if let Some(prompt) = &checkpoint.prompt {
+        println!("  请求: {}", prompt);
+    }
+}
+
+/// 打印文件恢复后的变化，返回是否需要恢复
+fn preview(file: &SnapshotFile, change: &Change) -> TaiResult<bool> {
+    let path = file.path.display();
+    println!();
+    match change {
+        Change::Unchanged => {
+            println!("  {}: 未变化", path);
+            return Ok(false);
+        }
+        Change::Created => println!("  {}: tai 新建的文件，恢复时删除", path),
+        Change::Modified { current, snapshot } => {
+            show_content_diff(&format!("{}（恢复后）", path), current, snapshot)?
+        }
+        Change::Deleted(snapshot) => {
+            show_content_diff(&format!("{}（恢复已删除的文件）", path), &[], snapshot)?
+        }
+    }
+    Ok(true)
+}
+
+fn show_content_diff(title: &str, current: &[u8], snapshot: &[u8]) -> TaiResult<()> {
+    match (text_of(current), text_of(snapshot)) {
+        (Some(current), Some(snapshot)) => {
+            show_diff(title, current, snapshot)?;
+        }
+        _ => println!("  {}: 二进制文件", title),
+    }
+    Ok(())
+}
+
+fn text_of(bytes: &[u8]) -> Option<&str> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    std::str::from_utf8(bytes).ok()
+}
+
+fn list_checkpoints() -> TaiResult<()> {
+    let checkpoints = checkpoint::list()?;
+    if checkpoints.is_empty() {
+        println!("暂无检查点（{}）", checkpoint::checkpoints_dir().display());
+        return Ok(());
+    }
+    for checkpoint in &checkpoints {
+        let status = match &checkpoint.restored {
+            Some(time) => format!("已于 {} 恢复", short_time(time)),
+            None => "可恢复".to_string(),
+        };
+        let prompt = checkpoint
+            .prompt
+            .as_deref()
+            .unwrap_or("")
+            .replace('\n', " ");
+        let prompt = if prompt.chars().count() > MAX_PROMPT_WIDTH {
+            let head: String = prompt.chars().take(MAX_PROMPT_WIDTH - 3).collect();
+            head + "..."
+        } else {
+            prompt
+        };
+        println!(
+            "{}  {}  tai {:<5} [{}]  {}",
+            checkpoint.id,
+            short_time(&checkpoint.created),
+            checkpoint.source,
+            status,
+            prompt
+        );
+        for file in &checkpoint.files {
+            let change = Change::of(checkpoint, file)?;
+            println!("    {:<6} {}", change.label(), file.path.display());
+        }
+        for repo in &checkpoint.repos {
+            println!("    {:<6} {}", "仓库", repo.root.display());
+        }
+        for path in &checkpoint.skipped {
+            println!("    {:<6} {}", "无快照", path);
+        }
+    }
+    println!();
+    println!("使用 tai undo --session <id> 查看差异并恢复");
+    Ok(())
+}
+
+/// 2024-05-01T12:30:45.123+08:00 -> 2024-05-01 12:30:45
+fn short_time(time: &str) -> String {
+    time.get(..19).unwrap_or(time).replace('T', " ")
+}
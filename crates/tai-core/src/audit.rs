@@ -0,0 +1,265 @@
+//! 审计日志：tai 代为执行的每条命令、写入的每个文件都追加一行 JSON 到 `~/.tai/audit/`
+//!
+//! 日志按天分文件（`audit-YYYY-MM-DD.jsonl`），只追加不改写，最多保留 MAX_AUDIT_FILES 个文件
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::{OnceLock, RwLock},
+};
+use tracing::{debug, warn};
+
+use crate::config::user_tai_dir;
+use crate::logging::cleanup_old_logs;
+use crate::TaiResult;
+
+/// 最多保留的审计日志文件数（按天）
+pub const MAX_AUDIT_FILES: usize = 90;
+
+/// 审计日志目录 ~/.tai/audit
+pub fn audit_dir() -> PathBuf {
+    user_tai_dir().join("audit")
+}
+
+/// 操作是如何被批准（或拒绝）的
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditDecision {
+    /// 用户在操作栏中确认
+    Approved,
+    /// 用户修改后确认
+    Edited,
+    /// 命中本次会话中的放行规则
+    Session,
+    /// 无需交互确认（如 `tai go --run`）
+    Auto,
+    /// 被执行策略拒绝，未执行
+    Denied,
+    /// dry-run 模式，只打印未执行
+    DryRun,
+}
+
+impl AuditDecision {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditDecision::Approved => "已确认",
+            AuditDecision::Edited => "修改后确认",
+            AuditDecision::Session => "会话内放行",
+            AuditDecision::Auto => "自动执行",
+            AuditDecision::Denied => "策略拒绝",
+            AuditDecision::DryRun => "dry-run",
+        }
+    }
+}
+
+/// 发起操作的上下文：由哪个子命令、基于什么请求、使用哪个模型
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    pub source: String,
+    pub prompt: Option<String>,
+    pub model: Option<String>,
+}
+
+static CONTEXT: OnceLock<RwLock<AuditContext>> = OnceLock::new();
+
+fn context() -> &'static RwLock<AuditContext> {
+    CONTEXT.get_or_init(|| RwLock::new(AuditContext::default()))
+}
+
+/// 设置之后审计记录使用的上下文，子命令在拿到请求和模型后调用
+pub fn set_context(source: &str, prompt: Option<&str>, model: Option<&str>) {
+    let mut current = context()
+        .write()
+        .expect("AUDIT CONTEXT write lock poisoned");
+    *current = AuditContext {
+        source: source.to_string(),
+        prompt: prompt.map(str::to_string),
+        model: model.map(str::to_string),
+    };
+}
+
+/// 当前的审计上下文，检查点等其他记录复用
+pub(crate) fn current_context() -> AuditContext {
+    context()
+        .read()
+        .expect("AUDIT CONTEXT read lock poisoned")
+        .clone()
+}
+
+/// 审计日志中的一条记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// RFC 3339 本地时间
+    pub timestamp: String,
+    /// 发起操作的子命令，如 go、fix、do
+    pub source: String,
+    pub prompt: Option<String>,
+    /// provider/model
+    pub model: Option<String>,
+    /// command：执行命令；write：直接写入文件
+    pub action: String,
+    pub command: Option<String>,
+    pub decision: AuditDecision,
+    /// 拒绝原因等补充说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub exit_code: Option<i32>,
+    /// 命令输出或写入内容的 SHA-256
+    pub output_sha256: Option<String>,
+    /// 命令会写入或删除的文件（静态分析所得）、直接写入的文件
+    pub files: Vec<String>,
+    pub cwd: String,
+}
+
+impl AuditEntry {
+    fn new(action: &str, decision: AuditDecision) -> Self {
+        let context = current_context();
+        Self {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            source: context.source,
+            prompt: context.prompt,
+            model: context.model,
+            action: action.to_string(),
+            command: None,
+            decision,
+            reason: None,
+            exit_code: None,
+            output_sha256: None,
+            files: Vec::new(),
+            cwd: std::env::current_dir()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 执行命令的记录，files 取命令行静态分析出的写入目标
+    pub fn command(command: &str, decision: AuditDecision) -> Self {
+        let mut entry = Self::new("command", decision);
+        let cwd = PathBuf::from(&entry.cwd);
+        entry.files = crate::risk::plan(command, &cwd)
+            .writes
+            .into_iter()
+            .map(|target| match target.path {
+                Some(path) => path.display().to_string(),
+                None => target.raw,
+            })
+            .collect();
+        entry.command = Some(command.to_string());
+        entry
+    }
+
+    /// 直接写入文件的记录
+    pub fn write(path: &str, decision: AuditDecision) -> Self {
+        let mut entry = Self::new("write", decision);
+        entry.files = vec![path.to_string()];
+        entry
+    }
+
+    pub fn with_exit_code(mut self, code: i32) -> Self {
+        self.exit_code = Some(code);
+        self
+    }
+
+    pub fn with_output(mut self, output: &[u8]) -> Self {
+        self.output_sha256 = Some(format!("{:x}", Sha256::digest(output)));
+        self
+    }
+
+    pub fn with_reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+
+    /// 追加到当天的审计日志；写入失败只记录警告，不影响操作本身
+    pub fn record(&self) {
+        if let Err(e) = append(self) {
+            warn!("写入审计日志失败: {}", e);
+        }
+    }
+}
+
+fn append(entry: &AuditEntry) -> TaiResult<()> {
+    let dir = audit_dir();
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!(
+        "audit-{}.jsonl",
+        chrono::Local::now().format("%Y-%m-%d")
+    ));
+    let is_new = !path.exists();
+
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    debug!("已写入审计日志 {:?}", path);
+
+    if is_new {
+        cleanup_old_logs(&dir, "jsonl", MAX_AUDIT_FILES);
+    }
+    Ok(())
+}
+
+/// 读取全部审计记录，最新的在前；无法解析的行跳过
+pub fn load_entries() -> TaiResult<Vec<AuditEntry>> {
+    let dir = audit_dir();
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect();
+    // 文件名含日期，按名称倒序即从新到旧
+    files.sort_by(|a, b| b.cmp(a));
+
+    let mut entries = Vec::new();
+    for path in files {
+        let content = fs::read_to_string(&path)?;
+        let mut day: Vec<AuditEntry> = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(i, line)| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("审计日志 {:?} 第 {} 行无法解析: {}", path, i + 1, e);
+                    None
+                }
+            })
+            .collect();
+        day.reverse();
+        entries.extend(day);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_round_trip() {
+        set_context("go", Some("列出大文件"), Some("openai/gpt-4o"));
+        let entry = AuditEntry::command("du -sh * > sizes.txt", AuditDecision::Approved)
+            .with_exit_code(0)
+            .with_output(b"4.0K\tREADME.md\n");
+        assert_eq!(entry.source, "go");
+        assert_eq!(entry.model.as_deref(), Some("openai/gpt-4o"));
+        assert!(entry.files[0].ends_with("sizes.txt"));
+
+        let line = serde_json::to_string(&entry).unwrap();
+        assert!(line.contains(r#""decision":"approved""#));
+        let parsed: AuditEntry = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.output_sha256, entry.output_sha256);
+        assert_eq!(parsed.exit_code, Some(0));
+    }
+}
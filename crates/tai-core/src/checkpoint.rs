@@ -0,0 +1,691 @@
+//! 检查点：tai 修改文件前把受影响的文件快照到 `~/.tai/checkpoints/<会话>/`，`tai undo` 据此恢复
+//!
+//! 一次 tai 运行对应一个会话；同一文件在会话中只快照第一次，恢复后回到会话开始前的状态
+//!
+//! 命令的写入目标只能静态分析出一部分，因此在 git 仓库中执行命令前还会把整个工作区
+//! （已跟踪和未忽略的文件）记录为 `refs/tai/checkpoints/<id>`，恢复时据此还原；
+//! 不在 git 仓库中时把命令记入 skipped，`tai undo` 会提示它的改动无法恢复
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Mutex, OnceLock},
+};
+use tracing::{debug, warn};
+
+use crate::audit::current_context;
+use crate::config::user_tai_dir;
+use crate::policy::real_path;
+use crate::{TaiError, TaiResult};
+
+/// 检查点目录中的清单文件
+const MANIFEST: &str = "checkpoint.json";
+/// 快照内容存放的子目录
+const FILES_DIR: &str = "files";
+/// 最多保留的检查点（会话）数
+pub const MAX_CHECKPOINTS: usize = 50;
+/// 单个路径（文件或目录）快照的文件数上限
+const MAX_SNAPSHOT_FILES: usize = 1000;
+/// 单个路径（文件或目录）快照的总大小上限（字节）
+const MAX_SNAPSHOT_BYTES: u64 = 64 * 1024 * 1024;
+/// 仓库快照的 git 引用前缀
+const REF_PREFIX: &str = "refs/tai/checkpoints/";
+
+/// 检查点根目录 ~/.tai/checkpoints
+pub fn checkpoints_dir() -> PathBuf {
+    user_tai_dir().join("checkpoints")
+}
+
+/// 一个被快照的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    pub path: PathBuf,
+    /// 快照内容在检查点 files/ 中的文件名；None 表示修改前文件不存在，恢复时删除
+    pub blob: Option<String>,
+    /// Unix 权限位
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+}
+
+/// 命令执行前对整个 git 工作区的快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoSnapshot {
+    /// 仓库根目录
+    pub root: PathBuf,
+    /// 记录工作区内容的提交，保存在 `refs/tai/checkpoints/<id>`
+    pub commit: String,
+    /// 快照时 files 中已有的条目数；这些文件的快照更早，恢复时优先
+    pub files_before: usize,
+}
+
+/// 一次会话的检查点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// 创建时间 `YYYYMMDD_HHMMSS`，同时是目录名
+    pub id: String,
+    /// RFC 3339 本地时间
+    pub created: String,
+    /// 发起修改的子命令，如 do、go
+    pub source: String,
+    pub prompt: Option<String>,
+    pub files: Vec<SnapshotFile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repos: Vec<RepoSnapshot>,
+    /// 无法快照的路径（目录过大、符号链接、通配符等）和不在 git 仓库中执行的命令，恢复时不会还原
+    #[serde(default)]
+    pub skipped: Vec<String>,
+    /// 被 tai undo 恢复的时间
+    #[serde(default)]
+    pub restored: Option<String>,
+    #[serde(skip)]
+    dir: PathBuf,
+    /// repos 是否已展开到 files
+    #[serde(skip)]
+    expanded: bool,
+}
+
+static SESSION: OnceLock<Mutex<Option<Checkpoint>>> = OnceLock::new();
+
+/// 在修改之前快照 paths 到本次会话的检查点，首次调用时创建检查点
+pub fn snapshot(paths: &[PathBuf]) -> TaiResult<()> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    with_session(|checkpoint| {
+        let mut changed = false;
+        for path in paths {
+            changed |= checkpoint.add(path)?;
+        }
+        Ok(changed)
+    })
+}
+
+/// 在执行命令之前创建检查点：快照静态分析出的写入目标；cwd 在 git 仓库中时再快照整个工作区，
+/// 否则把命令记入 skipped
+pub fn snapshot_command(command: &str, cwd: &Path) -> TaiResult<()> {
+    let paths: Vec<PathBuf> = crate::risk::plan(command, cwd)
+        .writes
+        .into_iter()
+        .filter_map(|target| target.path)
+        .collect();
+    with_session(|checkpoint| {
+        let mut changed = false;
+        for path in &paths {
+            changed |= checkpoint.add(path)?;
+        }
+        let repo = repo_root(cwd).map(|root| {
+            checkpoint.add_repo(&root).inspect_err(|e| {
+                warn!("无法为仓库 {} 创建快照: {}", root.display(), e);
+            })
+        });
+        match repo {
+            Some(Ok(added)) => Ok(changed || added),
+            failed => {
+                let reason = match failed {
+                    Some(_) => "无法创建仓库快照",
+                    None => "不在 git 仓库中",
+                };
+                checkpoint.skip(&format!("命令 `{}`", command), reason);
+                Ok(true)
+            }
+        }
+    })
+}
+
+/// 取得本次会话的检查点（首次调用时创建），f 返回清单是否有变化，有变化时保存
+fn with_session(f: impl FnOnce(&mut Checkpoint) -> TaiResult<bool>) -> TaiResult<()> {
+    let mut session = SESSION
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .expect("CHECKPOINT SESSION lock poisoned");
+    let checkpoint = match session.as_mut() {
+        Some(checkpoint) => checkpoint,
+        None => session.insert(Checkpoint::create_in(&checkpoints_dir())?),
+    };
+    if f(checkpoint)? {
+        checkpoint.save()?;
+    }
+    Ok(())
+}
+
+/// 所有检查点，最新的在前；无法读取的跳过
+pub fn list() -> TaiResult<Vec<Checkpoint>> {
+    list_in(&checkpoints_dir())
+}
+
+/// 按 id 读取检查点
+pub fn load(id: &str) -> TaiResult<Checkpoint> {
+    let dir = checkpoints_dir().join(id);
+    if id.contains(['/', '\\']) || !dir.join(MANIFEST).is_file() {
+        return Err(TaiError::Other(format!("检查点 {} 不存在", id)));
+    }
+    Checkpoint::load_from(&dir)
+}
+
+fn list_in(base: &Path) -> TaiResult<Vec<Checkpoint>> {
+    if !base.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut dirs: Vec<PathBuf> = fs::read_dir(base)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.join(MANIFEST).is_file())
+        .collect();
+    // 目录名是创建时间，按名称倒序即从新到旧
+    dirs.sort_by(|a, b| b.cmp(a));
+    Ok(dirs
+        .iter()
+        .filter_map(|dir| match Checkpoint::load_from(dir) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                warn!("无法读取检查点 {:?}: {}", dir, e);
+                None
+            }
+        })
+        .collect())
+}
+
+impl Checkpoint {
+    fn create_in(base: &Path) -> TaiResult<Self> {
+        fs::create_dir_all(base)?;
+        let stamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+        // 同一秒内的多次运行加序号区分
+        let mut id = stamp.clone();
+        let mut n = 1;
+        while base.join(&id).exists() {
+            n += 1;
+            id = format!("{}_{}", stamp, n);
+        }
+        let dir = base.join(&id);
+        fs::create_dir_all(dir.join(FILES_DIR))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+        }
+        debug!("创建检查点 {:?}", dir);
+        cleanup_old_checkpoints(base, MAX_CHECKPOINTS);
+
+        let context = current_context();
+        Ok(Self {
+            id,
+            created: chrono::Local::now().to_rfc3339(),
+            source: context.source,
+            prompt: context.prompt,
+            files: Vec::new(),
+            repos: Vec::new(),
+            skipped: Vec::new(),
+            restored: None,
+            dir,
+            expanded: false,
+        })
+    }
+
+    fn load_from(dir: &Path) -> TaiResult<Self> {
+        let content = fs::read_to_string(dir.join(MANIFEST))?;
+        let mut checkpoint: Checkpoint = serde_json::from_str(&content)?;
+        checkpoint.dir = dir.to_path_buf();
+        Ok(checkpoint)
+    }
+
+    fn save(&self) -> TaiResult<()> {
+        fs::write(self.dir.join(MANIFEST), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        self.files.iter().any(|f| f.path == path)
+    }
+
+    /// 快照一个路径，返回清单是否有变化
+    fn add(&mut self, path: &Path) -> TaiResult<bool> {
+        if self.contains(path) || self.skipped.iter().any(|p| Path::new(p) == path) {
+            return Ok(false);
+        }
+        if path.to_string_lossy().contains(['*', '?', '[']) {
+            self.skip(&path.display().to_string(), "包含通配符");
+            return Ok(true);
+        }
+        let meta = match fs::symlink_metadata(path) {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                self.files.push(SnapshotFile {
+                    path: path.to_path_buf(),
+                    blob: None,
+                    mode: None,
+                });
+                return Ok(true);
+            }
+            Err(e) => return Err(TaiError::FileError(format!("{}: {}", path.display(), e))),
+        };
+        if meta.is_file() {
+            if meta.len() > MAX_SNAPSHOT_BYTES {
+                self.skip(&path.display().to_string(), "文件过大");
+            } else {
+                self.copy_in(path, &meta)?;
+            }
+            return Ok(true);
+        }
+        if !meta.is_dir() {
+            self.skip(&path.display().to_string(), "不是普通文件或目录");
+            return Ok(true);
+        }
+
+        let Some(files) = collect_files(path) else {
+            self.skip(&path.display().to_string(), "目录过大");
+            return Ok(true);
+        };
+        for (file, meta) in files {
+            if !self.contains(&file) {
+                self.copy_in(&file, &meta)?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn skip(&mut self, item: &str, reason: &str) {
+        if self.skipped.iter().any(|s| s == item) {
+            return;
+        }
+        warn!(
+            "无法为 {} 创建检查点（{}），tai undo 不能恢复它",
+            item, reason
+        );
+        self.skipped.push(item.to_string());
+    }
+
+    /// 把 root 仓库的整个工作区记录为 `refs/tai/checkpoints/<id>`，每个仓库在会话中只记录一次
+    fn add_repo(&mut self, root: &Path) -> TaiResult<bool> {
+        if self.repos.iter().any(|r| r.root == root) {
+            return Ok(false);
+        }
+        let tree = self.worktree_tree(root)?;
+        let message = format!("tai checkpoint {}", self.id);
+        let mut args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+        let head = git(root, &["rev-parse", "--verify", "-q", "HEAD"]).ok();
+        if let Some(head) = head.as_deref() {
+            args.extend(["-p", head]);
+        }
+        let commit = git(root, &args)?;
+        git(
+            root,
+            &["update-ref", &format!("{}{}", REF_PREFIX, self.id), &commit],
+        )?;
+        debug!("仓库 {:?} 的工作区快照 {}", root, commit);
+        self.repos.push(RepoSnapshot {
+            root: root.to_path_buf(),
+            commit,
+            files_before: self.files.len(),
+        });
+        Ok(true)
+    }
+
+    /// 把工作区当前内容（已跟踪和未忽略的文件）写成 tree；使用临时索引，不影响用户的暂存区
+    fn worktree_tree(&self, root: &Path) -> TaiResult<String> {
+        let index = self.dir.join("git-index");
+        let real_index = root.join(git(root, &["rev-parse", "--git-path", "index"])?);
+        // 从真实索引开始可以复用其中的文件状态缓存，避免重新计算所有文件的哈希
+        if fs::copy(&real_index, &index).is_err() {
+            let _ = fs::remove_file(&index);
+        }
+        let result = git_with_index(root, &index, &["add", "-A"])
+            .and_then(|_| git_with_index(root, &index, &["write-tree"]));
+        let _ = fs::remove_file(&index);
+        result
+    }
+
+    /// 把仓库快照展开为文件快照：快照之后有变化的文件按快照时的内容加入 files，
+    /// 之后没有变化的文件从 files 中去掉；恢复和预览前调用
+    pub fn expand_repos(&mut self) -> TaiResult<()> {
+        if self.expanded {
+            return Ok(());
+        }
+        for repo in self.repos.clone() {
+            if let Err(e) = self.expand_repo(&repo) {
+                warn!("无法读取仓库快照 {}: {}", repo.commit, e);
+                self.skip(&repo.root.display().to_string(), "仓库快照不可用");
+            }
+        }
+        self.expanded = true;
+        Ok(())
+    }
+
+    fn expand_repo(&mut self, repo: &RepoSnapshot) -> TaiResult<()> {
+        let root = &repo.root;
+        let current = self.worktree_tree(root)?;
+        let args = [
+            "diff-tree",
+            "-r",
+            "-z",
+            "--no-renames",
+            &repo.commit,
+            &current,
+        ];
+        let raw = String::from_utf8_lossy(&git_bytes(root, &args)?).into_owned();
+        // 原始格式：`:旧权限 新权限 旧对象 新对象 状态\0路径\0`
+        let mut changes: Vec<(PathBuf, Option<(String, u32)>)> = Vec::new();
+        let mut fields = raw.split('\0');
+        while let (Some(meta), Some(rel)) = (fields.next(), fields.next()) {
+            let meta: Vec<&str> = meta.trim_start_matches(':').split_whitespace().collect();
+            let [old_mode, _, old_oid, _, status] = meta[..] else {
+                continue;
+            };
+            let path = root.join(rel);
+            if status == "A" {
+                changes.push((path, None));
+            } else if matches!(old_mode, "100644" | "100755") {
+                let mode = u32::from_str_radix(old_mode, 8).unwrap_or(0o100644);
+                changes.push((path, Some((old_oid.to_string(), mode))));
+            } else {
+                self.skip(&path.display().to_string(), "符号链接或子模块");
+            }
+        }
+
+        // 仓库快照之后才加入的文件：有变化的以仓库快照为准，没有变化的无需恢复；
+        // 被忽略的文件不在仓库快照中，保留原有快照
+        let mut index = repo.files_before;
+        while index < self.files.len() {
+            let path = real_path(&self.files[index].path);
+            let rel = path
+                .strip_prefix(root)
+                .ok()
+                .map(|rel| rel.to_string_lossy().into_owned());
+            let unchanged = rel.is_some_and(|rel| {
+                !changes.iter().any(|(p, _)| *p == path)
+                    && git(root, &["cat-file", "-e", &format!("{}:{}", current, rel)]).is_ok()
+            });
+            if unchanged || changes.iter().any(|(p, _)| *p == path) {
+                self.files.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+
+        for (path, old) in changes {
+            // 仓库快照之前已快照过的文件以更早的快照为准
+            if self.files[..repo.files_before.min(self.files.len())]
+                .iter()
+                .any(|f| real_path(&f.path) == path)
+            {
+                continue;
+            }
+            let (blob, mode) = match old {
+                Some((oid, mode)) => {
+                    let blob = format!("git-{}", oid);
+                    let target = self.dir.join(FILES_DIR).join(&blob);
+                    if !target.exists() {
+                        let content = git_bytes(root, &["cat-file", "blob", &oid])?;
+                        fs::write(&target, content)?;
+                    }
+                    (Some(blob), Some(mode))
+                }
+                None => (None, None),
+            };
+            self.files.push(SnapshotFile { path, blob, mode });
+        }
+        Ok(())
+    }
+
+    fn copy_in(&mut self, path: &Path, meta: &fs::Metadata) -> TaiResult<()> {
+        let blob = format!("{:05}", self.files.len() + 1);
+        fs::copy(path, self.dir.join(FILES_DIR).join(&blob))
+            .map_err(|e| TaiError::FileError(format!("无法快照 {}: {}", path.display(), e)))?;
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            Some(meta.permissions().mode())
+        };
+        #[cfg(not(unix))]
+        let mode = {
+            let _ = meta;
+            None
+        };
+        self.files.push(SnapshotFile {
+            path: path.to_path_buf(),
+            blob: Some(blob),
+            mode,
+        });
+        Ok(())
+    }
+
+    /// 文件在快照时的内容；修改前不存在时为 None
+    pub fn snapshot_content(&self, file: &SnapshotFile) -> TaiResult<Option<Vec<u8>>> {
+        match &file.blob {
+            Some(blob) => Ok(Some(fs::read(self.dir.join(FILES_DIR).join(blob))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 把所有文件恢复到快照时的状态，修改前不存在的文件被删除；返回未能处理的路径和原因
+    pub fn restore(&mut self) -> TaiResult<Vec<(PathBuf, String)>> {
+        self.expand_repos()?;
+        let mut failures = Vec::new();
+        for file in &self.files {
+            let result = match self.snapshot_content(file)? {
+                Some(content) => restore_file(file, &content),
+                None => remove_created(&file.path),
+            };
+            if let Err(reason) = result {
+                failures.push((file.path.clone(), reason));
+            }
+        }
+        self.restored = Some(chrono::Local::now().to_rfc3339());
+        self.save()?;
+        Ok(failures)
+    }
+}
+
+fn restore_file(file: &SnapshotFile, content: &[u8]) -> Result<(), String> {
+    if let Some(dir) = file.path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(&file.path, content).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    if let Some(mode) = file.mode {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&file.path, fs::Permissions::from_mode(mode))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn remove_created(path: &Path) -> Result<(), String> {
+    match fs::symlink_metadata(path) {
+        Err(_) => Ok(()),
+        // 新建的目录里可能有用户自己的文件，不整体删除
+        Ok(meta) if meta.is_dir() => Err("修改前不存在的目录，未删除".to_string()),
+        Ok(_) => fs::remove_file(path).map_err(|e| e.to_string()),
+    }
+}
+
+/// cwd 所在 git 仓库的根目录；不在仓库中或没有安装 git 时返回 None
+fn repo_root(cwd: &Path) -> Option<PathBuf> {
+    let root = git(cwd, &["rev-parse", "--show-toplevel"]).ok()?;
+    Some(real_path(Path::new(&root)))
+}
+
+/// 在 dir 中运行 git，返回去掉首尾空白的标准输出
+fn git(dir: &Path, args: &[&str]) -> TaiResult<String> {
+    let output = git_bytes(dir, args)?;
+    Ok(String::from_utf8_lossy(&output).trim().to_string())
+}
+
+fn git_with_index(dir: &Path, index: &Path, args: &[&str]) -> TaiResult<String> {
+    run_git(dir, args, Some(index)).map(|out| String::from_utf8_lossy(&out).trim().to_string())
+}
+
+fn git_bytes(dir: &Path, args: &[&str]) -> TaiResult<Vec<u8>> {
+    run_git(dir, args, None)
+}
+
+fn run_git(dir: &Path, args: &[&str], index: Option<&Path>) -> TaiResult<Vec<u8>> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir).args(args);
+    if let Some(index) = index {
+        command.env("GIT_INDEX_FILE", index);
+    }
+    // 快照提交不需要用户配置的身份
+    for var in ["GIT_AUTHOR_NAME", "GIT_COMMITTER_NAME"] {
+        command.env(var, "tai");
+    }
+    for var in ["GIT_AUTHOR_EMAIL", "GIT_COMMITTER_EMAIL"] {
+        command.env(var, "tai@localhost");
+    }
+    let output = command
+        .output()
+        .map_err(|e| TaiError::Other(format!("无法运行 git: {}", e)))?;
+    if !output.status.success() {
+        return Err(TaiError::Other(format!(
+            "git {} 失败: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// 递归收集目录下的普通文件（不跟随符号链接）；超过数量或大小上限时返回 None
+fn collect_files(dir: &Path) -> Option<Vec<(PathBuf, fs::Metadata)>> {
+    let mut files = Vec::new();
+    let mut total = 0;
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                pending.push(entry.path());
+            } else if meta.is_file() {
+                total += meta.len();
+                files.push((entry.path(), meta));
+                if files.len() > MAX_SNAPSHOT_FILES || total > MAX_SNAPSHOT_BYTES {
+                    return None;
+                }
+            }
+        }
+    }
+    Some(files)
+}
+
+/// 只保留最新的 max 个检查点
+fn cleanup_old_checkpoints(base: &Path, max: usize) {
+    let Ok(entries) = fs::read_dir(base) else {
+        return;
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    dirs.sort_by(|a, b| b.cmp(a));
+    for dir in dirs.iter().skip(max) {
+        // 同时删除仓库快照的引用，让 git 可以回收这些对象
+        if let Ok(checkpoint) = Checkpoint::load_from(dir) {
+            for repo in &checkpoint.repos {
+                let reference = format!("{}{}", REF_PREFIX, checkpoint.id);
+                let _ = git(&repo.root, &["update-ref", "-d", &reference]);
+            }
+        }
+        if let Err(e) = fs::remove_dir_all(dir) {
+            warn!("清理检查点失败 {:?}: {}", dir, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let work = root.join("work");
+        fs::create_dir_all(work.join("src")).unwrap();
+        fs::write(work.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(work.join("notes.txt"), "keep me\n").unwrap();
+
+        let mut checkpoint = Checkpoint::create_in(&root.join("checkpoints")).unwrap();
+        assert!(checkpoint.add(&work.join("src")).unwrap());
+        assert!(checkpoint.add(&work.join("notes.txt")).unwrap());
+        assert!(checkpoint.add(&work.join("new.txt")).unwrap());
+        // 同一文件只快照第一次
+        assert!(!checkpoint.add(&work.join("notes.txt")).unwrap());
+        checkpoint.save().unwrap();
+
+        fs::write(work.join("src/main.rs"), "changed\n").unwrap();
+        fs::remove_file(work.join("notes.txt")).unwrap();
+        fs::write(work.join("new.txt"), "created\n").unwrap();
+
+        let mut loaded = list_in(&root.join("checkpoints")).unwrap().remove(0);
+        assert_eq!(loaded.files.len(), 3);
+        assert!(loaded.restore().unwrap().is_empty());
+        assert_eq!(
+            fs::read_to_string(work.join("src/main.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(work.join("notes.txt")).unwrap(),
+            "keep me\n"
+        );
+        assert!(!work.join("new.txt").exists());
+        assert!(list_in(&root.join("checkpoints")).unwrap()[0]
+            .restored
+            .is_some());
+    }
+
+    #[test]
+    fn test_repo_snapshot_and_restore() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let repo = root.join("repo");
+        fs::create_dir_all(&repo).unwrap();
+        let run = |args: &[&str]| git(&repo, args).unwrap();
+        run(&["init", "-q"]);
+        fs::write(repo.join("a.txt"), "one\n").unwrap();
+        fs::write(repo.join("b.txt"), "keep\n").unwrap();
+        fs::write(repo.join(".gitignore"), "ignored.txt\n").unwrap();
+        run(&["add", "."]);
+        run(&["commit", "-q", "-m", "init"]);
+        fs::write(repo.join("c.txt"), "draft\n").unwrap();
+
+        let mut checkpoint = Checkpoint::create_in(&root.join("checkpoints")).unwrap();
+        // 仓库快照之前快照过的文件以更早的快照为准
+        checkpoint.add(&repo.join("a.txt")).unwrap();
+        fs::write(repo.join("a.txt"), "two\n").unwrap();
+        let repo_root = repo_root(&repo).unwrap();
+        assert!(checkpoint.add_repo(&repo_root).unwrap());
+        assert!(!checkpoint.add_repo(&repo_root).unwrap());
+
+        // 命令的改动：静态分析看不到
+        fs::write(repo.join("b.txt"), "changed\n").unwrap();
+        fs::remove_file(repo.join("c.txt")).unwrap();
+        fs::write(repo.join("d.txt"), "created\n").unwrap();
+        fs::write(repo.join("ignored.txt"), "build output\n").unwrap();
+        checkpoint.add(&repo.join("b.txt")).unwrap();
+        fs::write(repo.join("b.txt"), "changed again\n").unwrap();
+        checkpoint.save().unwrap();
+
+        let mut loaded = list_in(&root.join("checkpoints")).unwrap().remove(0);
+        assert!(loaded.restore().unwrap().is_empty());
+        let read = |name: &str| fs::read_to_string(repo.join(name)).unwrap();
+        assert_eq!(read("a.txt"), "one\n");
+        assert_eq!(read("b.txt"), "keep\n");
+        assert_eq!(read("c.txt"), "draft\n");
+        assert!(!repo.join("d.txt").exists());
+        assert_eq!(read("ignored.txt"), "build output\n");
+        // 用户的暂存区不受影响
+        assert!(run(&["diff", "--cached", "--name-only"]).is_empty());
+        let reference = format!("{}{}", REF_PREFIX, loaded.id);
+        assert_eq!(
+            run(&["rev-parse", "--verify", &reference]),
+            loaded.repos[0].commit
+        );
+    }
+}
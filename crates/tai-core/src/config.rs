@@ -1,10 +1,29 @@
 use dirs_next::home_dir;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
 use tracing::{debug, warn};
 
+use crate::schema::{
+    load_versioned, object_version, parse_json, set_version, suggest_key, Diagnostic, Migration,
+    Schema, Severity,
+};
 use crate::{TaiError, TaiResult};
 
+/// config.json 的 schema 版本
+pub const CONFIG_VERSION: u32 = 2;
+
+/// v1: 无 version 字段；v2: 增加 version 字段
+static CONFIG_SCHEMA: Schema = Schema {
+    current: CONFIG_VERSION,
+    detect: object_version,
+    migrations: &[Migration { from: 1, apply: |doc| set_version(doc, 2) }],
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaiConfig {
     /// ask 命令回答后是否进入可滚动的 Markdown 渲染界面
@@ -23,6 +42,12 @@ pub struct TaiConfig {
     pub max_history_count: u32,
     /// 输出主题
     pub output_theme: String,
+    /// 覆盖 state.json 中的激活模型，格式 "provider/model" 或模型名
+    #[serde(default)]
+    pub model: Option<String>,
+    /// 附加在 ask 提问前的角色设定
+    #[serde(default)]
+    pub role: Option<String>,
 }
 
 impl Default for TaiConfig {
@@ -36,41 +61,118 @@ impl Default for TaiConfig {
             debug_logging: false,
             max_history_count: 50,
             output_theme: "默认".to_string(),
+            model: None,
+            role: None,
         }
     }
 }
 
-fn config_path() -> PathBuf {
+/// 配置项的取值来源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigOrigin {
+    Default,
+    User(PathBuf),
+    Project(PathBuf),
+    Env(String),
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigOrigin::Default => write!(f, "默认值"),
+            ConfigOrigin::User(path) => write!(f, "用户配置 {}", path.display()),
+            ConfigOrigin::Project(path) => write!(f, "项目配置 {}", path.display()),
+            ConfigOrigin::Env(var) => write!(f, "环境变量 {}", var),
+        }
+    }
+}
+
+/// 合并各层后的有效配置，以及每一项的来源
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    pub config: TaiConfig,
+    pub origins: BTreeMap<String, ConfigOrigin>,
+}
+
+impl LayeredConfig {
+    /// 按 key 排序返回 (key, 有效值, 来源)
+    pub fn entries(&self) -> Vec<(String, Value, ConfigOrigin)> {
+        let values = match serde_json::to_value(&self.config) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        values
+            .into_iter()
+            .map(|(key, value)| {
+                let origin = self
+                    .origins
+                    .get(&key)
+                    .cloned()
+                    .unwrap_or(ConfigOrigin::Default);
+                (key, value, origin)
+            })
+            .collect()
+    }
+}
+
+/// 用户级配置目录 ~/.tai
+pub fn user_tai_dir() -> PathBuf {
     home_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join(".tai")
-        .join("config.json")
+}
+
+/// 用户级配置文件 ~/.tai/config.json
+pub fn user_config_path() -> PathBuf {
+    user_tai_dir().join("config.json")
+}
+
+/// 从 start 向上查找项目级 `.tai/` 目录（不包括用户目录 ~/.tai）
+pub fn find_project_tai_dir(start: &Path) -> Option<PathBuf> {
+    let user_dir = user_tai_dir();
+    start
+        .ancestors()
+        .map(|dir| dir.join(".tai"))
+        .find(|dir| dir.is_dir() && *dir != user_dir)
+}
+
+/// 当前工作目录对应的项目级 `.tai/` 目录
+pub fn project_tai_dir() -> Option<PathBuf> {
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| find_project_tai_dir(&cwd))
 }
 
 impl TaiConfig {
+    /// 加载有效配置：默认值 ← ~/.tai/config.json ← 项目 .tai/config.json ← TAI_* 环境变量
     pub fn load() -> TaiResult<Self> {
-        let path = config_path();
-        if !path.exists() {
-            debug!("配置文件不存在，使用默认配置");
-            return Ok(Self::default());
-        }
-        let content = fs::read_to_string(&path).map_err(|e| {
-            TaiError::FileError(format!("无法读取配置文件 {:?}: {}", path, e))
-        })?;
-        let config: Self = serde_json::from_str(&content).unwrap_or_else(|e| {
-            warn!("配置文件解析失败，使用默认配置: {}", e);
-            Self::default()
-        });
-        debug!("配置文件已加载: {:?}", path);
-        Ok(config)
+        Ok(Self::load_layered()?.config)
+    }
+
+    /// 加载有效配置并记录每项来源
+    pub fn load_layered() -> TaiResult<LayeredConfig> {
+        let project = project_tai_dir()
+            .map(|dir| dir.join("config.json"))
+            .filter(|path| path.exists());
+        merge_layers(
+            Some(&user_config_path()),
+            project.as_deref(),
+            |var| std::env::var(var).ok(),
+        )
+    }
+
+    /// 只加载用户级配置（供 `tai config` 编辑后写回，避免把项目/环境覆盖写入用户文件）
+    pub fn load_user() -> TaiResult<Self> {
+        Ok(merge_layers(Some(&user_config_path()), None, |_| None)?.config)
     }
 
     pub fn save(&self) -> TaiResult<()> {
-        let path = config_path();
+        let path = user_config_path();
         fs::create_dir_all(path.parent().unwrap()).map_err(|e| {
             TaiError::FileError(format!("无法创建配置目录: {}", e))
         })?;
-        let content = serde_json::to_string_pretty(self)?;
+        let doc = set_version(serde_json::to_value(self)?, CONFIG_VERSION)?;
+        let content = serde_json::to_string_pretty(&doc)?;
         fs::write(&path, content).map_err(|e| {
             TaiError::FileError(format!("无法写入配置文件 {:?}: {}", path, e))
         })?;
@@ -78,3 +180,283 @@ impl TaiConfig {
         Ok(())
     }
 }
+
+/// 校验一个 config.json（用户级或项目级），返回所有问题
+pub fn validate_config_file(path: &Path) -> Vec<Diagnostic> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            return vec![Diagnostic::error(
+                path,
+                1,
+                1,
+                format!("无法读取文件: {}", e),
+            )]
+        }
+    };
+    let doc = match parse_json(path, &content) {
+        Ok(doc) => doc,
+        Err(diag) => return vec![diag],
+    };
+    let Some(map) = doc.as_object() else {
+        return vec![Diagnostic::error(path, 1, 1, "顶层必须是 JSON 对象")
+            .with_hint("参考 `tai config show` 的输出，使用 { \"key\": value } 的形式")];
+    };
+
+    let defaults = match serde_json::to_value(TaiConfig::default()) {
+        Ok(Value::Object(m)) => m,
+        _ => Map::new(),
+    };
+    let mut diags = Vec::new();
+
+    let version = object_version(&doc);
+    if version > CONFIG_VERSION {
+        diags.push(
+            Diagnostic::at_key(
+                path,
+                &content,
+                "version",
+                Severity::Error,
+                format!("不支持的版本 {}", version),
+            )
+            .with_hint(format!(
+                "当前 tai 支持的最高版本为 {}，请升级 tai",
+                CONFIG_VERSION
+            )),
+        );
+    } else if version < CONFIG_VERSION {
+        diags.push(
+            Diagnostic::warning(path, 1, 1, format!("版本 {} 较旧", version))
+                .with_hint("下次加载时会自动迁移并备份原文件"),
+        );
+    }
+
+    for (key, value) in map {
+        if key == "version" {
+            if !value.is_u64() {
+                diags.push(
+                    Diagnostic::at_key(
+                        path,
+                        &content,
+                        key,
+                        Severity::Error,
+                        "version 必须是正整数",
+                    )
+                    .with_hint(format!("改为 \"version\": {}", CONFIG_VERSION)),
+                );
+            }
+            continue;
+        }
+        let Some(default) = defaults.get(key) else {
+            let mut diag = Diagnostic::at_key(
+                path,
+                &content,
+                key,
+                Severity::Warning,
+                format!("未知配置项 `{}`，将被忽略", key),
+            );
+            if let Some(similar) = suggest_key(key, defaults.keys().map(String::as_str)) {
+                diag = diag.with_hint(format!("是否想写 `{}`？", similar));
+            }
+            diags.push(diag);
+            continue;
+        };
+        if !accepts(key, value) {
+            let expected = match default {
+                Value::Bool(_) => "true 或 false".to_string(),
+                Value::Number(_) => format!("非负整数，例如 {}", default),
+                Value::String(_) => format!("字符串，例如 {}", default),
+                _ => "字符串或 null".to_string(),
+            };
+            diags.push(
+                Diagnostic::at_key(
+                    path,
+                    &content,
+                    key,
+                    Severity::Error,
+                    format!("`{}` 的值 {} 类型不正确", key, value),
+                )
+                .with_hint(format!("应为{}", expected)),
+            );
+        } else if key == "max_history_count" && value.as_u64() == Some(0) {
+            diags.push(
+                Diagnostic::at_key(
+                    path,
+                    &content,
+                    key,
+                    Severity::Error,
+                    "max_history_count 不能为 0",
+                )
+                .with_hint("取值范围 1 - 500"),
+            );
+        }
+    }
+    diags
+}
+
+/// 配置项对应的环境变量名，如 show_markdown_view → TAI_SHOW_MARKDOWN_VIEW
+pub fn env_var_name(key: &str) -> String {
+    format!("TAI_{}", key.to_uppercase())
+}
+
+fn merge_layers(
+    user: Option<&Path>,
+    project: Option<&Path>,
+    env: impl Fn(&str) -> Option<String>,
+) -> TaiResult<LayeredConfig> {
+    let mut merged = match serde_json::to_value(TaiConfig::default())? {
+        Value::Object(map) => map,
+        _ => unreachable!("TaiConfig 总是序列化为对象"),
+    };
+    let mut origins: BTreeMap<String, ConfigOrigin> = merged
+        .keys()
+        .map(|k| (k.clone(), ConfigOrigin::Default))
+        .collect();
+
+    if let Some(path) = user {
+        overlay_file(&mut merged, &mut origins, path, ConfigOrigin::User(path.to_path_buf()))?;
+    }
+    if let Some(path) = project {
+        overlay_file(&mut merged, &mut origins, path, ConfigOrigin::Project(path.to_path_buf()))?;
+    }
+
+    let keys: Vec<String> = merged.keys().cloned().collect();
+    for key in keys {
+        let var = env_var_name(&key);
+        let Some(raw) = env(&var) else { continue };
+        match parse_env_value(&merged[&key], &raw) {
+            Some(value) => {
+                debug!("环境变量覆盖配置: {}={}", var, raw);
+                merged.insert(key.clone(), value);
+                origins.insert(key, ConfigOrigin::Env(var));
+            }
+            None => warn!("忽略无法解析的环境变量 {}={}", var, raw),
+        }
+    }
+
+    let config: TaiConfig = serde_json::from_value(Value::Object(merged))?;
+    Ok(LayeredConfig { config, origins })
+}
+
+fn overlay_file(
+    merged: &mut Map<String, Value>,
+    origins: &mut BTreeMap<String, ConfigOrigin>,
+    path: &Path,
+    origin: ConfigOrigin,
+) -> TaiResult<()> {
+    if !path.exists() {
+        debug!("配置文件不存在，跳过: {:?}", path);
+        return Ok(());
+    }
+    // 只有用户级配置会在迁移后写回，项目配置可能受版本控制，仅在内存中迁移
+    let persist = matches!(origin, ConfigOrigin::User(_));
+    let layer = match load_versioned(path, &CONFIG_SCHEMA, persist)? {
+        Value::Object(map) => map,
+        _ => {
+            return Err(TaiError::ConfigError(format!(
+                "配置文件 {} 顶层必须是 JSON 对象",
+                path.display()
+            )))
+        }
+    };
+
+    for (key, value) in layer {
+        if key == "version" {
+            continue;
+        }
+        if !merged.contains_key(&key) {
+            debug!("忽略未知配置项 {} ({:?})", key, path);
+            continue;
+        }
+        // 类型不匹配的值会导致整体反序列化失败，此处逐项校验后再覆盖
+        if !accepts(&key, &value) {
+            warn!("配置项 {} 类型不正确，已忽略 ({:?})", key, path);
+            continue;
+        }
+        merged.insert(key.clone(), value);
+        origins.insert(key, origin.clone());
+    }
+    debug!("配置文件已加载: {:?}", path);
+    Ok(())
+}
+
+/// 按默认值的类型判断某个配置值是否可接受（默认为 null 的是可选字符串）
+fn accepts(key: &str, value: &Value) -> bool {
+    let default = serde_json::to_value(TaiConfig::default())
+        .ok()
+        .and_then(|d| d.get(key).cloned())
+        .unwrap_or(Value::Null);
+    match (&default, value) {
+        (Value::Null, Value::Null | Value::String(_)) => true,
+        (Value::Bool(_), Value::Bool(_)) | (Value::String(_), Value::String(_)) => true,
+        (Value::Number(_), Value::Number(n)) => n.as_u64().is_some_and(|n| n <= u32::MAX as u64),
+        _ => false,
+    }
+}
+
+fn parse_env_value(current: &Value, raw: &str) -> Option<Value> {
+    match current {
+        Value::Bool(_) => match raw.trim().to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Some(Value::Bool(true)),
+            "0" | "false" | "no" | "off" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Value::Number(_) => raw.trim().parse::<u32>().ok().map(Value::from),
+        _ if raw.is_empty() => Some(Value::Null),
+        _ => Some(Value::String(raw.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = write_temp(
+            dir.path(),
+            "user.json",
+            r#"{"compact_output": true, "max_history_count": 10}"#,
+        );
+        let project = write_temp(
+            dir.path(),
+            "project.json",
+            r#"{"max_history_count": 20, "model": "deepseek/deepseek-chat", "role": "Rust 专家"}"#,
+        );
+
+        let layered = merge_layers(Some(&user), Some(&project), |var| {
+            (var == "TAI_SHOW_REASONING").then(|| "off".to_string())
+        })
+        .unwrap();
+
+        assert!(layered.config.compact_output);
+        assert_eq!(layered.config.max_history_count, 20);
+        assert_eq!(layered.config.model.as_deref(), Some("deepseek/deepseek-chat"));
+        assert!(!layered.config.show_reasoning);
+
+        assert_eq!(layered.origins["compact_output"], ConfigOrigin::User(user.clone()));
+        assert_eq!(layered.origins["max_history_count"], ConfigOrigin::Project(project.clone()));
+        assert_eq!(
+            layered.origins["show_reasoning"],
+            ConfigOrigin::Env("TAI_SHOW_REASONING".to_string())
+        );
+        assert_eq!(layered.origins["output_theme"], ConfigOrigin::Default);
+    }
+
+    #[test]
+    fn test_invalid_env_value_is_ignored() {
+        let layered = merge_layers(None, None, |var| {
+            (var == "TAI_MAX_HISTORY_COUNT").then(|| "many".to_string())
+        })
+        .unwrap();
+        assert_eq!(layered.config.max_history_count, 50);
+        assert_eq!(layered.origins["max_history_count"], ConfigOrigin::Default);
+    }
+}
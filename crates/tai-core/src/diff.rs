@@ -0,0 +1,245 @@
+/// 超过该规模（旧行数 × 新行数）时不再求最长公共子序列，中间部分整体视为替换
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// 一行差异的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffTag {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// 差异中的一行（不含换行符）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    pub text: String,
+}
+
+/// 一段连续的改动及其上下文，行号从 1 开始
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+impl Hunk {
+    /// unified diff 格式的块头，如 `@@ -3,4 +3,5 @@`
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_len, self.new_start, self.new_len
+        )
+    }
+}
+
+/// 逐行比较两段文本
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // 先去掉公共的首尾，只对中间部分求 LCS
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let line = |tag, text: &str| DiffLine {
+        tag,
+        text: text.to_string(),
+    };
+    let mut result: Vec<DiffLine> = old[..prefix]
+        .iter()
+        .map(|t| line(DiffTag::Equal, t))
+        .collect();
+
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_LCS_CELLS {
+        result.extend(old_mid.iter().map(|t| line(DiffTag::Delete, t)));
+        result.extend(new_mid.iter().map(|t| line(DiffTag::Insert, t)));
+    } else {
+        result.extend(lcs_diff(old_mid, new_mid));
+    }
+
+    result.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|t| line(DiffTag::Equal, t)),
+    );
+    result
+}
+
+/// 动态规划求最长公共子序列并回溯出差异，同一位置先输出删除再输出插入
+fn lcs_diff(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    let (n, m) = (old.len(), new.len());
+    // table[i][j]：old[i..] 与 new[j..] 的 LCS 长度
+    let mut table = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if old[i] == new[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        let (tag, text) = if i < n && j < m && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            (DiffTag::Equal, old[i - 1])
+        } else if i < n && (j == m || table[i + 1][j] >= table[i][j + 1]) {
+            i += 1;
+            (DiffTag::Delete, old[i - 1])
+        } else {
+            j += 1;
+            (DiffTag::Insert, new[j - 1])
+        };
+        result.push(DiffLine {
+            tag,
+            text: text.to_string(),
+        });
+    }
+    result
+}
+
+/// 把差异按改动分组为若干块，每块前后保留 context 行上下文；相距较近的改动合并为一块
+pub fn hunks(lines: &[DiffLine], context: usize) -> Vec<Hunk> {
+    let changes: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| l.tag != DiffTag::Equal)
+        .map(|(i, _)| i)
+        .collect();
+    if changes.is_empty() {
+        return Vec::new();
+    }
+
+    // 每块在 lines 中的范围 [start, end)
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &i in &changes {
+        let start = i.saturating_sub(context);
+        let end = (i + 1 + context).min(lines.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    // 每行之前的旧/新行号
+    let mut old_line = 1;
+    let mut new_line = 1;
+    let mut positions = Vec::with_capacity(lines.len());
+    for line in lines {
+        positions.push((old_line, new_line));
+        match line.tag {
+            DiffTag::Equal => {
+                old_line += 1;
+                new_line += 1;
+            }
+            DiffTag::Delete => old_line += 1,
+            DiffTag::Insert => new_line += 1,
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let lines = lines[start..end].to_vec();
+            let old_len = lines.iter().filter(|l| l.tag != DiffTag::Insert).count();
+            let new_len = lines.iter().filter(|l| l.tag != DiffTag::Delete).count();
+            let (old_start, new_start) = positions[start];
+            Hunk {
+                // unified diff 约定：长度为 0 时起始行号指向前一行
+                old_start: if old_len == 0 {
+                    old_start - 1
+                } else {
+                    old_start
+                },
+                old_len,
+                new_start: if new_len == 0 {
+                    new_start - 1
+                } else {
+                    new_start
+                },
+                new_len,
+                lines,
+            }
+        })
+        .collect()
+}
+
+/// 只应用 accepted 中为 true 的改动块，返回新文本；hunks 须按顺序来自同一份 old 的差异
+///
+/// 换行风格（\n 或 \r\n）和末尾换行沿用 old
+pub fn apply_hunks(old: &str, hunks: &[Hunk], accepted: &[bool]) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let mut result: Vec<&str> = Vec::with_capacity(old_lines.len());
+    let mut next = 0;
+    for (hunk, _) in hunks.iter().zip(accepted).filter(|(_, &ok)| ok) {
+        // 长度为 0 时 old_start 指向插入位置的前一行
+        let start = if hunk.old_len == 0 {
+            hunk.old_start
+        } else {
+            hunk.old_start - 1
+        };
+        result.extend(&old_lines[next..start]);
+        result.extend(
+            hunk.lines
+                .iter()
+                .filter(|l| l.tag != DiffTag::Delete)
+                .map(|l| l.text.as_str()),
+        );
+        next = start + hunk.old_len;
+    }
+    result.extend(&old_lines[next..]);
+
+    let newline = if old.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut text = result.join(newline);
+    if !text.is_empty() && (old.is_empty() || old.ends_with('\n')) {
+        text.push_str(newline);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_and_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let lines = diff_lines(old, new);
+        let tags: Vec<DiffTag> = lines.iter().map(|l| l.tag).collect();
+        assert_eq!(tags[1], DiffTag::Delete);
+        assert_eq!(tags[2], DiffTag::Insert);
+        assert_eq!(lines.last().unwrap().text, "k");
+
+        let hunks = hunks(&lines, 2);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].header(), "@@ -1,4 +1,4 @@");
+        assert_eq!(hunks[1].header(), "@@ -9,2 +9,3 @@");
+
+        assert_eq!(apply_hunks(old, &hunks, &[true, true]), new);
+        assert_eq!(
+            apply_hunks(old, &hunks, &[false, true]),
+            "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\n"
+        );
+        assert_eq!(apply_hunks(old, &hunks, &[false, false]), old);
+
+        assert!(diff_lines("same\n", "same\n")
+            .iter()
+            .all(|l| l.tag == DiffTag::Equal));
+    }
+}
@@ -35,6 +35,9 @@ pub enum TaiError {
     #[error("初始化错误: {0}")]
     InitError(String),
 
+    #[error("执行策略禁止: {0}")]
+    PolicyViolation(String),
+
     #[error("{0}")]
     Other(String),
 }
@@ -1,7 +1,21 @@
+pub mod audit;
+pub mod checkpoint;
 pub mod config;
+pub mod diff;
 pub mod error;
 pub mod logging;
+pub mod policy;
+pub mod risk;
+pub mod schema;
 
-pub use config::TaiConfig;
+pub use audit::{AuditDecision, AuditEntry};
+pub use checkpoint::{Checkpoint, RepoSnapshot, SnapshotFile};
+pub use config::{
+    env_var_name, find_project_tai_dir, project_tai_dir, user_config_path, user_tai_dir,
+    validate_config_file, ConfigOrigin, LayeredConfig, TaiConfig, CONFIG_VERSION,
+};
+pub use diff::{apply_hunks, diff_lines, hunks, DiffLine, DiffTag, Hunk};
 pub use error::{TaiError, TaiResult};
 pub use logging::init_logging;
+pub use policy::{Policy, PolicyMode, POLICY_FILE};
+pub use risk::{analyze, RiskFinding, RiskLevel, RiskReport};
@@ -1,5 +1,5 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
@@ -17,8 +17,8 @@ fn log_dir() -> PathBuf {
     tai_dir
 }
 
-/// 清理旧的日志文件，只保留最新的 max_files 个
-fn cleanup_old_logs(log_dir: &PathBuf, max_files: usize) {
+/// 清理目录中扩展名为 extension 的旧文件，只保留最新的 max_files 个
+pub(crate) fn cleanup_old_logs(log_dir: &Path, extension: &str, max_files: usize) {
     if let Ok(entries) = fs::read_dir(log_dir) {
         let mut log_files: Vec<_> = entries
             .filter_map(|e| e.ok())
@@ -26,7 +26,7 @@ fn cleanup_old_logs(log_dir: &PathBuf, max_files: usize) {
                 e.path()
                     .extension()
                     .and_then(|s| s.to_str())
-                    .map(|s| s == "log")
+                    .map(|s| s == extension)
                     .unwrap_or(false)
             })
             .collect();
@@ -56,7 +56,7 @@ pub fn init_logging() {
     let log_path = log_dir();
     
     // 清理旧日志
-    cleanup_old_logs(&log_path, 10);
+    cleanup_old_logs(&log_path, "log", 10);
 
     // 创建文件日志 appender（按小时滚动）
     let file_appender = RollingFileAppender::builder()
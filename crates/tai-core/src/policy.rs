@@ -0,0 +1,649 @@
+//! 执行策略：限制 tai 代为执行的命令和写入的文件
+//!
+//! 策略文件为 `~/.tai/policy.toml`，项目 `.tai/policy.toml` 中出现的配置项覆盖用户级同名项：
+//!
+//! ```toml
+//! mode = "normal"                  # normal | dry-run | read-only
+//!
+//! [commands]
+//! allow = ["git", "cargo", "ls"]   # 非空时只允许这些程序
+//! deny = ["curl", "wget"]
+//!
+//! [arguments]
+//! deny = ["git push *--force*"]    # 匹配 `程序名 参数...`，* 匹配任意字符
+//! allow = ["git push --force-with-lease*"]  # deny 规则的例外
+//!
+//! [paths]
+//! jail = true                      # 写入只允许在项目目录内
+//! writable = ["/tmp"]              # 项目目录之外额外允许写入的目录
+//! ```
+//!
+//! 检查基于对命令行的静态分析（见 risk 模块），无法识别脚本内部的行为
+
+use serde::Deserialize;
+use std::{
+    fs,
+    ops::Range,
+    path::{Component, Path, PathBuf},
+};
+use toml::{
+    de::{DeTable, DeValue},
+    Spanned,
+};
+use tracing::{debug, warn};
+
+use crate::config::{find_project_tai_dir, user_tai_dir};
+use crate::risk::{self, Invocation, WriteTarget};
+use crate::schema::suggest_key;
+use crate::{TaiError, TaiResult};
+
+pub const POLICY_FILE: &str = "policy.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PolicyMode {
+    #[default]
+    Normal,
+    /// 只打印将要执行的命令和写入的文件，不实际执行
+    DryRun,
+    /// 拒绝一切会修改文件或系统状态的命令
+    ReadOnly,
+}
+
+impl PolicyMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PolicyMode::Normal => "normal",
+            PolicyMode::DryRun => "dry-run",
+            PolicyMode::ReadOnly => "read-only",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "normal" => Some(PolicyMode::Normal),
+            "dry-run" | "dry_run" => Some(PolicyMode::DryRun),
+            "read-only" | "read_only" => Some(PolicyMode::ReadOnly),
+            _ => None,
+        }
+    }
+}
+
+/// 生效的执行策略
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    pub mode: PolicyMode,
+    /// 程序白名单，为空表示不限制
+    pub allow_commands: Vec<String>,
+    pub deny_commands: Vec<String>,
+    /// deny_args 的例外
+    pub allow_args: Vec<String>,
+    pub deny_args: Vec<String>,
+    /// 写入只允许在 root 和 writable 之内
+    pub jail: bool,
+    pub writable: Vec<PathBuf>,
+    /// 项目目录：项目 `.tai/` 的上级目录，没有项目配置时为当前目录
+    pub root: PathBuf,
+    /// 已加载的策略文件，按覆盖顺序
+    pub sources: Vec<PathBuf>,
+}
+
+/// 不会启动外部进程的 shell 内建命令和关键字，不受 allow 列表限制
+const BUILTINS: &[&str] = &[
+    "cd", "pwd", "echo", "printf", "test", "[", "[[", "]]", "true", "false", ":", "export",
+    "unset", "set", "local", "read", "shift", "return", "exit", "for", "case", "esac", "in",
+    "select", "function", "alias", "type", "wait", "command", "exec", "builtin", "time",
+];
+
+/// 只读模式下禁止的程序（写入路径另行检查）
+const MUTATING_COMMANDS: &[&str] = &[
+    "rm",
+    "rmdir",
+    "unlink",
+    "mv",
+    "cp",
+    "ln",
+    "install",
+    "rsync",
+    "scp",
+    "touch",
+    "mkdir",
+    "truncate",
+    "shred",
+    "dd",
+    "chmod",
+    "chown",
+    "chgrp",
+    "kill",
+    "killall",
+    "pkill",
+    "shutdown",
+    "reboot",
+    "halt",
+    "poweroff",
+    "mount",
+    "umount",
+    "systemctl",
+    "service",
+    "crontab",
+    "useradd",
+    "userdel",
+    "usermod",
+    "passwd",
+    "apt",
+    "apt-get",
+    "dnf",
+    "yum",
+    "pacman",
+    "zypper",
+    "snap",
+];
+
+/// 只读模式下允许的 git 子命令
+const READ_ONLY_GIT: &[&str] = &[
+    "status",
+    "log",
+    "diff",
+    "show",
+    "blame",
+    "grep",
+    "ls-files",
+    "ls-tree",
+    "rev-parse",
+    "describe",
+    "shortlog",
+    "reflog",
+    "cat-file",
+    "help",
+    "version",
+];
+
+impl Policy {
+    /// 加载当前目录对应的策略
+    pub fn load() -> TaiResult<Self> {
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        Self::load_for(&cwd)
+    }
+
+    /// 加载 ~/.tai/policy.toml，再用 cwd 所在项目的 .tai/policy.toml 覆盖
+    ///
+    /// 策略文件有误时返回错误而不是忽略，避免规则在不知情时失效
+    pub fn load_for(cwd: &Path) -> TaiResult<Self> {
+        let project_dir = find_project_tai_dir(cwd);
+        let root = project_dir
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(cwd)
+            .to_path_buf();
+        let mut policy = Policy {
+            root,
+            ..Default::default()
+        };
+        let files = std::iter::once(user_tai_dir()).chain(project_dir);
+        for path in files.map(|dir| dir.join(POLICY_FILE)) {
+            if !path.is_file() {
+                continue;
+            }
+            let content = fs::read_to_string(&path)
+                .map_err(|e| TaiError::FileError(format!("无法读取 {:?}: {}", path, e)))?;
+            policy
+                .apply(&content)
+                .map_err(|e| TaiError::ConfigError(format!("{}: {}", path.display(), e)))?;
+            debug!("已加载执行策略 {:?}", path);
+            policy.sources.push(path);
+        }
+        Ok(policy)
+    }
+
+    /// 用一个策略文件的内容覆盖对应配置项
+    fn apply(&mut self, content: &str) -> Result<(), String> {
+        let at = |span: Option<Range<usize>>, message: &str| match span {
+            Some(span) => format!("第 {} 行: {}", line_at(content, span.start), message),
+            None => message.to_string(),
+        };
+        let table = DeTable::parse(content).map_err(|e| at(e.span(), e.message()))?;
+        check_keys(content, table.get_ref(), "")?;
+        let file: PolicyFile = toml::from_str(content).map_err(|e| at(e.span(), e.message()))?;
+
+        if let Some(mode) = file.mode {
+            self.mode = PolicyMode::parse(mode.get_ref()).ok_or_else(|| {
+                at(
+                    Some(mode.span()),
+                    &format!(
+                        "mode 应为 normal、dry-run 或 read-only，而不是 {:?}",
+                        mode.get_ref()
+                    ),
+                )
+            })?;
+        }
+        let overrides = [
+            (&mut self.allow_commands, file.commands.allow),
+            (&mut self.deny_commands, file.commands.deny),
+            (&mut self.allow_args, file.arguments.allow),
+            (&mut self.deny_args, file.arguments.deny),
+        ];
+        for (field, value) in overrides {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        if let Some(jail) = file.paths.jail {
+            self.jail = jail;
+        }
+        if let Some(dirs) = file.paths.writable {
+            self.writable = dirs
+                .iter()
+                .map(|dir| match expand_home(dir) {
+                    Some(path) if path.is_absolute() => path,
+                    Some(path) => self.root.join(path),
+                    None => self.root.join(dir),
+                })
+                .collect();
+        }
+        Ok(())
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.mode == PolicyMode::DryRun
+    }
+
+    /// 检查一条将在 cwd 中执行的命令行，违反策略时返回 PolicyViolation
+    pub fn check_command(&self, command: &str, cwd: &Path) -> TaiResult<()> {
+        let plan = risk::plan(command, cwd);
+        if plan.truncated && self.is_restricted() {
+            return Err(self.violation("命令嵌套层数过多，无法完整检查".to_string()));
+        }
+        for invocation in &plan.invocations {
+            self.check_invocation(invocation)?;
+        }
+        for target in &plan.writes {
+            self.check_target(target)?;
+        }
+        Ok(())
+    }
+
+    /// 检查 tai 直接写入文件（如 tai do 的写入步骤），相对路径按 cwd 解析
+    pub fn check_write(&self, path: &Path, cwd: &Path) -> TaiResult<()> {
+        self.check_target(&WriteTarget {
+            raw: path.display().to_string(),
+            path: Some(cwd.join(path)),
+        })
+    }
+
+    fn is_restricted(&self) -> bool {
+        self.mode == PolicyMode::ReadOnly
+            || self.jail
+            || !self.allow_commands.is_empty()
+            || !self.deny_commands.is_empty()
+            || !self.deny_args.is_empty()
+    }
+
+    fn check_invocation(&self, invocation: &Invocation) -> TaiResult<()> {
+        let programs = invocation
+            .wrappers
+            .iter()
+            .chain(std::iter::once(&invocation.name));
+        for program in programs {
+            if self
+                .deny_commands
+                .iter()
+                .any(|p| program_matches(p, program))
+            {
+                return Err(self.violation(format!("`{}` 在 [commands] deny 列表中", program)));
+            }
+            if !self.allow_commands.is_empty()
+                && !BUILTINS.contains(&program.as_str())
+                && !self
+                    .allow_commands
+                    .iter()
+                    .any(|p| program_matches(p, program))
+            {
+                return Err(self.violation(format!("`{}` 不在 [commands] allow 列表中", program)));
+            }
+        }
+
+        let line = std::iter::once(invocation.name.as_str())
+            .chain(invocation.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Some(pattern) = self.deny_args.iter().find(|p| wildcard_match(p, &line)) {
+            if !self.allow_args.iter().any(|p| wildcard_match(p, &line)) {
+                return Err(self.violation(format!(
+                    "`{}` 匹配 [arguments] deny 规则 `{}`",
+                    line, pattern
+                )));
+            }
+        }
+
+        if self.mode == PolicyMode::ReadOnly {
+            let name = invocation.name.as_str();
+            if invocation.sudo {
+                return Err(self.violation(format!("只读模式下不能以 root 权限运行 `{}`", name)));
+            }
+            if MUTATING_COMMANDS.contains(&name) {
+                return Err(self.violation(format!("只读模式下不能运行 `{}`", name)));
+            }
+            if name == "git" {
+                if let Some((sub, _)) = risk::git_subcommand(&invocation.args) {
+                    if !READ_ONLY_GIT.contains(&sub) {
+                        return Err(self.violation(format!("只读模式下不能运行 `git {}`", sub)));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_target(&self, target: &WriteTarget) -> TaiResult<()> {
+        if self.mode == PolicyMode::ReadOnly {
+            return Err(self.violation(format!("只读模式下不能写入 {}", target.raw)));
+        }
+        if !self.jail {
+            return Ok(());
+        }
+        let Some(path) = &target.path else {
+            return Err(self.violation(format!(
+                "无法确定 {} 的写入位置，路径限制下不允许",
+                target.raw
+            )));
+        };
+        let path = real_path(path);
+        let allowed = std::iter::once(&self.root)
+            .chain(&self.writable)
+            .any(|dir| path.starts_with(real_path(dir)));
+        if allowed {
+            Ok(())
+        } else {
+            Err(self.violation(format!(
+                "写入 {} 超出项目目录 {}",
+                target.raw,
+                self.root.display()
+            )))
+        }
+    }
+
+    fn violation(&self, reason: String) -> TaiError {
+        let sources: Vec<String> = self
+            .sources
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        warn!("执行策略拒绝: {}", reason);
+        TaiError::PolicyViolation(format!("{}（策略文件: {}）", reason, sources.join(", ")))
+    }
+}
+
+/// 一个策略文件的内容，未出现的配置项为 None，不覆盖之前加载的值
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PolicyFile {
+    mode: Option<Spanned<String>>,
+    commands: ListSection,
+    arguments: ListSection,
+    paths: PathsSection,
+}
+
+/// [commands] 与 [arguments]
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ListSection {
+    allow: Option<Vec<String>>,
+    deny: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PathsSection {
+    jail: Option<bool>,
+    writable: Option<Vec<String>>,
+}
+
+const KNOWN_KEYS: &[&str] = &[
+    "mode",
+    "commands.allow",
+    "commands.deny",
+    "arguments.allow",
+    "arguments.deny",
+    "paths.jail",
+    "paths.writable",
+];
+
+/// 检查策略文件中的键名，未知的键报告所在行并提示相近的配置项
+fn check_keys(content: &str, table: &DeTable<'_>, prefix: &str) -> Result<(), String> {
+    for (key, value) in table {
+        let name = format!("{}{}", prefix, key.get_ref());
+        match value.get_ref() {
+            _ if KNOWN_KEYS.contains(&name.as_str()) => {}
+            // 未知的表同样逐个检查其中的键，以便把 [command] deny 提示为 commands.deny
+            DeValue::Table(table) => check_keys(content, table, &format!("{}.", name))?,
+            _ => {
+                let hint = suggest_key(&name, KNOWN_KEYS.iter().copied())
+                    .map(|k| format!("，是否想写 {}？", k))
+                    .unwrap_or_default();
+                return Err(format!(
+                    "第 {} 行: 未知的配置项 {}{}",
+                    line_at(content, key.span().start),
+                    name,
+                    hint
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 字节偏移所在的行号（从 1 开始）
+fn line_at(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// 程序名规则：按文件名比较，忽略规则和命令中的路径
+fn program_matches(rule: &str, program: &str) -> bool {
+    let rule = rule.rsplit('/').next().unwrap_or(rule);
+    wildcard_match(rule, program)
+}
+
+/// 通配匹配：`*` 匹配任意字符序列（含空格），`?` 匹配单个字符，其余按字面比较
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 * 的位置及其当时对应的文本位置，用于回溯
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    p = sp + 1;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn expand_home(path: &str) -> Option<PathBuf> {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            dirs_next::home_dir().map(|home| home.join(rest.trim_start_matches('/')))
+        }
+        _ => Some(PathBuf::from(path)),
+    }
+}
+
+/// 去掉 `.` / `..` 后解析符号链接；路径尚不存在时解析其最近的已存在上级目录
+pub fn real_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    let mut missing = Vec::new();
+    let mut existing = normalized.as_path();
+    loop {
+        if let Ok(real) = existing.canonicalize() {
+            return missing.iter().rev().fold(real, |dir, name| dir.join(name));
+        }
+        match (existing.file_name(), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return normalized,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(content: &str, root: &Path) -> Policy {
+        let mut policy = Policy {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+        policy.apply(content).unwrap();
+        policy
+    }
+
+    #[test]
+    fn test_parse_policy_file() {
+        let root = std::env::temp_dir();
+        let policy = policy(
+            r#"
+            # 团队策略
+            mode = "read-only"
+
+            [commands]
+            deny = [
+                "curl",   # 禁止下载
+                'wget',
+            ]
+
+            [paths]
+            jail = true
+            writable = ["/tmp", "build"]
+            "#,
+            &root,
+        );
+        assert_eq!(policy.mode, PolicyMode::ReadOnly);
+        assert_eq!(policy.deny_commands, vec!["curl", "wget"]);
+        assert!(policy.jail);
+        assert_eq!(
+            policy.writable,
+            vec![PathBuf::from("/tmp"), root.join("build")]
+        );
+
+        let mut empty = Policy::default();
+        let error = empty.apply("[command]\ndeny = [\"rm\"]").unwrap_err();
+        assert!(
+            error.contains("第 2 行") && error.contains("commands.deny"),
+            "{}",
+            error
+        );
+        assert!(empty.apply("mode = fast").is_err());
+    }
+
+    #[test]
+    fn test_parse_full_toml_syntax() {
+        let root = std::env::temp_dir();
+        let policy = policy(
+            r#"
+            commands = { allow = ["git", "cargo"] }
+            arguments.deny = ["echo \"*\"", 'rm -rf *']
+            [paths]
+            writable = ["""/tmp"""]
+            "#,
+            &root,
+        );
+        assert_eq!(policy.allow_commands, vec!["git", "cargo"]);
+        assert_eq!(policy.deny_args, vec!["echo \"*\"", "rm -rf *"]);
+        assert_eq!(policy.writable, vec![PathBuf::from("/tmp")]);
+
+        // 后加载的文件只覆盖其中出现的配置项
+        let mut layered = policy.clone();
+        layered.apply("[commands]\ndeny = [\"curl\"]").unwrap();
+        assert_eq!(layered.allow_commands, vec!["git", "cargo"]);
+        assert_eq!(layered.deny_commands, vec!["curl"]);
+
+        let mut empty = Policy::default();
+        let error = empty
+            .apply("mode = \"normal\"\n\n[paths]\njail = \"yes\"")
+            .unwrap_err();
+        assert!(error.starts_with("第 4 行"), "{}", error);
+        let error = empty
+            .apply("[commands]\ndeny = [\"rm\"]\ndeny = []")
+            .unwrap_err();
+        assert!(error.starts_with("第 3 行"), "{}", error);
+        let error = empty.apply("mode = \"fast\"").unwrap_err();
+        assert!(
+            error.contains("第 1 行") && error.contains("fast"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_check_commands_and_writes() {
+        let root = std::env::temp_dir().join("tai-policy-test");
+        let policy = policy(
+            r#"
+            [commands]
+            deny = ["curl"]
+            [arguments]
+            deny = ["git push *--force*"]
+            allow = ["git push --force-with-lease*"]
+            [paths]
+            jail = true
+            "#,
+            &root,
+        );
+        let allowed = |command: &str| policy.check_command(command, &root).is_ok();
+
+        assert!(allowed("ls -la | grep foo > out.txt"));
+        assert!(!allowed("sudo /usr/bin/curl https://example.com"));
+        assert!(!allowed("echo $(curl -s example.com)"));
+        assert!(!allowed("git push origin main --force"));
+        assert!(allowed("git push --force-with-lease origin main"));
+        assert!(!allowed("echo hi > /etc/hosts"));
+        assert!(!allowed("rm -rf ../other"));
+        assert!(!allowed("cd /tmp && touch x"));
+        assert!(!allowed("bash -c 'cp a.txt ~/a.txt'"));
+        assert!(!allowed("find / -name '*.log' -exec rm {} \\;"));
+        assert!(!allowed("cat list | xargs rm"));
+        assert!(!allowed("echo hi > \"$OUT\""));
+        assert!(allowed(
+            "cd sub && mkdir -p a/b && echo x 2>/dev/null > a/b/c"
+        ));
+        assert!(policy.check_write(Path::new("src/lib.rs"), &root).is_ok());
+        assert!(policy.check_write(Path::new("../x"), &root).is_err());
+
+        let read_only = Policy {
+            mode: PolicyMode::ReadOnly,
+            ..Default::default()
+        };
+        let readable = |command: &str| read_only.check_command(command, &root).is_ok();
+        assert!(readable("git log --oneline | head -5"));
+        assert!(!readable("git commit -m wip"));
+        assert!(!readable("sed -i 's/a/b/' file.txt"));
+        assert!(!readable("sudo ls"));
+        assert!(!readable("ls > listing.txt"));
+        assert!(read_only.check_write(Path::new("a.txt"), &root).is_err());
+    }
+}
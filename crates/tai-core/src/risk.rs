@@ -0,0 +1,1126 @@
+use std::{
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+/// 命令的风险等级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl RiskLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RiskLevel::Low => "低风险",
+            RiskLevel::Medium => "中风险",
+            RiskLevel::High => "高风险",
+        }
+    }
+}
+
+impl fmt::Display for RiskLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// 单条命中的规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiskFinding {
+    pub level: RiskLevel,
+    pub reason: String,
+}
+
+/// 对整条命令行的分析结果
+#[derive(Debug, Clone, Default)]
+pub struct RiskReport {
+    pub findings: Vec<RiskFinding>,
+}
+
+impl RiskReport {
+    /// 所有命中规则中的最高等级，未命中时为低风险
+    pub fn level(&self) -> RiskLevel {
+        self.findings
+            .iter()
+            .map(|f| f.level)
+            .max()
+            .unwrap_or(RiskLevel::Low)
+    }
+
+    pub fn is_high(&self) -> bool {
+        self.level() == RiskLevel::High
+    }
+
+    fn add(&mut self, level: RiskLevel, reason: impl Into<String>) {
+        let reason = reason.into();
+        if !self.findings.iter().any(|f| f.reason == reason) {
+            self.findings.push(RiskFinding { level, reason });
+        }
+    }
+}
+
+/// 分析命令行的风险，相对路径按当前目录解析
+pub fn analyze(command: &str) -> RiskReport {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    analyze_in(command, &cwd)
+}
+
+/// 分析命令行的风险，相对路径按 cwd 解析（用于判断重定向是否覆盖已有文件）
+pub fn analyze_in(command: &str, cwd: &Path) -> RiskReport {
+    let mut report = RiskReport::default();
+    analyze_into(command, cwd, &mut report, 0);
+    report.findings.sort_by_key(|f| std::cmp::Reverse(f.level));
+    report
+}
+
+/// 嵌套的 `sh -c` / `$(...)` 最多展开的层数
+const MAX_DEPTH: usize = 4;
+
+fn analyze_into(command: &str, cwd: &Path, report: &mut RiskReport, depth: usize) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let compact: String = command.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.contains(":(){:|:&};:") {
+        report.add(RiskLevel::High, "fork 炸弹，会耗尽系统进程");
+    }
+
+    let lexed = lex(command);
+    for nested in &lexed.substitutions {
+        analyze_into(nested, cwd, report, depth + 1);
+    }
+    for pipeline in split_pipelines(lexed.tokens) {
+        check_pipeline(&pipeline, &lexed.substitutions, cwd, report, depth);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 词法分析
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    /// 控制符：| |& || && ; & ( ) 换行
+    Op(String),
+    /// 重定向符：> >> >| &> &>> < 等，目标为下一个 Word
+    Redirect(String),
+}
+
+struct Lexed {
+    tokens: Vec<Token>,
+    /// `$(...)`、反引号、`<(...)` 中的命令文本
+    substitutions: Vec<String>,
+}
+
+fn lex(input: &str) -> Lexed {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut substitutions = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut i = 0;
+
+    macro_rules! flush {
+        () => {
+            if in_word {
+                tokens.push(Token::Word(std::mem::take(&mut word)));
+                in_word = false;
+            }
+        };
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            ' ' | '\t' => {
+                flush!();
+                i += 1;
+            }
+            '\\' if next == Some('\n') => i += 2,
+            '\\' => {
+                if let Some(n) = next {
+                    word.push(n);
+                }
+                in_word = true;
+                i += 2;
+            }
+            '#' if !in_word => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                i += 1;
+                while i < chars.len() && chars[i] != '\'' {
+                    word.push(chars[i]);
+                    i += 1;
+                }
+                i += 1;
+            }
+            '"' => {
+                in_word = true;
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    match chars[i] {
+                        '\\' if i + 1 < chars.len() => {
+                            word.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        '$' if chars.get(i + 1) == Some(&'(') => {
+                            let (inner, end) = read_group(&chars, i + 2);
+                            substitutions.push(inner);
+                            word.push_str("$(..)");
+                            i = end;
+                        }
+                        '`' => {
+                            let (inner, end) = read_backtick(&chars, i + 1);
+                            substitutions.push(inner);
+                            word.push_str("$(..)");
+                            i = end;
+                        }
+                        ch => {
+                            word.push(ch);
+                            i += 1;
+                        }
+                    }
+                }
+                i += 1;
+            }
+            '$' if next == Some('(') => {
+                let (inner, end) = read_group(&chars, i + 2);
+                substitutions.push(inner);
+                word.push_str("$(..)");
+                in_word = true;
+                i = end;
+            }
+            '`' => {
+                let (inner, end) = read_backtick(&chars, i + 1);
+                substitutions.push(inner);
+                word.push_str("$(..)");
+                in_word = true;
+                i = end;
+            }
+            '<' | '>' if next == Some('(') => {
+                // 进程替换 <(...) / >(...)
+                let (inner, end) = read_group(&chars, i + 2);
+                substitutions.push(inner);
+                flush!();
+                tokens.push(Token::Word("$(..)".to_string()));
+                i = end;
+            }
+            '>' | '<' => {
+                // 紧贴的数字是文件描述符，如 2>
+                let fd_prefix = in_word && word.chars().all(|ch| ch.is_ascii_digit());
+                if fd_prefix {
+                    word.clear();
+                    in_word = false;
+                }
+                flush!();
+                let mut op = c.to_string();
+                i += 1;
+                while i < chars.len() && matches!(chars[i], '>' | '<' | '|' | '&') {
+                    // `>&2` 之类的描述符复制不是写文件
+                    if chars[i] == '&'
+                        && !chars
+                            .get(i + 1)
+                            .is_some_and(|n| n.is_ascii_digit() || *n == '-')
+                    {
+                        break;
+                    }
+                    op.push(chars[i]);
+                    i += 1;
+                    if op.len() >= 3 {
+                        break;
+                    }
+                }
+                if op.ends_with('&') {
+                    // 读取描述符编号并丢弃
+                    while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '-') {
+                        i += 1;
+                    }
+                } else {
+                    tokens.push(Token::Redirect(op));
+                }
+            }
+            '&' if next == Some('>') => {
+                flush!();
+                let mut op = "&>".to_string();
+                i += 2;
+                if chars.get(i) == Some(&'>') {
+                    op.push('>');
+                    i += 1;
+                }
+                tokens.push(Token::Redirect(op));
+            }
+            '|' | '&' | ';' | '(' | ')' | '\n' => {
+                flush!();
+                let op = match (c, next) {
+                    ('|', Some('|')) | ('&', Some('&')) | ('|', Some('&')) | (';', Some(';')) => {
+                        i += 2;
+                        format!("{}{}", c, next.unwrap_or_default())
+                    }
+                    _ => {
+                        i += 1;
+                        c.to_string()
+                    }
+                };
+                tokens.push(Token::Op(op));
+            }
+            _ => {
+                word.push(c);
+                in_word = true;
+                i += 1;
+            }
+        }
+    }
+    if in_word {
+        tokens.push(Token::Word(word));
+    }
+
+    Lexed {
+        tokens,
+        substitutions,
+    }
+}
+
+/// 读取到与起始 `(` 配对的 `)`，返回 (内部文本, 结束位置之后的下标)
+fn read_group(chars: &[char], start: usize) -> (String, usize) {
+    let mut depth = 1;
+    let mut quote: Option<char> = None;
+    let mut i = start;
+    while i < chars.len() {
+        let c = chars[i];
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return (chars[start..i].iter().collect(), i + 1);
+                    }
+                }
+                _ => {}
+            },
+        }
+        i += 1;
+    }
+    (chars[start..].iter().collect(), chars.len())
+}
+
+fn read_backtick(chars: &[char], start: usize) -> (String, usize) {
+    let end = chars[start..]
+        .iter()
+        .position(|c| *c == '`')
+        .map(|p| start + p)
+        .unwrap_or(chars.len());
+    (chars[start..end].iter().collect(), end + 1)
+}
+
+// ---------------------------------------------------------------------------
+// 语法结构
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Default)]
+struct SimpleCommand {
+    argv: Vec<String>,
+    /// (重定向符, 目标)
+    redirects: Vec<(String, String)>,
+}
+
+/// 以 `|` 连接的一组命令
+type Pipeline = Vec<SimpleCommand>;
+
+fn split_pipelines(tokens: Vec<Token>) -> Vec<Pipeline> {
+    let mut pipelines = Vec::new();
+    let mut pipeline: Pipeline = Vec::new();
+    let mut current = SimpleCommand::default();
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Word(w) => current.argv.push(w),
+            Token::Redirect(op) => {
+                if let Some(Token::Word(target)) = tokens.peek().cloned() {
+                    tokens.next();
+                    current.redirects.push((op, target));
+                }
+            }
+            Token::Op(op) => {
+                if !current.argv.is_empty() || !current.redirects.is_empty() {
+                    pipeline.push(std::mem::take(&mut current));
+                }
+                if op != "|" && op != "|&" && !pipeline.is_empty() {
+                    pipelines.push(std::mem::take(&mut pipeline));
+                }
+            }
+        }
+    }
+    if !current.argv.is_empty() || !current.redirects.is_empty() {
+        pipeline.push(current);
+    }
+    if !pipeline.is_empty() {
+        pipelines.push(pipeline);
+    }
+    pipelines
+}
+
+/// 去掉 sudo / env / xargs 等前缀后的实际命令
+struct Resolved<'a> {
+    name: String,
+    args: &'a [String],
+    sudo: bool,
+    /// 参数来自 xargs 的标准输入，无法静态得知
+    via_xargs: bool,
+    /// 被去掉的前缀命令，如 sudo、env
+    wrappers: Vec<String>,
+}
+
+const SHELL_KEYWORDS: &[&str] = &[
+    "{", "}", "!", "if", "then", "else", "elif", "fi", "do", "done", "while", "until",
+];
+
+const INTERPRETERS: &[&str] = &[
+    "sh", "bash", "zsh", "dash", "ksh", "fish", "python", "python3", "perl", "ruby", "node",
+];
+
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish"];
+
+/// resolve 会跳过的前缀命令
+const WRAPPERS: &[&str] = &[
+    "sudo", "doas", "env", "xargs", "timeout", "nice", "ionice", "stdbuf", "nohup", "time",
+    "command", "exec", "builtin",
+];
+
+fn basename(word: &str) -> &str {
+    word.rsplit('/').next().unwrap_or(word)
+}
+
+fn is_assignment(word: &str) -> bool {
+    match word.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty()
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+/// 跳过前缀命令的选项；`with_value` 中的选项会额外吃掉一个参数
+fn skip_options(argv: &[String], mut i: usize, with_value: &[&str]) -> usize {
+    while i < argv.len() && argv[i].starts_with('-') && argv[i] != "-" {
+        if argv[i] == "--" {
+            return i + 1;
+        }
+        let takes_value = with_value.contains(&argv[i].as_str());
+        i += if takes_value { 2 } else { 1 };
+    }
+    i
+}
+
+fn resolve(argv: &[String]) -> Option<Resolved<'_>> {
+    let mut i = 0;
+    let mut sudo = false;
+    let mut via_xargs = false;
+    let mut wrappers = Vec::new();
+
+    while i < argv.len() {
+        let word = argv[i].as_str();
+        if SHELL_KEYWORDS.contains(&word) || is_assignment(word) {
+            i += 1;
+            continue;
+        }
+        let name = basename(word);
+        if WRAPPERS.contains(&name) {
+            wrappers.push(name.to_string());
+        }
+        match name {
+            "sudo" | "doas" => {
+                sudo = true;
+                i = skip_options(
+                    argv,
+                    i + 1,
+                    &["-u", "-g", "-C", "-D", "-h", "-p", "-r", "-t", "-U"],
+                );
+            }
+            "env" => {
+                i = skip_options(argv, i + 1, &["-u", "-C", "-S"]);
+                while i < argv.len() && is_assignment(&argv[i]) {
+                    i += 1;
+                }
+            }
+            "xargs" => {
+                via_xargs = true;
+                i = skip_options(
+                    argv,
+                    i + 1,
+                    &["-I", "-n", "-P", "-L", "-d", "-s", "-E", "-a"],
+                );
+            }
+            "timeout" => {
+                i = skip_options(argv, i + 1, &["-s", "-k"]);
+                i += 1; // 时长
+            }
+            "nice" | "ionice" | "stdbuf" => {
+                i = skip_options(argv, i + 1, &["-n", "-c"]);
+            }
+            "nohup" | "time" | "command" | "exec" | "builtin" => i += 1,
+            name => {
+                return Some(Resolved {
+                    name: name.to_string(),
+                    args: &argv[i + 1..],
+                    sudo,
+                    via_xargs,
+                    wrappers,
+                })
+            }
+        }
+    }
+    None
+}
+
+// ---------------------------------------------------------------------------
+// 规则
+// ---------------------------------------------------------------------------
+
+/// 解释器是否从参数（`-c` 或脚本路径）而不是标准输入读取脚本
+///
+/// 只看 `-s` 和 `--` 之前的参数：`sh -s -- --flag` 从标准输入读取脚本，其后都是脚本的参数
+fn reads_script_arg(args: &[String]) -> bool {
+    for arg in args {
+        match arg.as_str() {
+            "-c" => return true,
+            "--" => return false,
+            flags if flags.starts_with('-') => {
+                if !flags.starts_with("--") && flags[1..].contains('s') {
+                    return false;
+                }
+            }
+            _ => return true,
+        }
+    }
+    false
+}
+
+fn check_pipeline(
+    pipeline: &Pipeline,
+    substitutions: &[String],
+    cwd: &Path,
+    report: &mut RiskReport,
+    depth: usize,
+) {
+    let mut downloads = false;
+    for command in pipeline {
+        for (op, target) in &command.redirects {
+            check_redirect(op, target, cwd, report);
+        }
+        let Some(resolved) = resolve(&command.argv) else {
+            continue;
+        };
+        let name = resolved.name.as_str();
+
+        if INTERPRETERS.contains(&name) {
+            if downloads && !reads_script_arg(resolved.args) {
+                report.add(
+                    RiskLevel::High,
+                    format!("下载的内容通过管道直接交给 {} 执行", name),
+                );
+            }
+            let fetches = substitutions
+                .iter()
+                .any(|s| s.contains("curl") || s.contains("wget"));
+            if fetches && resolved.args.iter().any(|a| a.contains("$(..)")) {
+                report.add(RiskLevel::High, format!("下载的脚本直接交给 {} 执行", name));
+            }
+        }
+        if SHELLS.contains(&name) {
+            if let Some(pos) = resolved.args.iter().position(|a| a == "-c") {
+                if let Some(script) = resolved.args.get(pos + 1) {
+                    analyze_into(script, cwd, report, depth + 1);
+                }
+            }
+        }
+        if matches!(name, "curl" | "wget" | "fetch") {
+            downloads = true;
+        }
+
+        let before = report.findings.len();
+        check_command(&resolved, cwd, report);
+        if resolved.sudo && report.findings.len() == before {
+            report.add(RiskLevel::Medium, "以 root 权限运行");
+        }
+    }
+}
+
+fn check_command(cmd: &Resolved, cwd: &Path, report: &mut RiskReport) {
+    let args = cmd.args;
+    let (flags, operands) = split_args(args);
+    let has_short = |c: char| flags.iter().any(|f| !f.starts_with("--") && f.contains(c));
+    let has_long = |name: &str| flags.contains(&name);
+
+    match cmd.name.as_str() {
+        "rm" => {
+            let recursive = has_short('r') || has_short('R') || has_long("--recursive");
+            if has_long("--no-preserve-root") {
+                report.add(RiskLevel::High, "rm 使用了 --no-preserve-root");
+            }
+            if !recursive {
+                report.add(RiskLevel::Medium, "删除文件");
+            } else if cmd.via_xargs {
+                report.add(RiskLevel::High, "递归删除 xargs 传入的路径");
+            } else if let Some(target) = operands.iter().find(|t| is_broad_path(t)) {
+                report.add(RiskLevel::High, format!("递归删除 {}", target));
+            } else {
+                report.add(
+                    RiskLevel::Medium,
+                    format!("递归删除 {}", operands.join(" ")),
+                );
+            }
+        }
+        "dd" => {
+            if let Some(dev) = args.iter().find_map(|a| a.strip_prefix("of=")) {
+                if dev.starts_with("/dev/") && !is_harmless_device(dev) {
+                    report.add(RiskLevel::High, format!("dd 直接写入块设备 {}", dev));
+                }
+            }
+        }
+        name if name.starts_with("mkfs") || matches!(name, "mke2fs" | "mkswap" | "wipefs") => {
+            report.add(
+                RiskLevel::High,
+                format!("{} 会清空磁盘或分区上的数据", name),
+            );
+        }
+        "fdisk" | "sfdisk" | "parted" | "sgdisk" | "shred" => {
+            let device = operands.iter().find(|a| a.starts_with("/dev/"));
+            match device {
+                Some(dev) => report.add(
+                    RiskLevel::High,
+                    format!("{} 直接修改设备 {}", cmd.name, dev),
+                ),
+                None => report.add(
+                    RiskLevel::Medium,
+                    format!("{} 会修改磁盘或销毁数据", cmd.name),
+                ),
+            }
+        }
+        "chmod" | "chown" | "chgrp" => {
+            let recursive = has_short('R') || has_long("--recursive");
+            let broad = operands.iter().skip(1).find(|t| is_broad_path(t));
+            let world_writable = cmd.name == "chmod"
+                && operands.first().is_some_and(|m| {
+                    m.ends_with("777") || m.ends_with("666") || m.contains("o+w") || m == &"a+rwx"
+                });
+            if recursive && world_writable {
+                report.add(RiskLevel::High, "递归设置全局可写权限");
+            } else if recursive && broad.is_some() {
+                report.add(
+                    RiskLevel::High,
+                    format!(
+                        "递归修改 {} 的权限或属主",
+                        broad.copied().unwrap_or_default()
+                    ),
+                );
+            } else if world_writable {
+                report.add(RiskLevel::Medium, "设置全局可写权限");
+            } else if recursive {
+                report.add(RiskLevel::Medium, "递归修改权限或属主");
+            }
+        }
+        "mv" => {
+            if operands.last().is_some_and(|t| *t == "/dev/null") {
+                report.add(RiskLevel::High, "把文件移动到 /dev/null 会丢失数据");
+            } else if operands.iter().any(|t| is_broad_path(t)) {
+                report.add(RiskLevel::Medium, "移动系统目录或主目录");
+            }
+        }
+        "git" => check_git(args, report),
+        "find" => {
+            if args.iter().any(|a| a == "-delete") {
+                report.add(RiskLevel::Medium, "find -delete 会删除匹配的文件");
+            }
+            if let Some(pos) = args.iter().position(|a| a == "-exec" || a == "-execdir") {
+                let end = args[pos + 1..]
+                    .iter()
+                    .position(|a| a == ";" || a == "+")
+                    .map(|p| pos + 1 + p)
+                    .unwrap_or(args.len());
+                if let Some(inner) = resolve(&args[pos + 1..end]) {
+                    let inner = Resolved {
+                        via_xargs: true,
+                        ..inner
+                    };
+                    check_command(&inner, cwd, report);
+                }
+            }
+        }
+        "tee" if !has_short('a') && !has_long("--append") => {
+            for target in &operands {
+                check_overwrite(target, cwd, report);
+            }
+        }
+        "shutdown" | "reboot" | "halt" | "poweroff" => {
+            report.add(RiskLevel::Medium, "关闭或重启系统");
+        }
+        "kill" if args.iter().any(|a| a == "-1") => {
+            report.add(RiskLevel::High, "向所有进程发送信号");
+        }
+        "killall" | "pkill" => report.add(RiskLevel::Medium, "批量结束进程"),
+        _ => {}
+    }
+}
+
+/// git 的子命令及其后的参数（跳过 -C <path> / -c <k=v> 等全局选项）
+pub(crate) fn git_subcommand(args: &[String]) -> Option<(&str, &[String])> {
+    let i = skip_options(args, 0, &["-C", "-c", "--git-dir", "--work-tree"]);
+    args.get(i).map(|sub| (sub.as_str(), &args[i + 1..]))
+}
+
+fn check_git(args: &[String], report: &mut RiskReport) {
+    let Some((sub, rest)) = git_subcommand(args) else {
+        return;
+    };
+    let has = |flag: &str| rest.iter().any(|a| a == flag);
+    let has_short = |c: char| {
+        rest.iter()
+            .any(|a| a.starts_with('-') && !a.starts_with("--") && a.contains(c))
+    };
+
+    match sub {
+        "push" => {
+            if has("--force") || has_short('f') || rest.iter().any(|a| a.starts_with('+')) {
+                report.add(RiskLevel::High, "强制推送会覆盖远程历史");
+            } else if rest.iter().any(|a| a.starts_with("--force-with-lease")) {
+                report.add(RiskLevel::Medium, "带保护的强制推送");
+            } else if has("--delete") || rest.iter().any(|a| a.starts_with(':')) {
+                report.add(RiskLevel::Medium, "删除远程分支");
+            }
+        }
+        "reset" if has("--hard") => {
+            report.add(RiskLevel::Medium, "git reset --hard 会丢弃未提交的修改");
+        }
+        "clean" if has_short('f') || has("--force") => {
+            report.add(RiskLevel::Medium, "git clean 会删除未跟踪的文件");
+        }
+        "checkout" | "restore" if has(".") || has("--") => {
+            report.add(RiskLevel::Medium, "会丢弃工作区的修改");
+        }
+        _ => {}
+    }
+}
+
+fn check_redirect(op: &str, target: &str, cwd: &Path, report: &mut RiskReport) {
+    if op.starts_with('<') {
+        return;
+    }
+    if target.starts_with("/dev/") {
+        if !is_harmless_device(target) {
+            report.add(RiskLevel::High, format!("直接写入设备 {}", target));
+        }
+        return;
+    }
+    if op.ends_with(">>") {
+        if is_system_path(target) {
+            report.add(RiskLevel::Medium, format!("修改系统文件 {}", target));
+        }
+        return;
+    }
+    check_overwrite(target, cwd, report);
+}
+
+fn check_overwrite(target: &str, cwd: &Path, report: &mut RiskReport) {
+    if target.starts_with("/dev/") || target.contains("$(..)") {
+        return;
+    }
+    let path = expand_home(target).map(|p| if p.is_absolute() { p } else { cwd.join(p) });
+    if is_system_path(target) {
+        report.add(RiskLevel::High, format!("覆盖系统文件 {}", target));
+    } else if path.is_some_and(|p| p.is_file()) {
+        report.add(RiskLevel::Medium, format!("覆盖已有文件 {}", target));
+    }
+}
+
+/// 拆分为选项和操作数（`--` 之后全部视为操作数）
+fn split_args(args: &[String]) -> (Vec<&str>, Vec<&str>) {
+    let mut flags = Vec::new();
+    let mut operands = Vec::new();
+    let mut only_operands = false;
+    for arg in args {
+        if only_operands {
+            operands.push(arg.as_str());
+        } else if arg == "--" {
+            only_operands = true;
+        } else if arg.starts_with('-') && arg.len() > 1 {
+            flags.push(arg.as_str());
+        } else {
+            operands.push(arg.as_str());
+        }
+    }
+    (flags, operands)
+}
+
+fn is_harmless_device(path: &str) -> bool {
+    matches!(
+        path,
+        "/dev/null" | "/dev/zero" | "/dev/stdout" | "/dev/stderr" | "/dev/stdin" | "/dev/tty"
+    ) || path.starts_with("/dev/fd/")
+        || path.starts_with("/dev/pts/")
+}
+
+fn expand_home(path: &str) -> Option<PathBuf> {
+    for prefix in ["~", "$HOME", "${HOME}"] {
+        if let Some(rest) = path.strip_prefix(prefix) {
+            if rest.is_empty() || rest.starts_with('/') {
+                let home = dirs_next::home_dir()?;
+                return Some(home.join(rest.trim_start_matches('/')));
+            }
+        }
+    }
+    Some(PathBuf::from(path))
+}
+
+/// 根目录、主目录、当前/上级目录、一级系统目录及其通配
+fn is_broad_path(path: &str) -> bool {
+    let trimmed = path.trim_end_matches('*').trim_end_matches('/');
+    if matches!(
+        trimmed,
+        "" | "~" | "$HOME" | "${HOME}" | "." | ".." | "./" | "../" | "/"
+    ) {
+        return true;
+    }
+    if trimmed.starts_with('/') {
+        let depth = Path::new(trimmed)
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .count();
+        return depth <= 1;
+    }
+    false
+}
+
+const SYSTEM_DIRS: &[&str] = &[
+    "/etc/",
+    "/boot/",
+    "/usr/",
+    "/bin/",
+    "/sbin/",
+    "/lib/",
+    "/var/lib/",
+];
+
+fn is_system_path(path: &str) -> bool {
+    SYSTEM_DIRS.iter().any(|dir| path.starts_with(dir))
+}
+
+// ---------------------------------------------------------------------------
+// 执行计划（供执行策略检查）
+// ---------------------------------------------------------------------------
+
+/// 命令行中会启动的一个程序
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Invocation {
+    /// 程序名（去掉路径）
+    pub name: String,
+    pub args: Vec<String>,
+    /// 被去掉的前缀命令，如 sudo、env、xargs
+    pub wrappers: Vec<String>,
+    pub sudo: bool,
+}
+
+/// 命令会写入或删除的路径；含变量、命令替换或 cd 到未知目录时无法确定，path 为 None
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WriteTarget {
+    pub raw: String,
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct CommandPlan {
+    pub invocations: Vec<Invocation>,
+    pub writes: Vec<WriteTarget>,
+    /// 嵌套层数超过 MAX_DEPTH，内层命令没有展开
+    pub truncated: bool,
+}
+
+impl CommandPlan {
+    fn add_write(&mut self, raw: &str, cwd: Option<&Path>) {
+        if is_harmless_device(raw) {
+            return;
+        }
+        self.writes.push(WriteTarget {
+            raw: raw.to_string(),
+            path: resolve_path(raw, cwd),
+        });
+    }
+}
+
+/// 静态展开命令行（含 sh -c、命令替换和 find -exec），列出会启动的程序和写入的路径
+///
+/// 相对路径按 cwd 解析，并跟踪命令中的 cd
+pub(crate) fn plan(command: &str, cwd: &Path) -> CommandPlan {
+    let mut plan = CommandPlan::default();
+    plan_into(command, &mut Some(cwd.to_path_buf()), &mut plan, 0);
+    plan
+}
+
+/// 命令行会启动的全部程序，包括 sudo、env、xargs 等前缀命令，以及 sh -c 和命令替换中的命令，
+/// 用于按程序放行
+///
+/// 嵌套过深无法完全展开，或带有可以改写程序行为的参数（如 git -c）时返回 None
+pub fn programs(command: &str) -> Option<Vec<String>> {
+    let plan = plan(command, Path::new("."));
+    if plan.truncated || plan.invocations.is_empty() {
+        return None;
+    }
+    let mut programs: Vec<String> = Vec::new();
+    for invocation in &plan.invocations {
+        if overrides_behavior(invocation) {
+            return None;
+        }
+        for program in invocation
+            .wrappers
+            .iter()
+            .chain(std::iter::once(&invocation.name))
+        {
+            if !programs.contains(program) {
+                programs.push(program.clone());
+            }
+        }
+    }
+    Some(programs)
+}
+
+/// 可以让程序执行任意命令的选项，如 git -c alias.x='!sh -c ...'
+const BEHAVIOR_OVERRIDES: &[(&str, &[&str])] = &[
+    ("git", &["-c", "--config-env", "--exec-path"]),
+    ("cargo", &["--config"]),
+];
+
+fn overrides_behavior(invocation: &Invocation) -> bool {
+    BEHAVIOR_OVERRIDES
+        .iter()
+        .filter(|(name, _)| *name == invocation.name)
+        .any(|(_, options)| {
+            invocation.args.iter().any(|arg| {
+                options.iter().any(|option| {
+                    arg == option
+                        || arg
+                            .strip_prefix(option)
+                            .is_some_and(|rest| rest.starts_with('='))
+                })
+            })
+        })
+}
+
+fn plan_into(command: &str, cwd: &mut Option<PathBuf>, plan: &mut CommandPlan, depth: usize) {
+    if depth > MAX_DEPTH {
+        plan.truncated = true;
+        return;
+    }
+    let lexed = lex(command);
+    for nested in &lexed.substitutions {
+        // 命令替换在子 shell 中执行，其中的 cd 不影响外层
+        plan_into(nested, &mut cwd.clone(), plan, depth + 1);
+    }
+    for pipeline in split_pipelines(lexed.tokens) {
+        for command in &pipeline {
+            for (op, target) in &command.redirects {
+                if !op.starts_with('<') {
+                    plan.add_write(target, cwd.as_deref());
+                }
+            }
+            if let Some(resolved) = resolve(&command.argv) {
+                plan_command(&resolved, cwd, plan, depth);
+            }
+        }
+    }
+}
+
+fn plan_command(cmd: &Resolved, cwd: &mut Option<PathBuf>, plan: &mut CommandPlan, depth: usize) {
+    let args = cmd.args;
+    let name = cmd.name.as_str();
+    plan.invocations.push(Invocation {
+        name: cmd.name.clone(),
+        args: args.to_vec(),
+        wrappers: cmd.wrappers.clone(),
+        sudo: cmd.sudo,
+    });
+    let (flags, operands) = split_args(args);
+    let has_short = |c: char| flags.iter().any(|f| !f.starts_with("--") && f.contains(c));
+
+    let targets: Option<Vec<&str>> = match name {
+        "cd" => {
+            *cwd = match operands.first() {
+                Some(dir) => resolve_path(dir, cwd.as_deref()),
+                None => dirs_next::home_dir(),
+            };
+            None
+        }
+        _ if SHELLS.contains(&name) => {
+            if let Some(pos) = args.iter().position(|a| a == "-c") {
+                if let Some(script) = args.get(pos + 1) {
+                    plan_into(script, &mut cwd.clone(), plan, depth + 1);
+                }
+            }
+            None
+        }
+        "rm" | "rmdir" | "unlink" | "touch" | "mkdir" | "truncate" | "shred" | "tee" => {
+            Some(operands)
+        }
+        // mv 同时删除源文件
+        "mv" => Some(operands),
+        "cp" | "ln" | "install" | "rsync" | "scp" => Some(operands.last().copied().into_iter().collect()),
+        "chmod" | "chown" | "chgrp" => Some(operands.into_iter().skip(1).collect()),
+        "sed" | "perl" if has_short('i') => Some(operands.into_iter().skip(1).collect()),
+        "dd" => Some(args.iter().filter_map(|a| a.strip_prefix("of=")).collect()),
+        "find" => {
+            let end = args
+                .iter()
+                .position(|a| a.starts_with('-') || a == "!" || a == "(")
+                .unwrap_or(args.len());
+            let roots: Vec<&str> = match &args[..end] {
+                [] => vec!["."],
+                roots => roots.iter().map(String::as_str).collect(),
+            };
+            if let Some(pos) = args.iter().position(|a| a == "-exec" || a == "-execdir") {
+                let stop = args[pos + 1..]
+                    .iter()
+                    .position(|a| a == ";" || a == "+")
+                    .map(|p| pos + 1 + p)
+                    .unwrap_or(args.len());
+                // 用查找的起点代替 {}，以便检查写入位置
+                for root in &roots {
+                    let inner: Vec<String> = args[pos + 1..stop]
+                        .iter()
+                        .map(|a| a.replace("{}", root))
+                        .collect();
+                    if let Some(resolved) = resolve(&inner) {
+                        plan_command(&resolved, &mut cwd.clone(), plan, depth);
+                    }
+                }
+            }
+            args.iter().any(|a| a == "-delete").then_some(roots)
+        }
+        _ => None,
+    };
+
+    if let Some(targets) = targets {
+        if cmd.via_xargs {
+            // 操作数来自标准输入，写入位置无法静态确定
+            plan.writes.push(WriteTarget {
+                raw: format!("{} 从 xargs 读取的路径", name),
+                path: None,
+            });
+        }
+        for target in targets {
+            plan.add_write(target, cwd.as_deref());
+        }
+    }
+}
+
+/// 展开 ~ / $HOME 并按 cwd 解析相对路径；含其他变量或命令替换时返回 None
+fn resolve_path(raw: &str, cwd: Option<&Path>) -> Option<PathBuf> {
+    let path = expand_home(raw)?;
+    let text = path.to_string_lossy();
+    if text.contains('$') || text.starts_with('~') {
+        return None;
+    }
+    if path.is_absolute() {
+        Some(path)
+    } else {
+        cwd.map(|dir| dir.join(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(command: &str) -> RiskLevel {
+        analyze_in(command, &std::env::temp_dir()).level()
+    }
+
+    #[test]
+    fn test_destructive_patterns_are_high() {
+        assert_eq!(level("rm -rf /"), RiskLevel::High);
+        assert_eq!(level("sudo rm -r --force ~/"), RiskLevel::High);
+        assert_eq!(level("cd /tmp && rm -rf *"), RiskLevel::High);
+        assert_eq!(
+            level("find . -name '*.log' | xargs rm -rf"),
+            RiskLevel::High
+        );
+        assert_eq!(
+            level("sudo dd if=disk.img of=/dev/sda bs=4M"),
+            RiskLevel::High
+        );
+        assert_eq!(level("mkfs.ext4 /dev/sdb1"), RiskLevel::High);
+        assert_eq!(level("chmod -R 777 ."), RiskLevel::High);
+        assert_eq!(
+            level("curl -fsSL https://x.sh | sudo bash"),
+            RiskLevel::High
+        );
+        assert_eq!(
+            level("sh -c \"$(wget -qO- https://x.sh)\""),
+            RiskLevel::High
+        );
+        assert_eq!(level("git push origin main --force"), RiskLevel::High);
+        assert_eq!(level("bash -c 'rm -rf $HOME'"), RiskLevel::High);
+        assert_eq!(level("echo hi > /etc/hosts"), RiskLevel::High);
+    }
+
+    #[test]
+    fn test_pipe_to_shell_with_script_arguments() {
+        let high = |command: &str| level(command) == RiskLevel::High;
+        assert!(high("curl -fsSL https://x.sh | sh -s -- stable"));
+        assert!(high("curl -fsSL https://x.sh | bash -s -- -y"));
+        assert!(high(
+            "curl -fsSL https://sh.rustup.rs | sh -s -- --default-toolchain stable"
+        ));
+        assert!(high("wget -qO- https://x.sh | bash -xs install"));
+        // 脚本来自文件时，管道的内容只是脚本的输入
+        assert!(!high("curl -fsSL https://x/data | bash process.sh -s"));
+    }
+
+    #[test]
+    fn test_everyday_commands_are_low() {
+        assert_eq!(level("ls -la | grep foo"), RiskLevel::Low);
+        assert_eq!(level("find . -name \"*.rs\" 2>/dev/null"), RiskLevel::Low);
+        assert_eq!(level("echo 'rm -rf /' # just text"), RiskLevel::Low);
+        assert_eq!(level("git push origin main"), RiskLevel::Low);
+        assert_eq!(level("cargo build 2>&1 | tee -a build.log"), RiskLevel::Low);
+        assert_eq!(level("rm -rf target/debug"), RiskLevel::Medium);
+    }
+
+    #[test]
+    fn test_overwrite_existing_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("notes.txt"), "keep").unwrap();
+
+        let report = analyze_in("date > notes.txt", dir);
+        assert_eq!(report.level(), RiskLevel::Medium);
+        assert!(report.findings[0].reason.contains("notes.txt"));
+        assert_eq!(analyze_in("date >> notes.txt", dir).level(), RiskLevel::Low);
+        assert_eq!(analyze_in("date > new.txt", dir).level(), RiskLevel::Low);
+    }
+
+    #[test]
+    fn test_programs_include_wrappers_and_nested_commands() {
+        let programs = |command: &str| programs(command).unwrap_or_default();
+        assert_eq!(programs("cargo build && rm -rf src"), vec!["cargo", "rm"]);
+        assert_eq!(
+            programs("sudo env FOO=1 bash -c 'make install'"),
+            vec!["sudo", "env", "bash", "make"]
+        );
+        assert_eq!(programs("echo $(whoami) | xargs ls"), vec!["whoami", "echo", "xargs", "ls"]);
+        assert_eq!(super::programs("git -c alias.x='!rm -rf src' x"), None);
+    }
+}
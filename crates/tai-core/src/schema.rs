@@ -0,0 +1,444 @@
+use serde_json::Value;
+use std::{
+    fmt, fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+use tracing::{debug, info};
+
+use crate::{TaiError, TaiResult};
+
+/// 校验问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// 带位置的配置校验结果
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(path: &Path, line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            line,
+            column,
+            severity: Severity::Error,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub fn warning(path: &Path, line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(path, line, column, message)
+        }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// 定位到 JSON 文本中 `"key":` 的位置，找不到时指向文件开头
+    pub fn at_key(
+        path: &Path,
+        content: &str,
+        key: &str,
+        severity: Severity,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::at_key_in(path, content, 0..content.len(), key, severity, message)
+    }
+
+    /// 与 at_key 相同，但只在 content[span] 中查找（如数组中的某个元素），找不到时指向 span 的开头
+    pub fn at_key_in(
+        path: &Path,
+        content: &str,
+        span: Range<usize>,
+        key: &str,
+        severity: Severity,
+        message: impl Into<String>,
+    ) -> Self {
+        let offset = locate_key(content, span.clone(), key).unwrap_or(span.start);
+        Self::at_offset(path, content, offset, severity, message)
+    }
+
+    /// 定位到 content 中的字节偏移
+    pub fn at_offset(
+        path: &Path,
+        content: &str,
+        offset: usize,
+        severity: Severity,
+        message: impl Into<String>,
+    ) -> Self {
+        let (line, column) = line_column(content, offset);
+        Self {
+            severity,
+            ..Self::error(path, line, column, message)
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.severity {
+            Severity::Error => "错误",
+            Severity::Warning => "警告",
+        };
+        write!(
+            f,
+            "{}:{}:{}: {}: {}",
+            self.path.display(),
+            self.line,
+            self.column,
+            level,
+            self.message
+        )?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n  提示: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// `"key"` 在 content[span] 中首次作为键出现的字节偏移
+fn locate_key(content: &str, span: Range<usize>, key: &str) -> Option<usize> {
+    let needle = format!("\"{}\"", key);
+    let text = content.get(span.clone())?;
+    let mut search_from = 0;
+    while let Some(pos) = text[search_from..].find(&needle) {
+        let start = search_from + pos;
+        if text[start + needle.len()..].trim_start().starts_with(':') {
+            return Some(span.start + start);
+        }
+        search_from = start + needle.len();
+    }
+    None
+}
+
+/// 字节偏移对应的 (行, 列)，均从 1 开始
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let Some(before) = content.get(..offset) else {
+        return (1, 1);
+    };
+    let line = before.matches('\n').count() + 1;
+    let column = offset - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+    (line, column)
+}
+
+/// JSON 数组中每个元素在文本中的字节范围；key 为 None 时取顶层数组，否则取键 key 对应的数组
+///
+/// 用于把针对第 i 个元素的诊断定位到该元素内部，而不是整个文件中第一次出现的键
+pub fn array_element_spans(content: &str, key: Option<&str>) -> Vec<Range<usize>> {
+    let open = match key {
+        Some(key) => locate_key(content, 0..content.len(), key)
+            .and_then(|pos| content[pos..].find('[').map(|i| pos + i)),
+        None => content.find('['),
+    };
+    let Some(open) = open else {
+        return Vec::new();
+    };
+
+    let mut spans = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start: Option<usize> = None;
+    for (i, b) in content.bytes().enumerate().skip(open + 1) {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b',' | b']' | b'}' if depth == 0 => {
+                if let Some(start) = start.take() {
+                    spans.push(start..start + content[start..i].trim_end().len());
+                }
+                if b != b',' {
+                    break;
+                }
+            }
+            b']' | b'}' => depth -= 1,
+            _ if b.is_ascii_whitespace() => {}
+            _ => {
+                start.get_or_insert(i);
+                match b {
+                    b'"' => in_string = true,
+                    b'[' | b'{' => depth += 1,
+                    _ => {}
+                }
+            }
+        }
+    }
+    spans
+}
+
+/// 解析 JSON，失败时返回带行列号和修复建议的诊断
+pub fn parse_json(path: &Path, content: &str) -> Result<Value, Diagnostic> {
+    serde_json::from_str(content).map_err(|e| {
+        let line = e.line().max(1);
+        let column = e.column().max(1);
+        let message = e.to_string();
+        let hint = suggest_syntax_fix(&message, content, line);
+        let mut diag = Diagnostic::error(path, line, column, message);
+        if let Some(hint) = hint {
+            diag = diag.with_hint(hint);
+        }
+        diag
+    })
+}
+
+fn suggest_syntax_fix(message: &str, content: &str, line: usize) -> Option<String> {
+    let source_line = content
+        .lines()
+        .nth(line.saturating_sub(1))
+        .unwrap_or("")
+        .trim();
+    let previous_line = content
+        .lines()
+        .take(line.saturating_sub(1))
+        .filter(|l| !l.trim().is_empty())
+        .last()
+        .unwrap_or("")
+        .trim();
+
+    if message.contains("trailing comma") {
+        Some("删除最后一个元素后面多余的逗号".to_string())
+    } else if message.contains("key must be a string") {
+        Some("JSON 的键名必须用双引号包裹，例如 \"show_reasoning\": true".to_string())
+    } else if message.contains("expected `,` or `}`") || message.contains("expected `,` or `]`") {
+        if !previous_line.ends_with(',')
+            && !previous_line.ends_with('{')
+            && !previous_line.ends_with('[')
+        {
+            Some(format!("上一行 `{}` 末尾可能缺少逗号", previous_line))
+        } else {
+            Some("检查该位置前是否缺少逗号或多了字符".to_string())
+        }
+    } else if message.contains("EOF while parsing") {
+        Some("文件提前结束，检查括号 { } / [ ] 是否成对闭合".to_string())
+    } else if message.contains("expected value") || message.contains("expected ident") {
+        if source_line.contains('\'') {
+            Some("JSON 字符串必须使用双引号，不能用单引号".to_string())
+        } else {
+            Some("值应为 true / false、数字或双引号字符串".to_string())
+        }
+    } else if message.contains("control character") {
+        Some("字符串中的换行或制表符需要转义为 \\n / \\t".to_string())
+    } else {
+        None
+    }
+}
+
+/// 在候选键中查找与 key 最接近的一个（用于拼写错误提示）
+pub fn suggest_key<'a>(
+    key: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(key, c), c))
+        .filter(|(d, c)| *d <= (c.len() / 3).max(2))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// 单步迁移：把 `from` 版本的文档转换为 `from + 1` 版本
+pub struct Migration {
+    pub from: u32,
+    pub apply: fn(Value) -> TaiResult<Value>,
+}
+
+/// 一类版本化配置文件的描述
+pub struct Schema {
+    /// 当前版本
+    pub current: u32,
+    /// 识别文档版本（缺少 version 字段的旧文件视为 1）
+    pub detect: fn(&Value) -> u32,
+    pub migrations: &'static [Migration],
+}
+
+impl Schema {
+    /// 将文档逐步迁移到当前版本，返回 (新文档, 原版本)
+    pub fn migrate(&self, path: &Path, mut doc: Value) -> TaiResult<(Value, u32)> {
+        let original = (self.detect)(&doc);
+        if original > self.current {
+            return Err(TaiError::ConfigError(format!(
+                "{} 的版本为 {}，高于当前 tai 支持的 {}，请升级 tai",
+                path.display(),
+                original,
+                self.current
+            )));
+        }
+        let mut version = original;
+        while version < self.current {
+            let step = self
+                .migrations
+                .iter()
+                .find(|m| m.from == version)
+                .ok_or_else(|| {
+                    TaiError::ConfigError(format!(
+                        "{} 缺少从版本 {} 开始的迁移",
+                        path.display(),
+                        version
+                    ))
+                })?;
+            doc = (step.apply)(doc)?;
+            version += 1;
+            debug!("{} 已迁移到版本 {}", path.display(), version);
+        }
+        Ok((doc, original))
+    }
+}
+
+/// 读取版本化 JSON 文件；版本较旧时迁移，并在 `persist` 为真时备份原文件后写回
+pub fn load_versioned(path: &Path, schema: &Schema, persist: bool) -> TaiResult<Value> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| TaiError::FileError(format!("无法读取 {:?}: {}", path, e)))?;
+    let doc = parse_json(path, &content).map_err(|d| {
+        TaiError::ConfigError(format!("{}\n  运行 `tai config validate` 查看全部问题", d))
+    })?;
+    let (doc, original) = schema.migrate(path, doc)?;
+
+    if persist && original < schema.current {
+        let backup = backup_path(path, original);
+        fs::copy(path, &backup).map_err(|e| {
+            TaiError::FileError(format!("无法备份 {:?} 到 {:?}: {}", path, backup, e))
+        })?;
+        fs::write(path, serde_json::to_string_pretty(&doc)?)
+            .map_err(|e| TaiError::FileError(format!("无法写入 {:?}: {}", path, e)))?;
+        info!(
+            "{} 已从版本 {} 升级到 {}，原文件备份为 {}",
+            path.display(),
+            original,
+            schema.current,
+            backup.display()
+        );
+    }
+    Ok(doc)
+}
+
+/// 迁移前的备份路径，如 providers.json → providers.json.v1.bak
+pub fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}.bak", version));
+    path.with_file_name(name)
+}
+
+/// 对象形式文档的版本识别：读取 `version` 字段，缺省为 1
+pub fn object_version(doc: &Value) -> u32 {
+    doc.get("version")
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(1)
+}
+
+/// 给对象形式的文档写入 version 字段
+pub fn set_version(mut doc: Value, version: u32) -> TaiResult<Value> {
+    match doc.as_object_mut() {
+        Some(map) => {
+            map.insert("version".to_string(), Value::from(version));
+            Ok(doc)
+        }
+        None => Err(TaiError::ConfigError(
+            "配置文件顶层必须是 JSON 对象".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_has_location_and_hint() {
+        let content = "{\n  \"save_history\": true\n  \"show_reasoning\": false\n}";
+        let diag = parse_json(Path::new("config.json"), content).unwrap_err();
+        assert_eq!(diag.line, 3);
+        assert!(diag.hint.unwrap().contains("缺少逗号"));
+
+        let diag = parse_json(Path::new("c.json"), "{\"a\": 1,}").unwrap_err();
+        assert!(diag.hint.unwrap().contains("多余的逗号"));
+    }
+
+    #[test]
+    fn test_locate_within_array_element() {
+        let content = "{\n  \"providers\": [\n    {\"provider\": \"a\", \"base_url\": \"x\"},\n    {\n      \"provider\": \"b, ]\",\n      \"model_names\": [\"m\"]\n    },\n    3\n  ]\n}";
+        let spans = array_element_spans(content, Some("providers"));
+        assert_eq!(spans.len(), 3);
+        assert!(content[spans[1].clone()].starts_with('{'));
+        assert!(content[spans[1].clone()].ends_with('}'));
+        assert_eq!(&content[spans[2].clone()], "3");
+
+        let path = Path::new("providers.json");
+        let at =
+            |key| Diagnostic::at_key_in(path, content, spans[1].clone(), key, Severity::Error, "");
+        let diag = at("provider");
+        assert_eq!((diag.line, diag.column), (5, 7));
+        // 元素中没有该键时指向元素开头，而不是其他元素中的同名键
+        let diag = at("base_url");
+        assert_eq!((diag.line, diag.column), (4, 5));
+        assert_eq!(array_element_spans("[1, [2, 3]]", None), vec![1..2, 4..10]);
+    }
+
+    #[test]
+    fn test_suggest_key() {
+        let keys = ["show_reasoning", "save_history", "model"];
+        assert_eq!(suggest_key("show_reasonig", keys), Some("show_reasoning"));
+        assert_eq!(suggest_key("completely_else", keys), None);
+    }
+
+    #[test]
+    fn test_migrate_and_backup() {
+        static SCHEMA: Schema = Schema {
+            current: 2,
+            detect: object_version,
+            migrations: &[Migration {
+                from: 1,
+                apply: |doc| set_version(doc, 2),
+            }],
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        fs::write(&path, r#"{"provider": "openai", "model": "gpt-4o"}"#).unwrap();
+
+        let doc = load_versioned(&path, &SCHEMA, true).unwrap();
+        assert_eq!(doc["version"], 2);
+        assert!(backup_path(&path, 1).exists());
+        let rewritten: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(rewritten["version"], 2);
+
+        fs::write(&path, r#"{"version": 9}"#).unwrap();
+        assert!(load_versioned(&path, &SCHEMA, true).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::VecDeque;
+
+/// 有界环形缓冲区：超出容量时丢弃最早的字节，只保留最近的输出
+#[derive(Debug, Clone)]
+pub struct RingBuffer {
+    data: VecDeque<u8>,
+    capacity: usize,
+    /// 累计写入的字节数（含已丢弃部分）
+    total: usize,
+}
+
+impl RingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            data: VecDeque::with_capacity(capacity.min(64 * 1024)),
+            capacity,
+            total: 0,
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.total += bytes.len();
+        if self.capacity == 0 {
+            return;
+        }
+        // 单次写入超过容量时只需要保留末尾
+        let bytes = &bytes[bytes.len().saturating_sub(self.capacity)..];
+        let overflow = (self.data.len() + bytes.len()).saturating_sub(self.capacity);
+        self.data.drain(..overflow);
+        self.data.extend(bytes);
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// 是否有输出因超出容量被丢弃
+    pub fn truncated(&self) -> bool {
+        self.total > self.data.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.total = 0;
+    }
+
+    /// 原始字节
+    pub fn bytes(&self) -> Vec<u8> {
+        self.data.iter().copied().collect()
+    }
+
+    /// 按 UTF-8 解码（无效字节替换为 U+FFFD），保留终端控制序列
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.bytes()).into_owned()
+    }
+
+    /// 去掉 ANSI 控制序列后的纯文本
+    pub fn stripped_text(&self) -> String {
+        strip_ansi(&self.text())
+    }
+}
+
+/// 去掉 ANSI 转义序列（CSI、OSC 等），并按终端语义处理回车：
+/// `\r\n` 视为换行，行内单独的 `\r` 只保留其后被重绘的内容（如进度条）
+pub fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI：ESC [ 参数 ... 终止字节 0x40-0x7E
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC / DCS 等字符串序列：以 BEL 或 ESC \ 结束
+                Some(']' | 'P' | '_' | '^' | 'X') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                // 字符集选择等三字节序列，如 ESC ( B
+                Some('(' | ')' | '*' | '+') => {
+                    chars.next();
+                }
+                // 其余两字节序列，如 ESC =、ESC 7
+                _ => {}
+            },
+            '\u{9b}' => {
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\r' => {
+                // 回到行首：丢弃当前行已输出的内容
+                let line_start = plain.rfind('\n').map_or(0, |i| i + 1);
+                plain.truncate(line_start);
+            }
+            '\x08' => {
+                if !plain.ends_with('\n') {
+                    plain.pop();
+                }
+            }
+            c if c.is_control() && c != '\n' && c != '\t' => {}
+            c => plain.push(c),
+        }
+    }
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer_keeps_tail() {
+        let mut buffer = RingBuffer::new(8);
+        buffer.push(b"hello ");
+        buffer.push(b"world");
+        assert_eq!(buffer.text(), "lo world");
+        assert!(buffer.truncated());
+
+        buffer.push(b"0123456789abc");
+        assert_eq!(buffer.text(), "56789abc");
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(
+            strip_ansi("\x1b[1;31merror\x1b[0m: boom\r\n"),
+            "error: boom\n"
+        );
+        assert_eq!(strip_ansi("\x1b]0;title\x07ok"), "ok");
+        assert_eq!(strip_ansi("10%\r50%\r100%\r\ndone"), "100%\ndone");
+        assert_eq!(strip_ansi("ab\x08c"), "ac");
+    }
+}
@@ -1,14 +1,18 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+//! 伪终端（PTY）支持：在伪终端中启动程序或用户的 shell，提供异步读写流、
+//! 窗口大小同步、有界输出缓冲（可去除 ANSI 控制序列）以及超时与终止
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+mod buffer;
+#[cfg(unix)]
+mod process;
+#[cfg(unix)]
+mod sys;
+#[cfg(unix)]
+mod terminal;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use buffer::{strip_ansi, RingBuffer};
+#[cfg(unix)]
+pub use process::{
+    PtyCommand, PtyOutput, PtyProcess, PtyReader, PtySize, PtyWriter, DEFAULT_CAPACITY,
+};
+#[cfg(unix)]
+pub use terminal::{terminal_size, RawMode};
@@ -0,0 +1,559 @@
+use std::{
+    ffi::{OsStr, OsString},
+    future::Future,
+    io::{self, Write},
+    os::fd::{AsFd, AsRawFd, OwnedFd},
+    path::PathBuf,
+    pin::Pin,
+    process::{ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{unix::AsyncFd, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    process::{Child, Command},
+    signal::unix::{signal, SignalKind},
+    task::JoinHandle,
+};
+use tracing::debug;
+
+use crate::{sys, terminal_size, RawMode, RingBuffer};
+
+/// 默认保留的输出字节数
+pub const DEFAULT_CAPACITY: usize = 256 * 1024;
+
+/// 子进程退出后继续读取残留输出的最长时间
+const DRAIN_GRACE: Duration = Duration::from_millis(200);
+
+/// 伪终端窗口大小
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PtySize {
+    pub rows: u16,
+    pub cols: u16,
+}
+
+impl Default for PtySize {
+    fn default() -> Self {
+        Self { rows: 24, cols: 80 }
+    }
+}
+
+/// 要在伪终端中启动的程序
+#[derive(Debug, Clone)]
+pub struct PtyCommand {
+    program: OsString,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    cwd: Option<PathBuf>,
+    size: PtySize,
+    capacity: usize,
+}
+
+impl PtyCommand {
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Self {
+            program: program.as_ref().to_os_string(),
+            args: Vec::new(),
+            envs: Vec::new(),
+            cwd: None,
+            size: terminal_size().unwrap_or_default(),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// 用户的 shell：$SHELL，缺省 /bin/sh
+    pub fn user_shell() -> Self {
+        let shell = std::env::var_os("SHELL")
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "/bin/sh".into());
+        Self::new(shell)
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
+        self
+    }
+
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.envs
+            .push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        self
+    }
+
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(dir.into());
+        self
+    }
+
+    /// 初始窗口大小，默认跟随当前终端（无终端时 24x80）
+    pub fn size(mut self, size: PtySize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// 输出环形缓冲区的容量（字节）
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// 在新的伪终端中启动，子进程的 stdin/stdout/stderr 均为 slave 端，并以其为控制终端
+    pub fn spawn(self) -> io::Result<PtyProcess> {
+        let (master, slave) = sys::open_pty(self.size)?;
+        sys::set_nonblocking(master.as_raw_fd())?;
+
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .envs(self.envs.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave))
+            .kill_on_drop(true);
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(cwd);
+        }
+        // SAFETY: 闭包只调用 async-signal-safe 的 setsid / ioctl
+        unsafe {
+            command.pre_exec(sys::make_controlling_terminal);
+        }
+        let child = command.spawn()?;
+        // command 持有 slave 的副本，必须释放，否则子进程退出后读取端收不到 EOF
+        drop(command);
+        debug!("伪终端中启动 {:?}，pid {:?}", self.program, child.id());
+
+        let output = Arc::new(Mutex::new(RingBuffer::new(self.capacity)));
+        let reader = PtyReader {
+            fd: AsyncFd::new(master.try_clone()?)?,
+            output: Arc::clone(&output),
+        };
+        let writer = PtyWriter {
+            fd: AsyncFd::new(master.try_clone()?)?,
+        };
+        Ok(PtyProcess {
+            child,
+            master: Arc::new(master),
+            reader: Some(reader),
+            writer: Some(writer),
+            output,
+        })
+    }
+}
+
+/// 运行在伪终端中的子进程
+pub struct PtyProcess {
+    child: Child,
+    /// 用于调整窗口大小
+    master: Arc<OwnedFd>,
+    reader: Option<PtyReader>,
+    writer: Option<PtyWriter>,
+    output: Arc<Mutex<RingBuffer>>,
+}
+
+/// 子进程结束后的结果
+#[derive(Debug)]
+pub struct PtyOutput {
+    pub status: ExitStatus,
+    pub output: RingBuffer,
+}
+
+impl PtyProcess {
+    pub fn id(&self) -> Option<u32> {
+        self.child.id()
+    }
+
+    /// 取出输出流；读到的内容同时写入输出缓冲区
+    pub fn take_reader(&mut self) -> Option<PtyReader> {
+        self.reader.take()
+    }
+
+    /// 取出输入流，写入的内容相当于在终端中键入
+    pub fn take_writer(&mut self) -> Option<PtyWriter> {
+        self.writer.take()
+    }
+
+    /// 调整窗口大小，内核会向前台进程组发送 SIGWINCH
+    pub fn resize(&self, size: PtySize) -> io::Result<()> {
+        sys::set_size(self.master.as_raw_fd(), size)
+    }
+
+    pub fn size(&self) -> io::Result<PtySize> {
+        sys::get_size(self.master.as_raw_fd())
+    }
+
+    /// 监听当前终端的 SIGWINCH，把窗口大小同步给子进程；返回的任务在 abort 前一直运行
+    pub fn follow_terminal_size(&self) -> io::Result<JoinHandle<()>> {
+        let mut winch = signal(SignalKind::window_change())?;
+        let master = Arc::clone(&self.master);
+        Ok(tokio::spawn(async move {
+            while winch.recv().await.is_some() {
+                if let Some(size) = terminal_size() {
+                    debug!("同步窗口大小: {:?}", size);
+                    let _ = sys::set_size(master.as_raw_fd(), size);
+                }
+            }
+        }))
+    }
+
+    /// 到目前为止捕获的输出（只包含已经通过 PtyReader 读取的部分）
+    pub fn output(&self) -> RingBuffer {
+        self.output
+            .lock()
+            .map(|buffer| buffer.clone())
+            .unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+        self.child.wait().await
+    }
+
+    /// 最多等待 timeout，超时返回 None（不会终止子进程）
+    pub async fn wait_timeout(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        match tokio::time::timeout(timeout, self.child.wait()).await {
+            Ok(status) => status.map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// 强制终止子进程所在的整个进程组（SIGKILL）并回收
+    ///
+    /// 子进程在 spawn 时通过 setsid 成为进程组的首进程，shell 启动的管道和后台进程也在这个组里，
+    /// 只终止 shell 本身会让它们继续运行
+    pub async fn kill(&mut self) -> io::Result<()> {
+        debug!("终止伪终端子进程组 {:?}", self.child.id());
+        if let Some(pid) = self.child.id() {
+            sys::kill_group(pid)?;
+        }
+        self.child.kill().await
+    }
+
+    /// 读取全部输出直到子进程退出；设置 timeout 时超时会终止子进程并返回 TimedOut
+    ///
+    /// 输出流已被 take_reader 取走时只等待退出，输出由调用方负责读取
+    pub async fn wait_with_output(mut self, timeout: Option<Duration>) -> io::Result<PtyOutput> {
+        let reader = self.reader.take();
+        let drain = async move {
+            if let Some(mut reader) = reader {
+                let mut chunk = [0u8; 4096];
+                while reader.read(&mut chunk).await? > 0 {}
+            }
+            Ok(())
+        };
+        self.finish(drain, timeout).await
+    }
+
+    /// 把子进程接到当前终端上运行：输出实时写到 stdout，stdin 是终端时以原始模式转发键盘输入，
+    /// 窗口大小随终端同步；其余行为与 wait_with_output 相同
+    pub async fn attach(self, timeout: Option<Duration>) -> io::Result<PtyOutput> {
+        self.attach_to(std::io::stdout(), timeout).await
+    }
+
+    /// attach 的实现，输出写到 out
+    async fn attach_to(
+        mut self,
+        mut out: impl Write,
+        timeout: Option<Duration>,
+    ) -> io::Result<PtyOutput> {
+        let reader = self.reader.take();
+        let resize = self.follow_terminal_size().ok();
+        let raw = RawMode::enable().ok();
+        let input = match (&raw, self.writer.take()) {
+            (Some(_), Some(writer)) => Some(tokio::spawn(forward_stdin(writer))),
+            _ => None,
+        };
+
+        let drain = async move {
+            if let Some(mut reader) = reader {
+                let mut chunk = [0u8; 4096];
+                loop {
+                    let n = reader.read(&mut chunk).await?;
+                    if n == 0 {
+                        break;
+                    }
+                    out.write_all(&chunk[..n])?;
+                    out.flush()?;
+                }
+            }
+            Ok(())
+        };
+        let result = self.finish(drain, timeout).await;
+
+        if let Some(task) = input {
+            task.abort();
+        }
+        if let Some(task) = resize {
+            task.abort();
+        }
+        drop(raw);
+        result
+    }
+
+    async fn finish<F>(mut self, drain: F, timeout: Option<Duration>) -> io::Result<PtyOutput>
+    where
+        F: Future<Output = io::Result<()>>,
+    {
+        let status = match timeout {
+            Some(timeout) => {
+                match tokio::time::timeout(timeout, wait_and_drain(&mut self.child, drain)).await {
+                    Ok(status) => status?,
+                    Err(_) => {
+                        self.kill().await?;
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("命令在 {:?} 内没有结束，已终止", timeout),
+                        ));
+                    }
+                }
+            }
+            None => wait_and_drain(&mut self.child, drain).await?,
+        };
+        Ok(PtyOutput {
+            status,
+            output: self.output(),
+        })
+    }
+}
+
+/// 同时等待子进程退出并读空输出；子进程退出后最多再读 DRAIN_GRACE，
+/// 避免后台进程继续占用终端时一直等待
+async fn wait_and_drain<F>(child: &mut Child, drain: F) -> io::Result<ExitStatus>
+where
+    F: Future<Output = io::Result<()>>,
+{
+    tokio::pin!(drain);
+    let mut drained = false;
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status?,
+            result = &mut drain, if !drained => {
+                result?;
+                drained = true;
+            }
+        }
+    };
+    if !drained {
+        if let Ok(result) = tokio::time::timeout(DRAIN_GRACE, &mut drain).await {
+            result?;
+        }
+    }
+    Ok(status)
+}
+
+/// 把终端的键盘输入转发给子进程
+///
+/// stdin 只在可读时才读取，因此阻塞模式的 fd 也不会卡住运行时，任务可以随时 abort
+async fn forward_stdin(mut writer: PtyWriter) -> io::Result<()> {
+    let stdin = AsyncFd::new(std::io::stdin().as_fd().try_clone_to_owned()?)?;
+    let mut chunk = [0u8; 1024];
+    loop {
+        let mut guard = stdin.readable().await?;
+        match guard.try_io(|fd| sys::read(fd.as_raw_fd(), &mut chunk)) {
+            Ok(Ok(0)) => return Ok(()),
+            Ok(Ok(n)) => writer.write_all(&chunk[..n]).await?,
+            Ok(Err(e)) => return Err(e),
+            Err(_would_block) => continue,
+        }
+    }
+}
+
+/// 伪终端的输出流（master 读端）
+pub struct PtyReader {
+    fd: AsyncFd<OwnedFd>,
+    output: Arc<Mutex<RingBuffer>>,
+}
+
+impl AsyncRead for PtyReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            let mut guard = ready!(self.fd.poll_read_ready(cx))?;
+            let unfilled = buf.initialize_unfilled();
+            match guard.try_io(|fd| sys::read(fd.as_raw_fd(), unfilled)) {
+                Ok(Ok(n)) => {
+                    if let Ok(mut output) = self.output.lock() {
+                        output.push(&unfilled[..n]);
+                    }
+                    buf.advance(n);
+                    return Poll::Ready(Ok(()));
+                }
+                // Linux 上 slave 端全部关闭后读取返回 EIO，视为 EOF
+                Ok(Err(e)) if e.raw_os_error() == Some(libc::EIO) => return Poll::Ready(Ok(())),
+                Ok(Err(e)) => return Poll::Ready(Err(e)),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+/// 伪终端的输入流（master 写端）
+pub struct PtyWriter {
+    fd: AsyncFd<OwnedFd>,
+}
+
+impl AsyncWrite for PtyWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let mut guard = ready!(self.fd.poll_write_ready(cx))?;
+            match guard.try_io(|fd| sys::write(fd.as_raw_fd(), buf)) {
+                Ok(result) => return Poll::Ready(result),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> PtyCommand {
+        PtyCommand::new("/bin/sh").arg("-c").arg(script)
+    }
+
+    #[tokio::test]
+    async fn test_runs_in_terminal_and_captures_output() {
+        let result = sh(
+            "test -t 0 && test -t 1 && printf 'tty\\n'; printf '\\033[31mred\\033[0m\\n'; exit 3",
+        )
+        .spawn()
+        .unwrap()
+        .wait_with_output(Some(Duration::from_secs(10)))
+        .await
+        .unwrap();
+        assert_eq!(result.status.code(), Some(3));
+        assert_eq!(result.output.stripped_text(), "tty\nred\n");
+        assert!(result.output.text().contains("\x1b[31m"));
+
+        // attach 在 stdin 不是终端时只转发输出
+        let mut forwarded = Vec::new();
+        let result = sh("echo attached")
+            .spawn()
+            .unwrap()
+            .attach_to(&mut forwarded, Some(Duration::from_secs(10)))
+            .await
+            .unwrap();
+        assert!(result.status.success());
+        assert_eq!(result.output.stripped_text(), "attached\n");
+        assert_eq!(forwarded, b"attached\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_process_group() {
+        let temp = tempfile::tempdir().unwrap();
+        let pid_file = temp.path().join("sleep.pid");
+        // 忽略 SIGHUP：shell 退出时终端挂断不会顺带结束后台进程
+        let script = format!(
+            "trap '' HUP; sleep 30 & echo $! > '{}'; wait",
+            pid_file.display()
+        );
+        let err = sh(&script)
+            .spawn()
+            .unwrap()
+            .wait_with_output(Some(Duration::from_millis(500)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        let pid: libc::pid_t = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        // 后台的 sleep 与 shell 在同一进程组，应一并被终止（可能短暂停留为僵尸进程）
+        let alive = || {
+            // SAFETY: 信号 0 只检查进程是否存在
+            let exists = unsafe { libc::kill(pid, 0) } == 0;
+            let zombie = std::fs::read_to_string(format!("/proc/{}/stat", pid))
+                .is_ok_and(|stat| stat.contains(") Z"));
+            exists && !zombie
+        };
+        for _ in 0..40 {
+            if !alive() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("后台进程 {} 没有被终止", pid);
+    }
+
+    #[tokio::test]
+    async fn test_write_stream_and_resize() {
+        let mut process = sh("read line; echo \"got:$line\"; stty size")
+            .size(PtySize { rows: 24, cols: 80 })
+            .spawn()
+            .unwrap();
+        process
+            .resize(PtySize {
+                rows: 40,
+                cols: 100,
+            })
+            .unwrap();
+        assert_eq!(
+            process.size().unwrap(),
+            PtySize {
+                rows: 40,
+                cols: 100
+            }
+        );
+
+        let mut writer = process.take_writer().unwrap();
+        writer.write_all(b"hello\n").await.unwrap();
+        let result = process
+            .wait_with_output(Some(Duration::from_secs(10)))
+            .await
+            .unwrap();
+        let text = result.output.stripped_text();
+        assert!(text.contains("got:hello"), "{:?}", text);
+        assert!(text.contains("40 100"), "{:?}", text);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_and_buffer_is_bounded() {
+        let started = std::time::Instant::now();
+        let err = sh("echo started; sleep 30")
+            .spawn()
+            .unwrap()
+            .wait_with_output(Some(Duration::from_millis(300)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        let result = sh("i=0; while [ $i -lt 200 ]; do echo line$i; i=$((i+1)); done")
+            .capacity(64)
+            .spawn()
+            .unwrap()
+            .wait_with_output(Some(Duration::from_secs(10)))
+            .await
+            .unwrap();
+        assert!(result.output.truncated());
+        assert_eq!(result.output.len(), 64);
+        assert!(result.output.stripped_text().ends_with("line199\n"));
+    }
+}
@@ -0,0 +1,166 @@
+//! libc 系统调用的薄封装，unsafe 代码集中在这里
+
+use std::{
+    io, mem,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    ptr,
+};
+
+use crate::PtySize;
+
+/// 打开一对伪终端，返回 (master, slave)，两端都设置 FD_CLOEXEC
+pub(crate) fn open_pty(size: PtySize) -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut master: libc::c_int = -1;
+    let mut slave: libc::c_int = -1;
+    let mut winsize = to_winsize(size);
+    // SAFETY: 传入的指针均指向有效的栈变量，name 与 termios 允许为空
+    let ret = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            // Linux 上为 *const，macOS 上为 *mut
+            ptr::addr_of_mut!(winsize),
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: openpty 成功后两个 fd 均有效且归我们所有
+    let (master, slave) = unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+    set_cloexec(master.as_raw_fd())?;
+    set_cloexec(slave.as_raw_fd())?;
+    Ok((master, slave))
+}
+
+fn set_cloexec(fd: RawFd) -> io::Result<()> {
+    // SAFETY: fcntl 只读写 fd 标志
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFD);
+        if flags == -1 || libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    // SAFETY: fcntl 只读写文件状态标志
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        if flags == -1 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// 子进程 exec 之前调用：成为新会话的首进程，并把 stdin（伪终端 slave）设为控制终端
+pub(crate) fn make_controlling_terminal() -> io::Result<()> {
+    // SAFETY: setsid 与 ioctl 都是 async-signal-safe，可在 fork 后的子进程中调用
+    unsafe {
+        if libc::setsid() == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        if libc::ioctl(0, libc::TIOCSCTTY, 0) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// 向进程组 pgid 中的所有进程发送 SIGKILL；进程组已经不存在时视为成功
+pub(crate) fn kill_group(pgid: u32) -> io::Result<()> {
+    // SAFETY: kill 不涉及内存，负数 pid 表示进程组
+    let ret = unsafe { libc::kill(-(pgid as libc::pid_t), libc::SIGKILL) };
+    if ret == -1 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::ESRCH) {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn set_size(fd: RawFd, size: PtySize) -> io::Result<()> {
+    let winsize = to_winsize(size);
+    // SAFETY: winsize 在调用期间有效
+    let ret = unsafe { libc::ioctl(fd, libc::TIOCSWINSZ, &winsize) };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub(crate) fn get_size(fd: RawFd) -> io::Result<PtySize> {
+    // SAFETY: winsize 是纯数据结构，全零是合法值
+    let mut winsize: libc::winsize = unsafe { mem::zeroed() };
+    let ret = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut winsize) };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(PtySize {
+        rows: winsize.ws_row,
+        cols: winsize.ws_col,
+    })
+}
+
+pub(crate) fn read(fd: RawFd, buf: &mut [u8]) -> io::Result<usize> {
+    // SAFETY: buf 在调用期间有效且长度正确
+    let n = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+pub(crate) fn write(fd: RawFd, buf: &[u8]) -> io::Result<usize> {
+    // SAFETY: buf 在调用期间有效且长度正确
+    let n = unsafe { libc::write(fd, buf.as_ptr().cast(), buf.len()) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+fn to_winsize(size: PtySize) -> libc::winsize {
+    libc::winsize {
+        ws_row: size.rows,
+        ws_col: size.cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
+
+/// 终端原始模式的保存与恢复
+pub(crate) struct Termios {
+    fd: RawFd,
+    original: libc::termios,
+}
+
+impl Termios {
+    /// 把 fd 切换为原始模式，返回用于恢复的原设置
+    pub(crate) fn make_raw(fd: RawFd) -> io::Result<Self> {
+        // SAFETY: termios 是纯数据结构，由 tcgetattr 填充
+        unsafe {
+            let mut original: libc::termios = mem::zeroed();
+            if libc::tcgetattr(fd, &mut original) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(fd, libc::TCSANOW, &raw) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self { fd, original })
+        }
+    }
+
+    pub(crate) fn restore(&self) {
+        // SAFETY: original 来自 tcgetattr
+        unsafe {
+            libc::tcsetattr(self.fd, libc::TCSANOW, &self.original);
+        }
+    }
+}
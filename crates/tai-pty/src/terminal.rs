@@ -0,0 +1,35 @@
+use std::io::{self, IsTerminal};
+
+use crate::{sys, PtySize};
+
+/// 当前终端的窗口大小（依次尝试 stdout、stdin），都不是终端时返回 None
+pub fn terminal_size() -> Option<PtySize> {
+    [libc::STDOUT_FILENO, libc::STDIN_FILENO]
+        .into_iter()
+        .find_map(|fd| sys::get_size(fd).ok())
+        .filter(|size| size.rows > 0 && size.cols > 0)
+}
+
+/// 把 stdin 所在终端切换为原始模式，drop 时恢复
+///
+/// 转发键盘输入到子进程时需要：回显、行缓冲和 Ctrl-C 等都交给伪终端处理
+pub struct RawMode {
+    termios: sys::Termios,
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<Self> {
+        if !io::stdin().is_terminal() {
+            return Err(io::Error::other("stdin 不是终端"));
+        }
+        Ok(Self {
+            termios: sys::Termios::make_raw(libc::STDIN_FILENO)?,
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        self.termios.restore();
+    }
+}
@@ -0,0 +1,119 @@
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, ClearType},
+    ExecutableCommand, QueueableCommand,
+};
+use std::io::{self, Write};
+
+use crate::term::RawModeGuard;
+
+pub struct ActionItem {
+    /// 快捷键
+    pub key: char,
+    pub label: String,
+}
+
+impl ActionItem {
+    pub fn new(key: char, label: impl Into<String>) -> Self {
+        Self {
+            key,
+            label: label.into(),
+        }
+    }
+}
+
+/// 单行操作栏：按快捷键直接选择，或用 ←→ 移动后回车确认
+/// 返回选中的 ActionItem 索引，Esc/Ctrl+C 取消返回 None
+pub fn select_action(actions: &[ActionItem]) -> io::Result<Option<usize>> {
+    if actions.is_empty() {
+        return Ok(None);
+    }
+
+    let mut stdout = io::stdout();
+    let mut selected = 0usize;
+
+    let _guard = RawModeGuard::enable()?;
+
+    // 预留两行，避免在终端底部绘制时滚屏导致 start_row 失效
+    stdout.execute(Print("\r\n\r\n"))?;
+    stdout.execute(cursor::MoveToPreviousLine(2))?;
+    let start_row = cursor::position()?.1;
+    stdout.execute(cursor::Hide)?;
+
+    render_bar(&mut stdout, actions, selected, start_row)?;
+
+    let result = loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break None,
+                KeyCode::Esc => break None,
+                KeyCode::Left => {
+                    selected = if selected > 0 { selected - 1 } else { actions.len() - 1 };
+                    render_bar(&mut stdout, actions, selected, start_row)?;
+                }
+                KeyCode::Right | KeyCode::Tab => {
+                    selected = if selected < actions.len() - 1 { selected + 1 } else { 0 };
+                    render_bar(&mut stdout, actions, selected, start_row)?;
+                }
+                KeyCode::Enter => break Some(selected),
+                KeyCode::Char(c) => {
+                    let c = c.to_ascii_lowercase();
+                    if let Some(index) = actions.iter().position(|a| a.key == c) {
+                        break Some(index);
+                    }
+                }
+                _ => {}
+            }
+        }
+    };
+
+    cleanup(&mut stdout, start_row)?;
+    stdout.execute(cursor::Show)?;
+    Ok(result)
+}
+
+fn render_bar(
+    stdout: &mut impl Write,
+    actions: &[ActionItem],
+    selected: usize,
+    start_row: u16,
+) -> io::Result<()> {
+    stdout.queue(cursor::MoveTo(0, start_row))?;
+    stdout.queue(terminal::Clear(ClearType::FromCursorDown))?;
+    stdout.queue(Print("  "))?;
+
+    for (i, action) in actions.iter().enumerate() {
+        let text = format!("[{}] {}", action.key, action.label);
+        if i == selected {
+            stdout
+                .queue(SetForegroundColor(Color::Cyan))?
+                .queue(SetAttribute(Attribute::Bold))?
+                .queue(Print(text))?
+                .queue(SetAttribute(Attribute::Reset))?
+                .queue(ResetColor)?;
+        } else {
+            stdout.queue(Print(text))?;
+        }
+        stdout.queue(Print("  "))?;
+    }
+
+    stdout
+        .queue(SetForegroundColor(Color::DarkGrey))?
+        .queue(Print("\r\n  按快捷键选择，←→ 移动，Enter 确认，Esc 取消"))?
+        .queue(ResetColor)?;
+
+    stdout.flush()?;
+    Ok(())
+}
+
+fn cleanup(stdout: &mut impl Write, start_row: u16) -> io::Result<()> {
+    stdout.queue(cursor::MoveTo(0, start_row))?;
+    stdout.queue(terminal::Clear(ClearType::FromCursorDown))?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use crossterm::{
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    QueueableCommand,
+};
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthStr;
+
+use crate::term::wrap;
+
+/// 命令片段的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Program,
+    Flag,
+    Argument,
+    Pipe,
+    Redirect,
+    Operator,
+    Subshell,
+}
+
+impl TokenKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TokenKind::Program => "程序",
+            TokenKind::Flag => "选项",
+            TokenKind::Argument => "参数",
+            TokenKind::Pipe => "管道",
+            TokenKind::Redirect => "重定向",
+            TokenKind::Operator => "控制符",
+            TokenKind::Subshell => "子命令",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            TokenKind::Program => Color::Cyan,
+            TokenKind::Flag => Color::Yellow,
+            TokenKind::Argument => Color::Green,
+            TokenKind::Pipe | TokenKind::Redirect | TokenKind::Operator => Color::Magenta,
+            TokenKind::Subshell => Color::Blue,
+        }
+    }
+}
+
+/// 命令中的一个片段及其解释
+pub struct Annotation {
+    pub text: String,
+    pub kind: TokenKind,
+    pub explanation: String,
+}
+
+impl Annotation {
+    pub fn new(text: impl Into<String>, kind: TokenKind, explanation: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            kind,
+            explanation: explanation.into(),
+        }
+    }
+}
+
+/// 片段列宽上限，过长的片段单独占一行
+const MAX_TOKEN_WIDTH: usize = 24;
+
+/// 打印带注释的命令：先按类别着色显示整条命令和总体说明，再逐个片段列出解释
+pub fn print_annotated(command: &str, summary: &str, annotations: &[Annotation]) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let width = crossterm::terminal::size()
+        .map(|(w, _)| w as usize)
+        .unwrap_or(80)
+        .max(40);
+
+    stdout.queue(Print("  "))?;
+    print_highlighted(&mut stdout, command, annotations)?;
+    stdout.queue(Print("\n"))?;
+    if !summary.is_empty() {
+        stdout.queue(Print("\n"))?;
+        for line in wrap(summary, width - 2) {
+            stdout.queue(Print(format!("  {}\n", line)))?;
+        }
+    }
+    stdout.queue(Print("\n"))?;
+
+    let column = annotations
+        .iter()
+        .map(|a| a.text.width())
+        .filter(|w| *w <= MAX_TOKEN_WIDTH)
+        .max()
+        .unwrap_or(0);
+    // "  ├─ " + 片段 + 两个空格 + 类别（最宽 6）+ 一个空格
+    let indent = 5 + column + 2 + 7;
+    let text_width = width.saturating_sub(indent).max(20);
+
+    for (i, annotation) in annotations.iter().enumerate() {
+        let last = i + 1 == annotations.len();
+        let (branch, rail) = if last {
+            ("└─", "  ")
+        } else {
+            ("├─", "│ ")
+        };
+        let token_width = annotation.text.width();
+
+        stdout
+            .queue(SetForegroundColor(Color::DarkGrey))?
+            .queue(Print(format!("  {} ", branch)))?
+            .queue(SetForegroundColor(annotation.kind.color()))?
+            .queue(SetAttribute(Attribute::Bold))?
+            .queue(Print(&annotation.text))?
+            .queue(SetAttribute(Attribute::Reset))?
+            .queue(ResetColor)?;
+        if token_width > column {
+            // 过长的片段独占一行，说明另起一行对齐
+            stdout.queue(Print(format!("\n  {} {}", rail, " ".repeat(column))))?;
+        } else {
+            stdout.queue(Print(" ".repeat(column - token_width)))?;
+        }
+
+        let label = annotation.kind.label();
+        stdout
+            .queue(SetForegroundColor(Color::DarkGrey))?
+            .queue(Print(format!(
+                "  {}{} ",
+                label,
+                " ".repeat(6 - label.width())
+            )))?
+            .queue(ResetColor)?;
+
+        for (n, line) in wrap(&annotation.explanation, text_width).iter().enumerate() {
+            if n > 0 {
+                stdout
+                    .queue(SetForegroundColor(Color::DarkGrey))?
+                    .queue(Print(format!("  {}", rail)))?
+                    .queue(ResetColor)?
+                    .queue(Print(" ".repeat(indent - 4)))?;
+            }
+            stdout.queue(Print(format!("{}\n", line)))?;
+        }
+        if annotation.explanation.is_empty() {
+            stdout.queue(Print("\n"))?;
+        }
+    }
+
+    stdout.flush()
+}
+
+/// 按片段在命令中的位置依次着色，找不到的片段忽略
+fn print_highlighted(
+    stdout: &mut impl Write,
+    command: &str,
+    annotations: &[Annotation],
+) -> io::Result<()> {
+    let mut cursor = 0;
+    for annotation in annotations {
+        let Some(offset) = command[cursor..].find(annotation.text.as_str()) else {
+            continue;
+        };
+        if annotation.text.is_empty() {
+            continue;
+        }
+        let start = cursor + offset;
+        let end = start + annotation.text.len();
+        stdout
+            .queue(Print(&command[cursor..start]))?
+            .queue(SetForegroundColor(annotation.kind.color()))?
+            .queue(Print(&command[start..end]))?
+            .queue(ResetColor)?;
+        cursor = end;
+    }
+    stdout.queue(Print(&command[cursor..]))?;
+    Ok(())
+}
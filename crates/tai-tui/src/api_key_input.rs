@@ -7,6 +7,8 @@ use crossterm::{
 };
 use std::io::{self, Write};
 
+use crate::term::RawModeGuard;
+
 fn provider_key_url(provider_name: &str) -> Option<&'static str> {
     match provider_name.to_lowercase().as_str() {
         "openai" => Some("https://platform.openai.com/api-keys"),
@@ -21,8 +23,7 @@ pub fn prompt_api_key(provider_name: &str) -> io::Result<Option<String>> {
     let mut stdout = io::stdout();
     let mut input = String::new();
 
-    terminal::enable_raw_mode()?;
-    let _guard = RawModeGuard;
+    let _guard = RawModeGuard::enable()?;
 
     let start_row = cursor::position()?.1;
     stdout.execute(cursor::Hide)?;
@@ -139,11 +140,3 @@ fn cleanup(stdout: &mut impl Write, start_row: u16) -> io::Result<()> {
     stdout.flush()?;
     Ok(())
 }
-
-struct RawModeGuard;
-
-impl Drop for RawModeGuard {
-    fn drop(&mut self) {
-        let _ = terminal::disable_raw_mode();
-    }
-}
@@ -0,0 +1,186 @@
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{self, ClearType},
+    ExecutableCommand, QueueableCommand,
+};
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthChar;
+
+use crate::term::{wrap, RawModeGuard};
+use crate::RiskTone;
+
+/// 预览区最多占用的行数
+const PREVIEW_LINES: usize = 6;
+
+pub struct CandidateItem {
+    pub command: String,
+    pub explanation: String,
+    pub risk: RiskTone,
+    pub risk_label: String,
+}
+
+impl CandidateItem {
+    pub fn new(
+        command: impl Into<String>,
+        explanation: impl Into<String>,
+        risk: RiskTone,
+        risk_label: impl Into<String>,
+    ) -> Self {
+        Self {
+            command: command.into(),
+            explanation: explanation.into(),
+            risk,
+            risk_label: risk_label.into(),
+        }
+    }
+}
+
+/// 候选命令选择器：上方为命令列表，下方预览选中命令的完整内容、说明和风险
+/// 支持 ↑↓ / 数字键选择，回车确认；返回选中的索引，Esc/q 取消返回 None
+pub fn select_candidate(items: &[CandidateItem]) -> io::Result<Option<usize>> {
+    if items.is_empty() {
+        return Ok(None);
+    }
+
+    let mut stdout = io::stdout();
+    let mut selected = 0usize;
+
+    let _guard = RawModeGuard::enable()?;
+
+    // 预留列表和预览区所需的行数，避免在终端底部绘制时滚屏
+    let height = (items.len() + PREVIEW_LINES + 3) as u16;
+    stdout.execute(Print("\r\n".repeat(height as usize)))?;
+    stdout.execute(cursor::MoveToPreviousLine(height))?;
+    let start_row = cursor::position()?.1;
+    stdout.execute(cursor::Hide)?;
+
+    render(&mut stdout, items, selected, start_row)?;
+
+    let result = loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Up => {
+                    selected = if selected > 0 {
+                        selected - 1
+                    } else {
+                        items.len() - 1
+                    };
+                }
+                KeyCode::Down | KeyCode::Tab => {
+                    selected = if selected < items.len() - 1 {
+                        selected + 1
+                    } else {
+                        0
+                    };
+                }
+                KeyCode::Char(c @ '1'..='9') => {
+                    let index = c as usize - '1' as usize;
+                    if index < items.len() {
+                        selected = index;
+                    }
+                }
+                KeyCode::Enter => break Some(selected),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break None,
+                KeyCode::Esc | KeyCode::Char('q') => break None,
+                _ => continue,
+            }
+            render(&mut stdout, items, selected, start_row)?;
+        }
+    };
+
+    stdout.queue(cursor::MoveTo(0, start_row))?;
+    stdout.queue(terminal::Clear(ClearType::FromCursorDown))?;
+    stdout.queue(cursor::Show)?;
+    stdout.flush()?;
+    Ok(result)
+}
+
+fn render(
+    stdout: &mut impl Write,
+    items: &[CandidateItem],
+    selected: usize,
+    start_row: u16,
+) -> io::Result<()> {
+    let width = terminal::size()?.0.max(20) as usize;
+
+    stdout.queue(cursor::MoveTo(0, start_row))?;
+    stdout.queue(terminal::Clear(ClearType::FromCursorDown))?;
+    stdout
+        .queue(SetForegroundColor(Color::DarkGrey))?
+        .queue(Print("选择命令 (↑↓/数字 移动, Enter 确认, Esc 取消)\r\n"))?
+        .queue(ResetColor)?;
+
+    for (i, item) in items.iter().enumerate() {
+        let prefix = if i == selected { ">" } else { " " };
+        let line = truncate(
+            &format!("{} {}. {}", prefix, i + 1, item.command),
+            width - 1,
+        );
+        if i == selected {
+            stdout
+                .queue(SetForegroundColor(Color::Cyan))?
+                .queue(Print(line))?
+                .queue(ResetColor)?;
+        } else {
+            stdout.queue(Print(line))?;
+        }
+        stdout.queue(Print("\r\n"))?;
+    }
+
+    stdout
+        .queue(SetForegroundColor(Color::DarkGrey))?
+        .queue(Print("─".repeat(width.min(60))))?
+        .queue(ResetColor)?
+        .queue(Print("\r\n"))?;
+
+    // 预览区：完整命令（折行）、说明、风险
+    let item = &items[selected];
+    let mut lines: Vec<(Color, String)> = wrap(&item.command, width - 3)
+        .into_iter()
+        .map(|l| (Color::Cyan, l))
+        .collect();
+    lines.extend(
+        wrap(&item.explanation, width - 3)
+            .into_iter()
+            .map(|l| (Color::Reset, l)),
+    );
+    let risk_color = match item.risk {
+        RiskTone::Low => Color::Green,
+        RiskTone::Medium => Color::Yellow,
+        RiskTone::High => Color::Red,
+    };
+    lines.truncate(PREVIEW_LINES - 1);
+    lines.push((risk_color, item.risk_label.clone()));
+
+    for (color, line) in lines {
+        stdout
+            .queue(SetForegroundColor(color))?
+            .queue(Print(format!("  {}", line)))?
+            .queue(ResetColor)?
+            .queue(Print("\r\n"))?;
+    }
+
+    stdout.flush()?;
+    Ok(())
+}
+
+/// 按显示宽度截断，超出部分以 … 结尾
+fn truncate(text: &str, width: usize) -> String {
+    let mut used = 0;
+    let mut out = String::new();
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width.saturating_sub(1) {
+            out.push('…');
+            return out;
+        }
+        used += w;
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,57 @@
+use crossterm::{
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    QueueableCommand,
+};
+use std::io::{self, Write};
+
+/// 差异预览中一行的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// 块头，如 `@@ -3,4 +3,5 @@`
+    Header,
+    Context,
+    Added,
+    Removed,
+}
+
+/// 差异预览中的一行
+pub struct DiffRow {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+impl DiffRow {
+    pub fn new(kind: DiffKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+        }
+    }
+}
+
+/// 以 unified diff 的样式打印差异：标题加粗，新增绿色，删除红色，块头青色
+pub fn print_diff(title: &str, rows: &[DiffRow]) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout
+        .queue(SetAttribute(Attribute::Bold))?
+        .queue(Print(title))?
+        .queue(SetAttribute(Attribute::Reset))?
+        .queue(Print("\n"))?;
+
+    for row in rows {
+        let (color, prefix) = match row.kind {
+            DiffKind::Header => (Some(Color::Cyan), ""),
+            DiffKind::Context => (None, " "),
+            DiffKind::Added => (Some(Color::Green), "+"),
+            DiffKind::Removed => (Some(Color::Red), "-"),
+        };
+        if let Some(color) = color {
+            stdout.queue(SetForegroundColor(color))?;
+        }
+        stdout
+            .queue(Print(format!("{}{}", prefix, row.text)))?
+            .queue(ResetColor)?
+            .queue(Print("\n"))?;
+    }
+    stdout.flush()
+}
@@ -1,15 +1,28 @@
+mod action_bar;
+mod annotated;
 mod api_key_input;
+mod candidate_selector;
+mod diff_view;
+mod line_editor;
 mod model_selector;
 mod provider_config;
 mod reasoning;
+mod risk_notice;
 mod settings;
 mod spinner;
+mod term;
 mod viewer;
 
+pub use action_bar::{select_action, ActionItem};
+pub use annotated::{print_annotated, Annotation, TokenKind};
 pub use api_key_input::prompt_api_key;
+pub use candidate_selector::{select_candidate, CandidateItem};
+pub use diff_view::{print_diff, DiffKind, DiffRow};
+pub use line_editor::edit_line;
 pub use model_selector::{select_model, ModelItem};
 pub use provider_config::{config_providers, ProviderEntry};
 pub use reasoning::TextRenderer;
+pub use risk_notice::{confirm_typed, confirm_typed_on_tty, print_risk, RiskTone};
 pub use settings::{show_settings, SettingItem, SettingValue};
 pub use spinner::Spinner;
-pub use viewer::{make_default_skin, show_markdown_view};
+pub use viewer::{make_default_skin, show_markdown_view, CopyHandler};
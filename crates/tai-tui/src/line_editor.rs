@@ -0,0 +1,107 @@
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{self, ClearType},
+    ExecutableCommand, QueueableCommand,
+};
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthChar;
+
+use crate::term::RawModeGuard;
+
+const PROMPT: &str = "  > ";
+
+/// 单行编辑器，初始内容为 initial，支持 ←→ / Home / End / Ctrl+A / Ctrl+E / Ctrl+U
+/// Enter 返回编辑后的内容，Esc/Ctrl+C 取消返回 None
+pub fn edit_line(title: &str, initial: &str) -> io::Result<Option<String>> {
+    let mut stdout = io::stdout();
+    let mut buffer: Vec<char> = initial.chars().collect();
+    let mut pos = buffer.len();
+
+    let _guard = RawModeGuard::enable()?;
+
+    // 预留标题行、输入行和提示行，避免底部滚屏
+    stdout.execute(Print("\r\n\r\n\r\n"))?;
+    stdout.execute(cursor::MoveToPreviousLine(3))?;
+    let start_row = cursor::position()?.1;
+
+    stdout
+        .queue(terminal::Clear(ClearType::FromCursorDown))?
+        .queue(SetForegroundColor(Color::Yellow))?
+        .queue(Print(format!("  {}\r\n", title)))?
+        .queue(ResetColor)?;
+    let input_row = start_row + 1;
+    draw_input(&mut stdout, &buffer, pos, input_row)?;
+
+    let result = loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Enter => break Some(buffer.iter().collect::<String>()),
+                KeyCode::Esc => break None,
+                KeyCode::Char('c') if ctrl => break None,
+                KeyCode::Char('a') if ctrl => pos = 0,
+                KeyCode::Char('e') if ctrl => pos = buffer.len(),
+                KeyCode::Char('u') if ctrl => {
+                    buffer.drain(..pos);
+                    pos = 0;
+                }
+                KeyCode::Home => pos = 0,
+                KeyCode::End => pos = buffer.len(),
+                KeyCode::Left => pos = pos.saturating_sub(1),
+                KeyCode::Right => pos = (pos + 1).min(buffer.len()),
+                KeyCode::Backspace if pos > 0 => {
+                    pos -= 1;
+                    buffer.remove(pos);
+                }
+                KeyCode::Delete if pos < buffer.len() => {
+                    buffer.remove(pos);
+                }
+                KeyCode::Char(c) => {
+                    buffer.insert(pos, c);
+                    pos += 1;
+                }
+                _ => continue,
+            }
+            draw_input(&mut stdout, &buffer, pos, input_row)?;
+        }
+    };
+
+    stdout.queue(cursor::MoveTo(0, start_row))?;
+    stdout.queue(terminal::Clear(ClearType::FromCursorDown))?;
+    stdout.flush()?;
+    Ok(result.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()))
+}
+
+/// 重绘输入区域并把光标放到编辑位置（长命令按终端宽度折行）
+fn draw_input(stdout: &mut impl Write, buffer: &[char], pos: usize, input_row: u16) -> io::Result<()> {
+    let width = terminal::size()?.0.max(1) as usize;
+    let text: String = buffer.iter().collect();
+
+    stdout.queue(cursor::MoveTo(0, input_row))?;
+    stdout.queue(terminal::Clear(ClearType::FromCursorDown))?;
+    stdout
+        .queue(SetForegroundColor(Color::Cyan))?
+        .queue(Print(PROMPT))?
+        .queue(Print(&text))?
+        .queue(ResetColor)?;
+    stdout
+        .queue(SetForegroundColor(Color::DarkGrey))?
+        .queue(Print("\r\n  Enter 确认  Esc 取消"))?
+        .queue(ResetColor)?;
+
+    let offset: usize = PROMPT.len()
+        + buffer[..pos]
+            .iter()
+            .map(|c| c.width().unwrap_or(0))
+            .sum::<usize>();
+    let row = input_row + (offset / width) as u16;
+    let col = (offset % width) as u16;
+    stdout.queue(cursor::MoveTo(col, row))?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -7,6 +7,8 @@ use crossterm::{
 };
 use std::io::{self, Write};
 
+use crate::term::RawModeGuard;
+
 pub struct ModelItem {
     pub provider: String,
     pub model: String,
@@ -35,8 +37,7 @@ pub fn select_model(items: &[ModelItem], current_index: usize) -> io::Result<Opt
     let mut stdout = io::stdout();
     let mut selected = current_index.min(items.len() - 1);
 
-    terminal::enable_raw_mode()?;
-    let _guard = RawModeGuard;
+    let _guard = RawModeGuard::enable()?;
 
     // 记录起始行
     let start_row = cursor::position()?.1;
@@ -133,12 +134,3 @@ fn cleanup(stdout: &mut impl Write, items: &[ModelItem], start_row: u16) -> io::
     stdout.flush()?;
     Ok(())
 }
-
-/// RAII 守卫：确保在退出时恢复终端模式
-struct RawModeGuard;
-
-impl Drop for RawModeGuard {
-    fn drop(&mut self) {
-        let _ = terminal::disable_raw_mode();
-    }
-}
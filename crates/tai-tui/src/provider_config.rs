@@ -7,6 +7,8 @@ use crossterm::{
 };
 use std::io::{self, Write};
 
+use crate::term::RawModeGuard;
+
 pub struct ProviderEntry {
     pub name: String,
     pub base_url: String,
@@ -43,8 +45,7 @@ pub fn config_providers(mut providers: Vec<ProviderEntry>) -> io::Result<Option<
     let mut phase = Phase::ProviderList { selected: 0 };
     let mut dirty = false;
 
-    terminal::enable_raw_mode()?;
-    let _guard = RawModeGuard;
+    let _guard = RawModeGuard::enable()?;
 
     let start_row = cursor::position()?.1;
     stdout.execute(cursor::Hide)?;
@@ -360,11 +361,3 @@ fn provider_key_url(provider_name: &str) -> Option<&'static str> {
         _ => None,
     }
 }
-
-struct RawModeGuard;
-
-impl Drop for RawModeGuard {
-    fn drop(&mut self) {
-        let _ = terminal::disable_raw_mode();
-    }
-}
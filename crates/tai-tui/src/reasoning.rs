@@ -7,20 +7,24 @@ use termimad::{
     },
 };
 
-use crate::viewer::{show_markdown_view, make_default_skin};
+use crate::viewer::{show_markdown_view, make_default_skin, CopyHandler};
 
 fn make_answer_skin() -> termimad::MadSkin {
     make_default_skin()
 }
 
 /// 流式渲染器
-/// - 流式阶段：reasoning 灰色增量输出，answer 直接打印 raw markdown
+/// - 流式阶段：reasoning 灰色增量输出，answer 直接打印 raw markdown，工具调用单独成行
 /// - finish 后：进入 alternate screen，展示可滚动的 Markdown 渲染视图
 pub struct TextRenderer {
     reasoning_buffer: String,
     answer_buffer: String,
     reasoning_rendered_bytes: usize,
     answer_rendered_bytes: usize,
+    /// 终端光标是否位于行首
+    at_line_start: bool,
+    /// 渲染视图中复制代码块使用的剪贴板
+    copy_handler: Option<CopyHandler>,
 }
 
 impl TextRenderer {
@@ -30,9 +34,17 @@ impl TextRenderer {
             answer_buffer: String::new(),
             reasoning_rendered_bytes: 0,
             answer_rendered_bytes: 0,
+            at_line_start: true,
+            copy_handler: None,
         }
     }
 
+    /// 设置渲染视图中复制代码块使用的剪贴板
+    pub fn with_copy_handler(mut self, handler: CopyHandler) -> Self {
+        self.copy_handler = Some(handler);
+        self
+    }
+
     pub fn append_reasoning(&mut self, text: &str) {
         self.reasoning_buffer.push_str(text);
     }
@@ -59,6 +71,7 @@ impl TextRenderer {
         stdout.queue(Print(new_text))?;
         stdout.queue(ResetColor)?;
         self.reasoning_rendered_bytes = self.reasoning_buffer.len();
+        self.at_line_start = new_text.ends_with('\n');
         Ok(())
     }
 
@@ -79,9 +92,40 @@ impl TextRenderer {
         let new_text = std::str::from_utf8(new_bytes).unwrap_or_default();
         stdout.queue(Print(new_text))?;
         self.answer_rendered_bytes = self.answer_buffer.len();
+        self.at_line_start = new_text.ends_with('\n');
         Ok(())
     }
 
+    /// 输出一次工具调用，如 `⚙ read_file src/main.rs`
+    pub fn print_tool_call(&mut self, name: &str, detail: &str) -> io::Result<()> {
+        self.tool_line(Color::Cyan, &format!("⚙ {} {}", name, detail))
+    }
+
+    /// 输出工具结果的摘要，紧跟在对应的调用之后
+    pub fn print_tool_result(&mut self, summary: &str) -> io::Result<()> {
+        self.tool_line(Color::DarkGrey, &format!("  └ {}", summary))
+    }
+
+    fn tool_line(&mut self, color: Color, text: &str) -> io::Result<()> {
+        self.render()?;
+        // 调用前后的回答属于不同段落，避免在 Markdown 中粘连
+        if !self.answer_buffer.is_empty() && !self.answer_buffer.ends_with("\n\n") {
+            let separator = if self.answer_buffer.ends_with('\n') { "\n" } else { "\n\n" };
+            self.answer_buffer.push_str(separator);
+            self.answer_rendered_bytes = self.answer_buffer.len();
+        }
+        let mut stdout = io::stdout();
+        if !self.at_line_start {
+            stdout.queue(Print("\n"))?;
+        }
+        stdout.queue(SetForegroundColor(color))?;
+        stdout.queue(Print(text.trim_end()))?;
+        stdout.queue(ResetColor)?;
+        stdout.queue(Print("\n"))?;
+        self.at_line_start = true;
+        stdout.flush()
+    }
+
     /// 流式结束后调用，只返回 answer 部分的 markdown（不包含 reasoning）
     /// render_markdown: 是否进入 alternate screen 展示可滚动的渲染视图
     pub fn finish(self, render_markdown: bool) -> io::Result<String> {
@@ -96,7 +140,7 @@ impl TextRenderer {
         stdout.flush()?;
 
         if render_markdown {
-            show_markdown_view(&self.answer_buffer, make_answer_skin())?;
+            show_markdown_view(&self.answer_buffer, make_answer_skin(), self.copy_handler)?;
         }
 
         Ok(self.answer_buffer.clone())
@@ -0,0 +1,103 @@
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{self, ClearType},
+    QueueableCommand,
+};
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+};
+
+use crate::edit_line;
+use crate::term::RawModeGuard;
+
+/// 风险等级的显示色调
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskTone {
+    Low,
+    Medium,
+    High,
+}
+
+/// 在命令下方显示风险等级及命中的原因
+pub fn print_risk(tone: RiskTone, label: &str, reasons: &[String]) -> io::Result<()> {
+    let (color, mark) = match tone {
+        RiskTone::Low => (Color::Green, "●"),
+        RiskTone::Medium => (Color::Yellow, "▲"),
+        RiskTone::High => (Color::Red, "✗"),
+    };
+    let mut stdout = io::stdout();
+    stdout
+        .queue(SetForegroundColor(color))?
+        .queue(Print(format!("  {} {}", mark, label)))?;
+    if !reasons.is_empty() {
+        stdout.queue(Print(format!("：{}", reasons.join("；"))))?;
+    }
+    stdout.queue(ResetColor)?.queue(Print("\n"))?;
+    stdout.flush()
+}
+
+/// 要求用户完整输入 expected 才视为确认，其余输入或取消均返回 false
+pub fn confirm_typed(title: &str, expected: &str) -> io::Result<bool> {
+    Ok(edit_line(title, "")?.is_some_and(|input| input == expected))
+}
+
+/// 控制终端；标准输出被 shell 捕获时提示仍要显示给用户
+#[cfg(windows)]
+const TTY_PATH: &str = "CONOUT$";
+#[cfg(not(windows))]
+const TTY_PATH: &str = "/dev/tty";
+
+/// 与 confirm_typed 相同，但提示画在控制终端上而不是标准输出，
+/// 供 `tai go --print` 这类标准输出被 shell 捕获的场景使用；没有控制终端时返回错误
+pub fn confirm_typed_on_tty(title: &str, expected: &str) -> io::Result<bool> {
+    let mut tty = OpenOptions::new().write(true).open(TTY_PATH)?;
+    let result = {
+        let _guard = RawModeGuard::enable()?;
+        read_typed(&mut tty, title)
+    };
+    // 清掉标题行和输入行
+    tty.queue(Print("\r"))?
+        .queue(terminal::Clear(ClearType::CurrentLine))?
+        .queue(cursor::MoveUp(1))?
+        .queue(terminal::Clear(ClearType::CurrentLine))?;
+    tty.flush()?;
+    Ok(result?.is_some_and(|input| input.trim() == expected))
+}
+
+/// 逐键读取一行输入并回显到 tty，Enter 返回输入内容，Esc/Ctrl+C 返回 None
+fn read_typed(tty: &mut impl Write, title: &str) -> io::Result<Option<String>> {
+    tty.queue(SetForegroundColor(Color::Yellow))?
+        .queue(Print(format!("  {}\r\n", title)))?
+        .queue(ResetColor)?;
+    let mut input = String::new();
+    loop {
+        tty.queue(Print("\r"))?
+            .queue(terminal::Clear(ClearType::CurrentLine))?
+            .queue(SetForegroundColor(Color::Cyan))?
+            .queue(Print(format!("  > {}", input)))?
+            .queue(ResetColor)?;
+        tty.flush()?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => return Ok(Some(input)),
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') if ctrl => return Ok(None),
+            KeyCode::Char('u') if ctrl => input.clear(),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) if !ctrl => input.push(c),
+            _ => {}
+        }
+    }
+}
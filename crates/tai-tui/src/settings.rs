@@ -75,7 +75,7 @@ pub fn show_settings(mut items: Vec<SettingItem>) -> io::Result<Option<Vec<Setti
 
 // ── Event loop ────────────────────────────────────────────────────────────────
 
-fn settings_loop(stdout: &mut impl Write, items: &mut Vec<SettingItem>) -> io::Result<bool> {
+fn settings_loop(stdout: &mut impl Write, items: &mut [SettingItem]) -> io::Result<bool> {
     let mut selected = 0usize;
     let label_col = max_label_width(items);
 
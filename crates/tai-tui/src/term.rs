@@ -0,0 +1,51 @@
+use crossterm::terminal;
+use std::io;
+use unicode_width::UnicodeWidthChar;
+
+/// RAII 守卫：创建时进入 raw 模式，析构时恢复终端模式
+pub(crate) struct RawModeGuard;
+
+impl RawModeGuard {
+    pub(crate) fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// 按显示宽度折行
+pub(crate) fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for raw in text.lines() {
+        let mut line = String::new();
+        let mut used = 0;
+        for c in raw.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w > width && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                used = 0;
+            }
+            used += w;
+            line.push(c);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_by_display_width() {
+        assert_eq!(wrap("abcdef", 4), vec!["abcd", "ef"]);
+        assert_eq!(wrap("中文字符", 5), vec!["中文", "字符"]);
+        assert_eq!(wrap("a\n\nb", 4), vec!["a", "", "b"]);
+    }
+}
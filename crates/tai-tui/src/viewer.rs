@@ -1,14 +1,29 @@
-use std::io::{self, Write};
+use std::{
+    borrow::Cow,
+    fmt::{self, Write as _},
+    io::{self, Write},
+};
 use termimad::{
     crossterm::{
-        cursor::{Hide, Show},
+        cursor::{Hide, MoveTo, Show},
         event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
         queue,
-        style::Color,
+        style::{Color, Print, ResetColor, SetForegroundColor},
         terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
     },
-    Area, MadSkin, MadView,
+    Area, CompositeKind, CompoundStyle, FmtComposite, FmtLine, FmtText, ListItemsIndentationMode,
+    MadSkin, Spacing, TextView,
 };
+use unicode_width::UnicodeWidthChar;
+
+/// 复制代码块使用的剪贴板函数，成功时返回复制方式的说明
+pub type CopyHandler = fn(&str) -> io::Result<String>;
+
+const HINT: &str =
+    "j/k 滚动  / 搜索  n/N 下/上一个  [ ] 上/下一个标题  g/G 开头/结尾  y+编号 复制代码块  q 退出";
+
+/// 跳到搜索结果时，结果上方保留的行数
+const SEARCH_CONTEXT: usize = 3;
 
 /// 创建默认的 Markdown 渲染皮肤
 pub fn make_default_skin() -> MadSkin {
@@ -20,15 +35,22 @@ pub fn make_default_skin() -> MadSkin {
     skin
 }
 
-/// 获取视图区域
+/// 获取视图区域，最后一行留给状态栏
 fn view_area() -> Area {
     let mut area = Area::full_screen();
     area.pad_for_max_width(120);
+    area.height = area.height.saturating_sub(1).max(1);
     area
 }
 
-/// 进入 alternate screen，展示可滚动的 MadView，按任意键退出
-pub fn show_markdown_view(markdown: &str, skin: MadSkin) -> io::Result<()> {
+/// 进入 alternate screen，展示可滚动的 Markdown 视图，支持搜索、标题跳转和复制代码块，按 q 退出
+///
+/// copy 为复制代码块使用的剪贴板，为 None 时复制会提示剪贴板不可用
+pub fn show_markdown_view(
+    markdown: &str,
+    skin: MadSkin,
+    copy: Option<CopyHandler>,
+) -> io::Result<()> {
     let mut w = io::stdout();
 
     // raw mode 必须在 EnterAlternateScreen 之前启用，否则 Windows 终端可能无法读取键盘事件
@@ -39,9 +61,9 @@ pub fn show_markdown_view(markdown: &str, skin: MadSkin) -> io::Result<()> {
     // 清空流式输出期间积累的残留事件（例如用户输入命令时按下的回车）
     flush_pending_events();
 
-    let mut view = MadView::from(markdown.to_owned(), view_area(), skin);
+    let mut viewer = Viewer::new(markdown, skin, copy);
 
-    let result = run_view_loop(&mut w, &mut view);
+    let result = run_view_loop(&mut w, &mut viewer);
 
     terminal::disable_raw_mode()?;
     queue!(w, Show, LeaveAlternateScreen)?;
@@ -51,9 +73,9 @@ pub fn show_markdown_view(markdown: &str, skin: MadSkin) -> io::Result<()> {
 }
 
 /// 视图交互循环
-fn run_view_loop(w: &mut impl Write, view: &mut MadView) -> io::Result<()> {
+fn run_view_loop(w: &mut impl Write, viewer: &mut Viewer) -> io::Result<()> {
     loop {
-        view.write_on(w).map_err(|e| io::Error::other(e.to_string()))?;
+        viewer.write_on(w)?;
         w.flush()?;
 
         match event::read() {
@@ -62,21 +84,14 @@ fn run_view_loop(w: &mut impl Write, view: &mut MadView) -> io::Result<()> {
                 if code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
                     break;
                 }
-                match code {
-                    KeyCode::Char('k') => view.try_scroll_lines(-1),
-                    KeyCode::Up => view.try_scroll_lines(-1),
-                    KeyCode::Char('j') => view.try_scroll_lines(1),
-                    KeyCode::Down => view.try_scroll_lines(1),
-                    KeyCode::PageUp => view.try_scroll_pages(-1),
-                    KeyCode::PageDown => view.try_scroll_pages(1),
-                    KeyCode::Char('q') | KeyCode::Esc => break,
-                    _ => {}
+                if !viewer.handle_key(code) {
+                    break;
                 }
             }
             Ok(Event::Key(_)) => {}
             Ok(Event::Resize(..)) => {
                 queue!(w, Clear(ClearType::All))?;
-                view.resize(&view_area());
+                viewer.area = view_area();
             }
             _ => {}
         }
@@ -84,9 +99,590 @@ fn run_view_loop(w: &mut impl Write, view: &mut MadView) -> io::Result<()> {
     Ok(())
 }
 
+/// 状态栏的输入状态
+enum Input {
+    Normal,
+    /// 按 / 后输入搜索内容
+    Search(String),
+    /// 按 y 后输入代码块编号
+    Copy(String),
+}
+
+/// 一行中各片段组（普通行只有一组，表格行每个单元格一组）里被高亮的片段下标
+type LineMarks = Vec<Vec<usize>>;
+
+/// 排版后的总行数、匹配搜索词的行和标题行，用于计算跳转位置
+struct Layout {
+    lines: usize,
+    matches: Vec<usize>,
+    headings: Vec<usize>,
+}
+
+struct Viewer {
+    /// 已插入代码块编号的 Markdown
+    markdown: String,
+    blocks: Vec<String>,
+    area: Area,
+    skin: MadSkin,
+    /// 搜索高亮的样式，不占用皮肤中的任何 Markdown 样式
+    highlight: CompoundStyle,
+    copy: Option<CopyHandler>,
+    scroll: usize,
+    query: String,
+    /// 当前所在的搜索结果（matches 中的下标）
+    current: Option<usize>,
+    input: Input,
+    /// 状态栏消息，按下一个键后清除
+    message: Option<String>,
+}
+
+impl Viewer {
+    fn new(markdown: &str, skin: MadSkin, copy: Option<CopyHandler>) -> Self {
+        let (markdown, blocks) = number_code_blocks(markdown);
+        Self {
+            markdown,
+            blocks,
+            area: view_area(),
+            skin,
+            highlight: CompoundStyle::with_fgbg(Color::Black, Color::Yellow),
+            copy,
+            scroll: 0,
+            query: String::new(),
+            current: None,
+            input: Input::Normal,
+            message: None,
+        }
+    }
+
+    /// 按当前区域排版并拆出搜索词，同时返回每行被高亮的片段
+    fn text(&self) -> (FmtText<'_, '_>, Vec<LineMarks>) {
+        let mut text = self.skin.area_text(&self.markdown, &self.area);
+        let marks = text
+            .lines
+            .iter_mut()
+            .map(|line| {
+                let composites: Vec<&mut FmtComposite> = match line {
+                    FmtLine::Normal(composite) => vec![composite],
+                    FmtLine::TableRow(row) => row.cells.iter_mut().collect(),
+                    _ => Vec::new(),
+                };
+                composites
+                    .into_iter()
+                    .map(|composite| highlight(composite, &self.query))
+                    .collect()
+            })
+            .collect();
+        (text, marks)
+    }
+
+    fn layout(&self) -> Layout {
+        let (text, marks) = self.text();
+        let matches = (0..marks.len())
+            .filter(|&i| marks[i].iter().any(|m| !m.is_empty()))
+            .collect();
+        let is_heading = |line: &FmtLine| {
+            matches!(line, FmtLine::Normal(c) if matches!(c.kind, CompositeKind::Header(_)))
+        };
+        // 折行的标题只取第一行
+        let headings = (0..text.lines.len())
+            .filter(|&i| {
+                is_heading(&text.lines[i]) && (i == 0 || !is_heading(&text.lines[i - 1]))
+            })
+            .collect();
+        Layout {
+            lines: text.lines.len(),
+            matches,
+            headings,
+        }
+    }
+
+    fn write_on(&self, w: &mut impl Write) -> io::Result<()> {
+        let (text, marks) = self.text();
+        let mut view = TextView::from(&self.area, &text);
+        view.scroll = self.scroll;
+        view.write_on(w).map_err(|e| io::Error::other(e.to_string()))?;
+
+        // 含搜索词的行用高亮样式重绘，宽度与 TextView 一致（不覆盖滚动条）
+        let width = self.area.width as usize - usize::from(view.scrollbar().is_some());
+        let visible = text.lines.iter().zip(&marks).enumerate().skip(self.scroll);
+        for (i, (line, marks)) in visible.take(self.area.height as usize) {
+            if marks.iter().all(Vec::is_empty) {
+                continue;
+            }
+            let line = MarkedLine {
+                skin: &self.skin,
+                highlight: &self.highlight,
+                line,
+                marks,
+                width,
+            };
+            let y = self.area.top + (i - self.scroll) as u16;
+            queue!(w, MoveTo(self.area.left, y), Print(line))?;
+        }
+
+        let (status, color) = match &self.input {
+            Input::Search(buffer) => (format!("/{}", buffer), Color::Cyan),
+            Input::Copy(buffer) => (
+                format!("复制代码块（1-{}）: {}", self.blocks.len(), buffer),
+                Color::Cyan,
+            ),
+            Input::Normal => match &self.message {
+                Some(message) => (message.clone(), Color::Yellow),
+                None => (HINT.to_string(), Color::DarkGrey),
+            },
+        };
+        queue!(
+            w,
+            MoveTo(self.area.left, self.area.top + self.area.height),
+            Clear(ClearType::UntilNewLine),
+            SetForegroundColor(color),
+            Print(fit_width(&status, self.area.width as usize)),
+            ResetColor
+        )
+    }
+
+    /// 处理按键，返回 false 表示退出
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        self.message = None;
+        match std::mem::replace(&mut self.input, Input::Normal) {
+            Input::Normal => return self.normal_key(code),
+            Input::Search(buffer) => self.search_key(code, buffer),
+            Input::Copy(buffer) => self.copy_key(code, buffer),
+        }
+        true
+    }
+
+    fn normal_key(&mut self, code: KeyCode) -> bool {
+        let layout = self.layout();
+        let page = self.area.height as isize;
+        match code {
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_by(&layout, -1),
+            KeyCode::Char('j') | KeyCode::Down => self.scroll_by(&layout, 1),
+            KeyCode::PageUp => self.scroll_by(&layout, -page),
+            KeyCode::PageDown => self.scroll_by(&layout, page),
+            KeyCode::Char('g') | KeyCode::Home => self.scroll_to(&layout, 0),
+            KeyCode::Char('G') | KeyCode::End => self.scroll_to(&layout, usize::MAX),
+            KeyCode::Char('n') => self.next_match(&layout, true),
+            KeyCode::Char('N') => self.next_match(&layout, false),
+            KeyCode::Char(']') => self.next_heading(&layout, true),
+            KeyCode::Char('[') => self.next_heading(&layout, false),
+            KeyCode::Char('/') => self.input = Input::Search(String::new()),
+            KeyCode::Char('y') if self.blocks.is_empty() => {
+                self.message = Some("没有代码块".to_string());
+            }
+            KeyCode::Char('y') => self.input = Input::Copy(String::new()),
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            _ => {}
+        }
+        true
+    }
+
+    fn search_key(&mut self, code: KeyCode, mut buffer: String) {
+        match code {
+            KeyCode::Enter => {
+                // 空内容清除高亮
+                self.query = buffer;
+                self.current = None;
+                if !self.query.is_empty() {
+                    let layout = self.layout();
+                    self.next_match(&layout, true);
+                }
+            }
+            KeyCode::Esc => {}
+            KeyCode::Backspace => {
+                buffer.pop();
+                self.input = Input::Search(buffer);
+            }
+            KeyCode::Char(c) => {
+                buffer.push(c);
+                self.input = Input::Search(buffer);
+            }
+            _ => self.input = Input::Search(buffer),
+        }
+    }
+
+    fn copy_key(&mut self, code: KeyCode, mut buffer: String) {
+        match code {
+            KeyCode::Char(c) if c.is_ascii_digit() => {
+                buffer.push(c);
+                let n: usize = buffer.parse().unwrap_or(usize::MAX);
+                // 再输入一位也不会是有效编号时直接复制，否则等待回车
+                if n.saturating_mul(10) > self.blocks.len() {
+                    self.copy_block(n);
+                } else {
+                    self.input = Input::Copy(buffer);
+                }
+            }
+            KeyCode::Enter => self.copy_block(buffer.parse().unwrap_or(0)),
+            KeyCode::Backspace => {
+                buffer.pop();
+                self.input = Input::Copy(buffer);
+            }
+            // 其他键取消
+            _ => {}
+        }
+    }
+
+    fn scroll_to(&mut self, layout: &Layout, line: usize) {
+        let max = layout.lines.saturating_sub(self.area.height as usize);
+        self.scroll = line.min(max);
+    }
+
+    fn scroll_by(&mut self, layout: &Layout, delta: isize) {
+        self.scroll_to(layout, self.scroll.saturating_add_signed(delta));
+    }
+
+    fn next_match(&mut self, layout: &Layout, forward: bool) {
+        if self.query.is_empty() {
+            self.message = Some("按 / 输入要搜索的内容".to_string());
+            return;
+        }
+        let count = layout.matches.len();
+        if count == 0 {
+            self.message = Some(format!("未找到：{}", self.query));
+            return;
+        }
+        // 首次跳转从当前位置开始找，之后在结果间循环
+        let index = match self.current {
+            Some(i) if forward => (i + 1) % count,
+            Some(i) => (i.min(count - 1) + count - 1) % count,
+            None if forward => layout
+                .matches
+                .iter()
+                .position(|&m| m >= self.scroll)
+                .unwrap_or(0),
+            None => layout
+                .matches
+                .iter()
+                .rposition(|&m| m < self.scroll)
+                .unwrap_or(count - 1),
+        };
+        self.current = Some(index);
+        self.scroll_to(layout, layout.matches[index].saturating_sub(SEARCH_CONTEXT));
+        self.message = Some(format!("/{}  {}/{}", self.query, index + 1, count));
+    }
+
+    fn next_heading(&mut self, layout: &Layout, forward: bool) {
+        let target = if forward {
+            layout.headings.iter().find(|&&h| h > self.scroll)
+        } else {
+            layout.headings.iter().rev().find(|&&h| h < self.scroll)
+        };
+        match target {
+            Some(&line) => self.scroll_to(layout, line),
+            None if forward => self.message = Some("后面没有标题了".to_string()),
+            None => self.message = Some("前面没有标题了".to_string()),
+        }
+    }
+
+    fn copy_block(&mut self, n: usize) {
+        let Some(code) = n.checked_sub(1).and_then(|i| self.blocks.get(i)) else {
+            self.message = Some(format!("没有代码块 {}", n));
+            return;
+        };
+        self.message = Some(match self.copy {
+            Some(copy) => match copy(code) {
+                Ok(method) => format!("✓ 已复制代码块 {}（{}）", n, method),
+                Err(e) => format!("✗ 复制失败: {}", e),
+            },
+            None => "✗ 剪贴板不可用".to_string(),
+        });
+    }
+}
+
+/// 在每个代码块前插入编号行，返回处理后的 Markdown 和各代码块的内容
+///
+/// 识别缩进不超过三个空格的 ``` 和 ~~~ 围栏（如列表项中的代码块），
+/// 代码块内容去掉围栏的缩进；未闭合的代码块延续到末尾
+fn number_code_blocks(markdown: &str) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(markdown.len());
+    let mut blocks = Vec::new();
+    // 当前代码块的开始围栏和已读取的内容
+    let mut current: Option<(Fence, Vec<&str>)> = None;
+    for line in markdown.lines() {
+        match (current.as_mut(), fence(line)) {
+            (Some((open, code)), Some(close)) if close.closes(open, line) => {
+                blocks.push(code.join("\n"));
+                current = None;
+            }
+            (Some((open, code)), _) => {
+                let indent = line.len() - line.trim_start_matches(' ').len();
+                code.push(&line[indent.min(open.indent)..]);
+            }
+            (None, Some(open)) => {
+                let _ = writeln!(
+                    out,
+                    "{}*代码块 [{}]*",
+                    &line[..open.indent],
+                    blocks.len() + 1
+                );
+                current = Some((open, Vec::new()));
+            }
+            (None, None) => {}
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    if let Some((_, code)) = current {
+        blocks.push(code.join("\n"));
+    }
+    (out, blocks)
+}
+
+/// 代码块围栏：缩进、围栏字符（` 或 ~）和长度
+#[derive(Debug, Clone, Copy)]
+struct Fence {
+    indent: usize,
+    mark: char,
+    len: usize,
+}
+
+impl Fence {
+    /// 结束围栏与开始围栏字符相同、不短于开始围栏，且后面没有其他内容
+    fn closes(&self, open: &Fence, line: &str) -> bool {
+        self.mark == open.mark
+            && self.len >= open.len
+            && line[self.indent + self.len..].trim().is_empty()
+    }
+}
+
+/// 识别缩进不超过三个空格、至少三个 ` 或 ~ 的围栏行
+fn fence(line: &str) -> Option<Fence> {
+    let rest = line.trim_start_matches(' ');
+    let indent = line.len() - rest.len();
+    let mark = rest.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = rest.len() - rest.trim_start_matches(mark).len();
+    (indent <= 3 && len >= 3).then_some(Fence { indent, mark, len })
+}
+
+/// 把搜索词所在的部分拆成单独的片段，返回需要高亮的片段下标
+fn highlight(composite: &mut FmtComposite<'_>, query: &str) -> Vec<usize> {
+    let text: String = composite.compounds.iter().map(|c| c.src).collect();
+    let ranges = find_all(&text, query);
+    let mut compounds = Vec::with_capacity(composite.compounds.len() + ranges.len() * 2);
+    let mut marked = Vec::new();
+    let mut start = 0;
+    for compound in composite.compounds.drain(..) {
+        let end = start + compound.src.len();
+        let mut cuts = vec![start, end];
+        for &(s, e) in &ranges {
+            cuts.extend([s, e].into_iter().filter(|&p| p > start && p < end));
+        }
+        cuts.sort_unstable();
+        for pair in cuts.windows(2) {
+            if ranges.iter().any(|&(s, e)| s <= pair[0] && pair[1] <= e) {
+                marked.push(compounds.len());
+            }
+            compounds.push(compound.sub(pair[0] - start, pair[1] - start));
+        }
+        start = end;
+    }
+    composite.compounds = compounds;
+    marked
+}
+
+/// 带搜索高亮的一行：高亮片段使用高亮样式，其余部分与 termimad 的渲染保持一致
+struct MarkedLine<'a, 's> {
+    skin: &'a MadSkin,
+    highlight: &'a CompoundStyle,
+    line: &'a FmtLine<'s>,
+    marks: &'a [Vec<usize>],
+    width: usize,
+}
+
+impl fmt::Display for MarkedLine<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let skin = self.skin;
+        match self.line {
+            FmtLine::Normal(fc) => self.write_composite(f, fc, &self.marks[0], Some(self.width)),
+            FmtLine::TableRow(row) => {
+                let border = &skin.table.compound_style;
+                let vertical = skin.table_border_chars.vertical;
+                let inner: usize = row
+                    .cells
+                    .iter()
+                    .map(|c| c.spacing.map_or(c.visible_length, |sp| sp.width) + 1)
+                    .sum();
+                let (lpo, rpo) =
+                    Spacing::optional_completions(skin.table.align, inner + 1, Some(self.width));
+                skin.paragraph.repeat_space(f, lpo)?;
+                for (cell, marks) in row.cells.iter().zip(self.marks) {
+                    write!(f, "{}", border.apply_to(vertical))?;
+                    self.write_composite(f, cell, marks, None)?;
+                }
+                write!(f, "{}", border.apply_to(vertical))?;
+                skin.paragraph.repeat_space(f, rpo)
+            }
+            line => skin.write_fmt_line(f, line, Some(self.width), true),
+        }
+    }
+}
+
+impl MarkedLine<'_, '_> {
+    /// 对应 MadSkin::write_fmt_composite；outer_width 为 None 时（表格单元格）不加边距和右侧补齐
+    fn write_composite(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        fc: &FmtComposite<'_>,
+        marks: &[usize],
+        outer_width: Option<usize>,
+    ) -> fmt::Result {
+        let skin = self.skin;
+        let ls = skin.line_style(fc.kind);
+        let (left_margin, right_margin) = match outer_width {
+            Some(_) => ls.margins_in(outer_width),
+            None => (0, 0),
+        };
+        let (lpi, rpi) = fc.completions();
+        let inner_width = fc.spacing.map_or(fc.visible_length, |sp| sp.width);
+        let (lpo, rpo) = Spacing::optional_completions(
+            ls.align,
+            inner_width + left_margin + right_margin,
+            outer_width,
+        );
+        let space = skin.paragraph.compound_style.apply_to(' ');
+        skin.paragraph.repeat_space(f, lpo + left_margin)?;
+        ls.compound_style.repeat_space(f, lpi)?;
+        match fc.kind {
+            CompositeKind::ListItem(depth) => {
+                for _ in 0..depth {
+                    write!(f, "{}", space)?;
+                }
+                write!(f, "{}{}", skin.bullet, space)?;
+            }
+            CompositeKind::ListItemFollowUp(depth)
+                if skin.list_items_indentation_mode == ListItemsIndentationMode::Block =>
+            {
+                for _ in 0..depth + 2 {
+                    write!(f, "{}", space)?;
+                }
+            }
+            CompositeKind::Quote => write!(f, "{}{}", skin.quote_mark, space)?,
+            _ => {}
+        }
+        for (i, c) in fc.compounds.iter().enumerate() {
+            if marks.contains(&i) {
+                write!(f, "{}", self.highlight.apply_to(c.as_str()))?;
+            } else if let Some(replacement) = skin.special_chars.get(c) {
+                write!(f, "{}", replacement)?;
+            } else {
+                write!(f, "{}", skin.compound_style(ls, c).apply_to(c.as_str()))?;
+            }
+        }
+        ls.compound_style.repeat_space(f, rpi)?;
+        if outer_width.is_some() {
+            skin.paragraph.repeat_space(f, rpo + right_margin)?;
+        }
+        Ok(())
+    }
+}
+
+/// 查找搜索词的所有位置（字节范围）；搜索词不含大写字母时忽略 ASCII 大小写
+fn find_all(text: &str, query: &str) -> Vec<(usize, usize)> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    // 只转换 ASCII 大小写，保证字节位置与原文一致
+    let haystack = if query.chars().any(char::is_uppercase) {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(text.to_ascii_lowercase())
+    };
+    haystack
+        .match_indices(query)
+        .map(|(i, m)| (i, i + m.len()))
+        .collect()
+}
+
+/// 按显示宽度截断，避免状态栏折行
+fn fit_width(text: &str, width: usize) -> String {
+    let mut used = 0;
+    text.chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= width
+        })
+        .collect()
+}
+
 /// 消费掉 stdin 中所有尚未处理的积压事件，避免进入交互循环时被立即触发退出
 fn flush_pending_events() {
     while event::poll(std::time::Duration::from_millis(0)).unwrap_or(false) {
         let _ = event::read();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_code_blocks_and_highlight() {
+        let markdown = "# 标题\n\n```rust\nfn main() {}\n```\n\n文字\n\n```\necho 1\necho 2";
+        let (numbered, blocks) = number_code_blocks(markdown);
+        assert_eq!(blocks, vec!["fn main() {}", "echo 1\necho 2"]);
+        assert!(numbered.contains("*代码块 [1]*\n```rust\n"));
+        assert!(numbered.contains("*代码块 [2]*\n```\n"));
+
+        let markdown = "1. 安装\n\n   ```sh\n   cargo install tai\n     --locked\n   ```\n\n~~~~\n```\nraw\n~~~\n~~~~~\n    ```\nindented code\n";
+        let (numbered, blocks) = number_code_blocks(markdown);
+        assert_eq!(
+            blocks,
+            vec!["cargo install tai\n  --locked", "```\nraw\n~~~"]
+        );
+        assert!(numbered.contains("   *代码块 [1]*\n   ```sh\n"));
+        assert!(numbered.contains("*代码块 [2]*\n~~~~\n"));
+        assert!(!numbered.contains("[3]"));
+
+        let skin = MadSkin::default();
+        let text = FmtText::from(&skin, "Run **cargo Build** now", None);
+        let FmtLine::Normal(mut composite) = text.lines.into_iter().next().unwrap() else {
+            panic!("应为普通行");
+        };
+        assert_eq!(highlight(&mut composite, "o b"), vec![2]);
+        let pieces: Vec<(&str, bool)> = composite
+            .compounds
+            .iter()
+            .map(|c| (c.src, c.bold))
+            .collect();
+        assert_eq!(
+            pieces,
+            vec![
+                ("Run ", false),
+                ("carg", true),
+                ("o B", true),
+                ("uild", true),
+                (" now", false)
+            ]
+        );
+        assert!(highlight(&mut composite, "missing").is_empty());
+
+        // 原文的删除线不受搜索影响
+        let text = FmtText::from(&skin, "~~old~~ new", None);
+        let FmtLine::Normal(mut composite) = text.lines.into_iter().next().unwrap() else {
+            panic!("应为普通行");
+        };
+        assert_eq!(highlight(&mut composite, "new"), vec![2]);
+        assert!(composite.compounds[0].strikeout);
+        assert!(!composite.compounds[2].strikeout);
+    }
+
+    #[test]
+    fn test_marked_line_matches_termimad_rendering() {
+        let skin = make_default_skin();
+        let markdown = "# 标题\n\n- 列表 **粗体**\n  续行\n\n> 引用\n\n|a|b|\n|-|-|\n|`x`|~~y~~|\n";
+        let text = FmtText::from(&skin, markdown, Some(40));
+        for line in &text.lines {
+            let marks = vec![Vec::new(); 2];
+            let marked = MarkedLine {
+                skin: &skin,
+                highlight: &CompoundStyle::default(),
+                line,
+                marks: &marks,
+                width: 40,
+            };
+            let expected = termimad::DisplayableLine::new(&skin, line, Some(40));
+            assert_eq!(marked.to_string(), expected.to_string());
+        }
+    }
+}